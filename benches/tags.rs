@@ -0,0 +1,42 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+use git2::{Repository, Signature};
+use std::path::Path;
+use std::process::Command;
+use tempfile::tempdir;
+
+const BIN: &str = env!("CARGO_BIN_EXE_ccclog");
+const TAG_COUNT: u64 = 2000;
+
+// Every commit has a tag like 0.0.1, 0.0.2, ...
+fn init_repo(path: &Path) -> Result<(), git2::Error> {
+    let repo = Repository::init(path)?;
+    let sig = Signature::now("Test User", "test-user@test.com")?;
+    let tree_id = repo.index()?.write_tree()?;
+    let tree = repo.find_tree(tree_id)?;
+
+    let mut parent = None;
+    for n in 1..=TAG_COUNT {
+        let message = format!("feat: add {}", n);
+        let parents = parent.iter().collect::<Vec<_>>();
+        let id = repo.commit(Some("HEAD"), &sig, &sig, &message, &tree, &parents)?;
+        let commit = repo.find_commit(id)?;
+        repo.tag_lightweight(&format!("0.0.{}", n), commit.as_object(), false)?;
+        parent = Some(commit);
+    }
+    Ok(())
+}
+
+fn many_tags(c: &mut Criterion) {
+    let dir = tempdir().unwrap();
+    init_repo(dir.path()).unwrap();
+
+    c.bench_function("many_tags", |b| {
+        b.iter(|| {
+            let output = Command::new(BIN).arg(dir.path()).output().unwrap();
+            assert!(output.status.success());
+        })
+    });
+}
+
+criterion_group!(benches, many_tags);
+criterion_main!(benches);
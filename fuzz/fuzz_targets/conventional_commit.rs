@@ -0,0 +1,19 @@
+#![no_main]
+#[macro_use]
+extern crate strum_macros;
+
+use libfuzzer_sys::fuzz_target;
+use std::str::FromStr;
+
+// ccclog is a binary crate, so the parser module is shared by the path
+#[allow(dead_code)]
+#[path = "../../src/git/conventional_commit.rs"]
+mod conventional_commit;
+
+use conventional_commit::ConventionalCommits;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(s) = std::str::from_utf8(data) {
+        let _ = ConventionalCommits::from_str(s);
+    }
+});
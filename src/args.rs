@@ -1,40 +1,459 @@
-use crate::git::CommitType;
-use anyhow::Result;
+use crate::changelog::{
+    AuthorLinkStyle, Chronology, CustomOrder, Format, GroupKey, Level, RevertHandling,
+    ScopelessPosition,
+};
+use crate::git::{CommitType, Forge, Identity, MultiPrefix, UrlKind};
+use anyhow::{anyhow, bail, Result};
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveTime, Utc};
+use glob::Pattern;
 use regex::Regex;
+use std::path::PathBuf;
+use std::str::FromStr;
 use structopt::{clap, StructOpt};
 
 #[derive(StructOpt, Debug)]
-#[structopt(setting(clap::AppSettings::ColoredHelp))]
+#[structopt(
+    setting(clap::AppSettings::ColoredHelp),
+    usage = "ccclog [FLAGS] [OPTIONS] [REPO_PATH] [REVISION_SPEC] [-- <REPOS>...]"
+)]
 pub struct Args {
-    #[structopt(short, long, help = "Make a link to the author using git config.email")]
+    #[structopt(
+        short,
+        long,
+        help = "Make a link to the author using git config.email. Same as `--author-link-style email`"
+    )]
     pub enable_email_link: bool,
-    #[structopt(short, long, help = "Reverse commit display order")]
+    #[structopt(
+        long,
+        conflicts_with = "enable-email-link",
+        help = "How to link the author. ex) none|email|profile"
+    )]
+    pub author_link_style: Option<AuthorLinkStyle>,
+    #[structopt(
+        long,
+        help = "TOML file mapping the emails to the GitHub usernames for `--author-link-style profile`. ex) \"user@example.com\" = \"octocat\""
+    )]
+    pub author_map: Option<PathBuf>,
+    #[structopt(
+        short,
+        long,
+        help = "Reverse commit display order. Same as `--commit-chronology oldest-first`"
+    )]
     pub reverse: bool,
+    #[structopt(
+        long,
+        conflicts_with = "reverse",
+        help = "Order of the commits in every section. ex) newest-first|oldest-first"
+    )]
+    pub commit_chronology: Option<Chronology>,
+    #[structopt(
+        long,
+        help = "Sort the commits in a section by the author name, then the date"
+    )]
+    pub stable_author_sort: bool,
+    #[structopt(
+        long,
+        help = "Add a section collecting the `BREAKING CHANGE:` footers with their URLs linked"
+    )]
+    pub show_breaking_changes: bool,
+    #[structopt(long, help = "Add a section collecting the `Deprecated:` footers")]
+    pub show_deprecations: bool,
+    #[structopt(long, help = "Show merge commits with their parent commit hashes")]
+    pub show_merge_parents: bool,
+    #[structopt(
+        long,
+        help = "Show the non-version tags on the release commit. ex) 1.2.0 (stable)"
+    )]
+    pub show_tag_aliases: bool,
+    #[structopt(
+        long,
+        help = "Show the annotated tag message under the release heading"
+    )]
+    pub show_tag_messages: bool,
+    #[structopt(
+        long,
+        help = "Show the signer of the signed tag as `(signed by <signer>)` next to the release heading. The signer is the tagger, and the signature isn't verified"
+    )]
+    pub show_tag_signer: bool,
+    #[structopt(
+        long,
+        help = "Verify the signatures of the release commits with `git verify-commit`, and mark the good ones as `(verified)`. Requires the `git` executable in the PATH"
+    )]
+    pub verify_release_commit_only: bool,
+    #[structopt(long, help = "Drop the oldest release when it spans the whole history")]
+    pub drop_unbounded_first: bool,
+    #[structopt(
+        long,
+        help = "Drop the commits older than the first tag. The tagged commit is kept"
+    )]
+    pub drop_pre_first_tag: bool,
+    #[structopt(
+        long,
+        help = "Fail with the hashes of the commits not following Conventional Commits instead of listing them in Others"
+    )]
+    pub strict: bool,
+    #[structopt(long, help = "Add a link to the files changed by each commit")]
+    pub show_files_link: bool,
+    #[structopt(long, help = "Group the commits by the author instead of the release")]
+    pub by_author: bool,
+    #[structopt(
+        long,
+        help = "Drop the commits with the same message and author in a section. ex) rebased commits"
+    )]
+    pub dedupe: bool,
+    #[structopt(
+        long,
+        help = "Ignore case and surrounding whitespace when comparing messages for --dedupe"
+    )]
+    pub dedupe_normalize: bool,
+    #[structopt(
+        long,
+        help = "Print the stable tab-separated lines: release, type, short hash, author, message"
+    )]
+    pub porcelain: bool,
+    #[structopt(
+        long,
+        help = "Show the inserted and deleted lines of each commit. It's slow on large ranges"
+    )]
+    pub show_stats: bool,
+    #[structopt(long, help = "Use the full commit hash instead of the short hash")]
+    pub full_hash: bool,
+    #[structopt(
+        long,
+        help = "Show the scope before the message. ex) **parser:** add x"
+    )]
+    pub show_scope: bool,
+    #[structopt(
+        long,
+        help = "Show the commits without a subject as `(no subject)` instead of dropping them"
+    )]
+    pub keep_empty_subjects: bool,
+    #[structopt(
+        long,
+        default_value = "alpha",
+        help = "Order of the custom type sections. ex) alpha|first-seen"
+    )]
+    pub custom_type_order: CustomOrder,
+    #[structopt(long, help = "Hide the scope same as the commit type. ex) feat(feat)")]
+    pub hide_redundant_scope: bool,
+    #[structopt(
+        long,
+        help = "Link the references in the messages. ex) GitLab #123, !45 and %milestone"
+    )]
+    pub link_references: bool,
+    #[structopt(
+        long,
+        help = "Add a `**Full Changelog**` link comparing the whole range at the bottom"
+    )]
+    pub full_changelog_footer: bool,
+    #[structopt(
+        long,
+        help = "Link the issue references in the messages and the closing footers. ex) #123, org/repo#123, GH-123"
+    )]
+    pub link_issues: bool,
+    #[structopt(long, help = "Print only the compare URL of the range")]
+    pub compare_url_only: bool,
+    #[structopt(
+        long,
+        help = "Print only the detected versions from the oldest. With `--format json`, their prefixes and dates too"
+    )]
+    pub list_versions: bool,
+    #[structopt(
+        long,
+        default_value = "keep",
+        help = "How to show the reverted commits. ex) keep|drop|annotate"
+    )]
+    pub revert_handling: RevertHandling,
+    #[structopt(
+        long,
+        default_value = "markdown",
+        help = "Output format. ex) markdown|json|asciidoc|discord|plain|ndjson-releases"
+    )]
+    pub format: Format,
+    #[structopt(
+        long,
+        help = "Render the releases with a Tera template file instead of the built-in format"
+    )]
+    pub template: Option<PathBuf>,
+    #[structopt(
+        long,
+        help = "Shell command formatting the json output read from its stdin. ex) jq -r '.[].version'"
+    )]
+    pub formatter_cmd: Option<String>,
+    #[structopt(
+        long,
+        default_value = "patch",
+        help = "Drop the releases changing only the lower level. ex) patch|minor|major"
+    )]
+    pub min_level: Level,
+    #[structopt(
+        long,
+        default_value = "type",
+        help = "Key of the sections in a release. ex) type|type-scope"
+    )]
+    pub group_by: GroupKey,
+    #[structopt(
+        long,
+        default_value = "first",
+        help = "Position of the section without a scope with --group-by type-scope. ex) first|last"
+    )]
+    pub scopeless_position: ScopelessPosition,
     #[structopt(
         short = "i",
         long,
-        default_value = "2",
-        help = "Change markdown root subject indent"
+        help = "Change markdown root subject indent [default: 2]"
     )]
-    pub root_indent_level: u8,
+    pub root_indent_level: Option<u8>,
     #[structopt(
         short = "s",
         long,
         help = "Ignore summary use regex. Syntax: https://docs.rs/regex/1.3.7/regex/#syntax"
     )]
     pub ignore_summary: Option<Regex>,
+    #[structopt(
+        long,
+        number_of_values = 1,
+        help = "Keep only the commits matching any of the regexes. It can be specified multiple times"
+    )]
+    pub only_summary: Vec<Regex>,
     #[structopt(
         short = "t",
         long,
         help = "Ignore commit type. ex) feat|fix|build|doc|chore|ci|style|refactor|perf|test"
     )]
     pub ignore_types: Option<Vec<CommitType>>,
+    #[structopt(
+        long,
+        help = "Include only the commit types. The --ignore-types are removed from them. ex) feat fix"
+    )]
+    pub include_types: Option<Vec<CommitType>>,
+    #[structopt(
+        long,
+        help = "Keep the breaking commits regardless of the type filters"
+    )]
+    pub always_include_breaking: bool,
+    #[structopt(
+        long,
+        help = "Order of the commit type sections. The types not listed follow in the default order. ex) fix feat"
+    )]
+    pub type_order: Option<Vec<CommitType>>,
+    #[structopt(
+        long = "type-title",
+        number_of_values = 1,
+        parse(try_from_str = parse_type_title),
+        help = "Section title of the commit type. It can be specified multiple times. ex) feat=Features"
+    )]
+    pub type_titles: Vec<(CommitType, String)>,
+    #[structopt(
+        long = "type-alias",
+        number_of_values = 1,
+        parse(try_from_str = parse_type_alias),
+        help = "Commit type parsed as another type. It can be specified multiple times. ex) wip=chore"
+    )]
+    pub type_aliases: Vec<(String, CommitType)>,
+    #[structopt(
+        long,
+        help = "Message rendered under a release heading when all its commits are filtered out"
+    )]
+    pub empty_message: Option<String>,
+    #[structopt(
+        long,
+        help = "Link style of the remote. Detected from the remote host by default. ex) github|gitlab|bitbucket|gitea"
+    )]
+    pub forge: Option<Forge>,
+    #[structopt(
+        long = "url-template",
+        number_of_values = 1,
+        parse(try_from_str = parse_url_template),
+        help = "Link template overriding the forge. It can be specified multiple times. The kinds are commit, compare and files. ex) commit={base}/commit/{sha}, compare={base}/compare/{from}...{to}, files={base}/commit/{sha}.diff"
+    )]
+    pub url_templates: Vec<(UrlKind, String)>,
+    #[structopt(
+        long,
+        help = "Same as `--url-template commit=<template>`. ex) {base}/c/{sha}"
+    )]
+    commit_url_template: Option<String>,
+    #[structopt(
+        long,
+        help = "Same as `--url-template compare=<template>`. ex) {base}/diff/{from}/{to}"
+    )]
+    compare_url_template: Option<String>,
+    #[structopt(
+        long,
+        help = "Fail with the diff if the file differs from the generated changelog"
+    )]
+    pub check: Option<PathBuf>,
+    #[structopt(
+        long,
+        conflicts_with = "check",
+        help = "Insert the generated releases above the first release of the file instead of printing them"
+    )]
+    pub prepend: Option<PathBuf>,
+    #[structopt(
+        long,
+        help = "Write the JSON with the SHA-256 of the output, the range and the ccclog version"
+    )]
+    pub manifest: Option<PathBuf>,
+    #[structopt(
+        long,
+        help = "Map the author names and emails with the `.mailmap` of the repository"
+    )]
+    pub mailmap: bool,
+    #[structopt(
+        long,
+        default_value = "Unknown",
+        help = "Name rendered for the authors without a name"
+    )]
+    pub unknown_author: String,
     #[structopt(
         short = "p",
         long,
         help = "If there are multiple tag formats, specify the target prefix"
     )]
     pub tag_prefix: Option<String>,
+    #[structopt(
+        long,
+        help = "Regex to derive versions from tags. Requires a `version` capture group and accepts an optional `prefix` capture group. ex) ^(?P<prefix>release-)(?P<version>.+)$"
+    )]
+    pub tag_pattern: Option<Regex>,
+    #[structopt(
+        long,
+        default_value = "error",
+        help = "How to choose tags when there are multiple tag formats. ex) error|prefer-empty|prefer-v|first"
+    )]
+    pub on_multiple_prefixes: MultiPrefix,
+    #[structopt(
+        long,
+        default_value = "author",
+        help = "Whose name is shown for each commit. ex) author|committer"
+    )]
+    pub identity: Identity,
+    #[structopt(
+        long,
+        help = "Only the commits changing the files matched by the glob. The first parent is compared for merge commits. ex) packages/foo/**"
+    )]
+    pub path_filter: Option<Pattern>,
+    #[structopt(
+        long,
+        help = "Skip the pre-releases of the latest version when detecting the previous tag"
+    )]
+    pub skip_prerelease_previous: bool,
+    #[structopt(
+        long,
+        help = "Fold the pre-release tags into the next release. ex) 1.2.0-rc.1 is included in 1.2.0"
+    )]
+    pub exclude_prereleases: bool,
+    #[structopt(
+        long,
+        help = "Number of the latest releases detected without the revision spec [default: 1]"
+    )]
+    pub limit: Option<usize>,
+    #[structopt(
+        long,
+        conflicts_with_all = &["limit", "REVISION_SPEC"],
+        help = "All the releases from the first commit to HEAD"
+    )]
+    pub all: bool,
+    #[structopt(
+        long,
+        help = "Regex to parse the commit header. Requires `type` and `description` capture groups and accepts optional `scope` and `breaking` capture groups. ex) ^\\[(?P<type>\\w+)\\] (?P<description>.+)$"
+    )]
+    pub header_pattern: Option<Regex>,
+    #[structopt(
+        long,
+        help = "Strip the leading emojis and shortcodes before parsing the commit header. ex) ✨ feat: add x, :sparkles: feat: add x"
+    )]
+    pub strip_leading_emoji: bool,
+    #[structopt(
+        long,
+        help = "Pin the current time for reproducible output. ex) 2020-01-01T00:00:00Z"
+    )]
+    pub now: Option<DateTime<Utc>>,
+    #[structopt(
+        long,
+        parse(try_from_str = parse_since),
+        conflicts_with = "limit",
+        help = "Only the commits at or after the date in the whole history. ex) 2020-01-01, 2020-01-01T09:00:00+09:00"
+    )]
+    pub since: Option<DateTime<Utc>>,
+    #[structopt(
+        long,
+        parse(try_from_str = parse_until),
+        conflicts_with = "limit",
+        help = "Only the commits at or before the date in the whole history. The date is the end of the day. ex) 2020-12-31"
+    )]
+    pub until: Option<DateTime<Utc>>,
+    #[structopt(
+        long,
+        parse(try_from_str = parse_version),
+        help = "Render the untagged HEAD as this version dated today. ex) 1.0.0"
+    )]
+    pub as_version: Option<String>,
+    #[structopt(
+        long,
+        parse(try_from_str = parse_timezone),
+        help = "Offset of the release dates. The offset of each tagged commit is used by default. ex) +09:00"
+    )]
+    pub timezone: Option<FixedOffset>,
+    #[structopt(
+        long,
+        parse(try_from_str = parse_date_format),
+        help = "strftime format of the release dates [default: %Y-%m-%d]. ex) %d/%m/%Y"
+    )]
+    pub date_format: Option<String>,
+    #[structopt(long, help = "Hide the dates of the release headings")]
+    pub hide_date: bool,
+    #[structopt(
+        long,
+        help = "Show the versions without the tag prefix in the headings. The links keep the tag names"
+    )]
+    pub strip_tag_prefix_in_heading: bool,
+    #[structopt(
+        long,
+        help = "Indent the commit body without the footers under each commit"
+    )]
+    pub include_body: bool,
+    #[structopt(
+        long,
+        help = "Section title of the non-conventional commits instead of Others. ex) Miscellaneous"
+    )]
+    pub others_title: Option<String>,
+    #[structopt(
+        long,
+        conflicts_with = "others-title",
+        help = "Hide the section of the non-conventional commits"
+    )]
+    pub hide_others: bool,
+    #[structopt(long, help = "Line between the releases in markdown. ex) ---")]
+    pub release_separator: Option<String>,
+    #[structopt(
+        long,
+        help = "Show the types hidden by default: chore, ci, style, build and test. Same as including them"
+    )]
+    pub show_all: bool,
+    #[structopt(
+        long,
+        help = "Show the number of the distinct authors by the email in the release headings"
+    )]
+    pub show_contributor_count: bool,
+    #[structopt(
+        long,
+        help = "Only the commits of the type per release without the type headings. ex) security"
+    )]
+    pub type_only: Option<CommitType>,
+    #[structopt(
+        long,
+        help = "Prepend the YAML front-matter with the title and the date of the latest release"
+    )]
+    pub front_matter: bool,
+    #[structopt(
+        long = "front-matter-field",
+        number_of_values = 1,
+        parse(try_from_str = parse_front_matter_field),
+        help = "Extra field of the front-matter. It can be specified multiple times. ex) layout=post"
+    )]
+    pub front_matter_fields: Vec<(String, String)>,
     #[structopt(
         name = "REPO_PATH",
         default_value = ".",
@@ -46,18 +465,113 @@ pub struct Args {
         help = "Revision spec. Ref to https://git-scm.com/book/en/v2/Git-Tools-Revision-Selection"
     )]
     revspec: Option<String>,
+    #[structopt(
+        name = "REPOS",
+        last = true,
+        help = "Additional repositories after `--`, combined into the changelog with a heading per repository"
+    )]
+    pub repos: Vec<String>,
 }
 
 impl Args {
     pub fn new(args: &[String]) -> Result<Args> {
         let app = Args::clap();
         let clap = app.get_matches_from_safe(args)?;
-        Ok(Args::from_clap(&clap))
+        let args = Args::from_clap(&clap);
+        if let (Some(since), Some(until)) = (args.since, args.until) {
+            if since > until {
+                bail!("--since must be before --until")
+            }
+        }
+        Ok(args)
     }
 
     pub fn revspec(&self) -> Option<&str> {
         self.revspec.as_deref()
     }
+
+    // The named templates are applied after `--url-template`
+    pub fn url_templates(&self) -> Vec<(UrlKind, String)> {
+        let named = vec![
+            (UrlKind::Commit, self.commit_url_template.as_ref()),
+            (UrlKind::Compare, self.compare_url_template.as_ref()),
+        ];
+        self.url_templates
+            .iter()
+            .cloned()
+            .chain(
+                named
+                    .into_iter()
+                    .filter_map(|(kind, t)| t.map(|t| (kind, t.clone()))),
+            )
+            .collect()
+    }
+}
+
+fn parse_type_title(s: &str) -> Result<(CommitType, String)> {
+    let (_type, title) = s
+        .split_once('=')
+        .ok_or_else(|| anyhow!("Invalid type title. ex) feat=Features"))?;
+    Ok((CommitType::from_str(_type)?, title.to_string()))
+}
+
+fn parse_type_alias(s: &str) -> Result<(String, CommitType)> {
+    let (alias, _type) = s
+        .split_once('=')
+        .ok_or_else(|| anyhow!("Invalid type alias. ex) wip=chore"))?;
+    Ok((alias.to_lowercase(), CommitType::from_str(_type)?))
+}
+
+fn parse_front_matter_field(s: &str) -> Result<(String, String)> {
+    let (key, value) = s
+        .split_once('=')
+        .ok_or_else(|| anyhow!("Invalid front-matter field. ex) layout=post"))?;
+    Ok((key.to_string(), value.to_string()))
+}
+
+fn parse_url_template(s: &str) -> Result<(UrlKind, String)> {
+    let (kind, template) = s
+        .split_once('=')
+        .ok_or_else(|| anyhow!("Invalid url template. ex) commit={base}/commit/{sha}"))?;
+    Ok((UrlKind::from_str(kind)?, template.to_string()))
+}
+
+// Fails on the unknown specifiers instead of rendering them as is
+fn parse_date_format(s: &str) -> Result<String> {
+    if StrftimeItems::new(s).any(|item| item == Item::Error) {
+        bail!("Invalid date format. ex) %Y-%m-%d")
+    }
+    Ok(s.to_string())
+}
+
+fn parse_version(s: &str) -> Result<String> {
+    semver::Version::parse(s).map_err(|_| anyhow!("Invalid semver version. ex) 1.0.0"))?;
+    Ok(s.to_string())
+}
+
+// The date is in UTC. ex) 2020-01-01, 2020-01-01T09:00:00+09:00
+fn parse_date_bound(s: &str, time: NaiveTime) -> Result<DateTime<Utc>> {
+    if let Ok(date) = NaiveDate::parse_from_str(s, "%Y-%m-%d") {
+        return Ok(DateTime::from_utc(date.and_time(time), Utc));
+    }
+    DateTime::parse_from_rfc3339(s)
+        .map(|d| d.with_timezone(&Utc))
+        .map_err(|_| anyhow!("Invalid date. ex) 2020-01-01, 2020-01-01T09:00:00+09:00"))
+}
+
+fn parse_since(s: &str) -> Result<DateTime<Utc>> {
+    parse_date_bound(s, NaiveTime::from_hms(0, 0, 0))
+}
+
+fn parse_until(s: &str) -> Result<DateTime<Utc>> {
+    parse_date_bound(s, NaiveTime::from_hms(23, 59, 59))
+}
+
+// ex) +09:00, -0500
+fn parse_timezone(s: &str) -> Result<FixedOffset> {
+    let datetime = DateTime::parse_from_str(&format!("2000-01-01 00:00:00 {}", s), "%F %T %z")
+        .map_err(|_| anyhow!("Invalid timezone. ex) +09:00"))?;
+    Ok(*datetime.offset())
 }
 
 #[cfg(test)]
@@ -84,6 +598,115 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn type_title_ok() -> Result<()> {
+        let args = to_string(vec![
+            BIN,
+            "--type-title",
+            "feat=Features",
+            "--type-title=fix=Bug Fixes",
+        ]);
+        let args = Args::new(&args)?;
+        let e = vec![
+            (CommitType::Feat, String::from("Features")),
+            (CommitType::Fix, String::from("Bug Fixes")),
+        ];
+        assert_eq!(args.type_titles, e);
+
+        let args = to_string(vec![BIN, "--type-title", "feat"]);
+        assert!(Args::new(&args).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn type_alias_ok() -> Result<()> {
+        let args = to_string(vec![
+            BIN,
+            "--type-alias",
+            "WIP=chore",
+            "--type-alias=dep=Deps",
+        ]);
+        let args = Args::new(&args)?;
+        let e = vec![
+            (String::from("wip"), CommitType::Chore),
+            (
+                String::from("dep"),
+                CommitType::Custom(String::from("deps")),
+            ),
+        ];
+        assert_eq!(args.type_aliases, e);
+
+        let args = to_string(vec![BIN, "--type-alias", "wip"]);
+        assert!(Args::new(&args).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn url_template_ok() -> Result<()> {
+        let args = to_string(vec![
+            BIN,
+            "--url-template",
+            "compare={base}/compare/{from}..{to}",
+        ]);
+        let args = Args::new(&args)?;
+        let e = vec![(
+            UrlKind::Compare,
+            String::from("{base}/compare/{from}..{to}"),
+        )];
+        assert_eq!(args.url_templates, e);
+
+        let args = to_string(vec![BIN, "--url-template", "files={base}/c/{sha}/files"]);
+        let args = Args::new(&args)?;
+        let e = vec![(UrlKind::Files, String::from("{base}/c/{sha}/files"))];
+        assert_eq!(args.url_templates, e);
+
+        let args = to_string(vec![BIN, "--url-template", "tree={base}/tree/{sha}"]);
+        assert!(Args::new(&args).is_err());
+
+        let args = to_string(vec![
+            BIN,
+            "--compare-url-template",
+            "{base}/diff/{from}/{to}",
+            "--url-template",
+            "compare={base}/compare/{from}..{to}",
+            "--commit-url-template",
+            "{base}/c/{sha}",
+        ]);
+        let args = Args::new(&args)?;
+        let e = vec![
+            (
+                UrlKind::Compare,
+                String::from("{base}/compare/{from}..{to}"),
+            ),
+            (UrlKind::Commit, String::from("{base}/c/{sha}")),
+            (UrlKind::Compare, String::from("{base}/diff/{from}/{to}")),
+        ];
+        assert_eq!(args.url_templates(), e);
+        Ok(())
+    }
+
+    #[test]
+    fn timezone_ok() -> Result<()> {
+        let args = to_string(vec![BIN, "--timezone", "+09:00"]);
+        let args = Args::new(&args)?;
+        assert_eq!(args.timezone, Some(FixedOffset::east(9 * 3600)));
+
+        let args = to_string(vec![BIN, "--timezone", "JST"]);
+        assert!(Args::new(&args).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn date_format_ok() -> Result<()> {
+        let args = to_string(vec![BIN, "--date-format", "%d/%m/%Y"]);
+        let args = Args::new(&args)?;
+        assert_eq!(args.date_format, Some(String::from("%d/%m/%Y")));
+
+        let args = to_string(vec![BIN, "--date-format", "%Y-%m-%Q"]);
+        assert!(Args::new(&args).is_err());
+        Ok(())
+    }
+
     #[test]
     fn args_err() -> Result<()> {
         let args = to_string(vec![BIN, "-h"]);
@@ -1,27 +1,343 @@
 use anyhow::*;
+use chrono::FixedOffset;
 use itertools::Itertools;
+use lazy_static::*;
 
-use crate::git::{Author, Commit, CommitType, Commits, GithubUrl, ReleaseRange};
+use crate::error::ExitError;
+use crate::git::{
+    Author, Commit, CommitType, Commits, GithubUrl, NamableObj, ReleaseRange, SectionKey,
+};
+use git2::Oid;
 use regex::Regex;
-use std::collections::BTreeMap;
+use serde::ser::{SerializeMap, Serializer};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::iter;
+
+#[derive(Debug, Clone, PartialEq, EnumString)]
+#[strum(serialize_all = "kebab-case")]
+pub enum GroupKey {
+    Type,
+    // ex) feat(api) and feat(cli) are separate sections
+    TypeScope,
+}
+
+#[derive(Debug, Clone, PartialEq, PartialOrd, EnumString)]
+#[strum(serialize_all = "kebab-case")]
+pub enum Level {
+    Patch,
+    Minor,
+    Major,
+}
+
+#[derive(Debug, Clone, PartialEq, EnumString)]
+#[strum(serialize_all = "kebab-case")]
+pub enum CustomOrder {
+    Alpha,
+    FirstSeen,
+}
+
+// Where the section without a scope is in the sections of the same type
+#[derive(Debug, Clone, PartialEq, EnumString)]
+#[strum(serialize_all = "kebab-case")]
+pub enum ScopelessPosition {
+    First,
+    Last,
+}
+
+// Order of the commits in every section
+#[derive(Debug, Clone, PartialEq, EnumString)]
+#[strum(serialize_all = "kebab-case")]
+pub enum Chronology {
+    NewestFirst,
+    OldestFirst,
+}
+
+// How the author is linked
+#[derive(Debug, Clone, PartialEq, EnumString)]
+#[strum(serialize_all = "kebab-case")]
+pub enum AuthorLinkStyle {
+    None,
+    // ex) [Name](mailto:name@example.com)
+    Email,
+    // The GitHub profile of the mapped or the noreply email. ex) [@user](https://github.com/user)
+    Profile,
+}
+
+#[derive(Debug, PartialEq, EnumString)]
+#[strum(serialize_all = "kebab-case")]
+pub enum Format {
+    Markdown,
+    Json,
+    Asciidoc,
+    Discord,
+    Plain,
+    // One release object per line
+    NdjsonReleases,
+}
+
+// The rendered releases for the json format and the templates
+#[derive(Debug, Serialize)]
+struct ReleaseData {
+    version: String,
+    date: Option<String>,
+    compare_url: Option<String>,
+    // Commit type and scope => commits in the rendering order. ex) feat(api)
+    #[serde(serialize_with = "ordered_map")]
+    commits: Vec<(String, Vec<CommitData>)>,
+    sections: Vec<SectionData>,
+}
+
+// A section with the title of the markdown heading
+#[derive(Debug, Serialize)]
+struct SectionData {
+    #[serde(rename = "type")]
+    _type: String,
+    scope: Option<String>,
+    title: String,
+    commits: Vec<CommitData>,
+}
+
+#[derive(Debug, Serialize)]
+struct CommitData {
+    hash: String,
+    short_hash: String,
+    message: String,
+    // The rendered name. ex) Unknown for the authors without a name
+    author: String,
+    author_name: Option<String>,
+    author_email: Option<String>,
+    breaking: bool,
+    footers: Vec<FooterData>,
+}
+
+#[derive(Debug, Serialize)]
+struct FooterData {
+    token: String,
+    value: String,
+}
+
+// Serialized as a map keeping the order of the entries
+fn ordered_map<S>(entries: &[(String, Vec<CommitData>)], serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    let mut map = serializer.serialize_map(Some(entries.len()))?;
+    for (key, value) in entries {
+        map.serialize_entry(key, value)?;
+    }
+    map.end()
+}
+
+impl CommitData {
+    fn new(commit: &Commit, author: &str) -> Self {
+        CommitData {
+            hash: commit.hash(),
+            short_hash: commit.short_hash(),
+            message: commit.message(),
+            author: String::from(author),
+            author_name: commit.author().name().map(String::from),
+            author_email: commit.author().email().map(String::from),
+            breaking: commit.is_breaking(),
+            footers: commit
+                .footers()
+                .iter()
+                .map(|f| FooterData {
+                    token: f.token.clone(),
+                    value: f.value.clone(),
+                })
+                .collect(),
+        }
+    }
+}
+
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+// Rendered for the commits without a subject
+const EMPTY_SUBJECT: &str = "(no subject)";
+
+// Filtered sections of a release in the rendering order
+type Sections<'a> = Vec<(SectionKey, Vec<&'a Commit>)>;
+
+// A release filtered and ordered once, rendered by all the formats
+struct Release<'a> {
+    range: ReleaseRange,
+    // Collected from the sections, rendered before them
+    notes: Vec<Note<'a>>,
+    sections: Sections<'a>,
+}
+
+impl<'a> Release<'a> {
+    fn is_empty(&self) -> bool {
+        self.notes.is_empty() && self.sections.is_empty()
+    }
+}
+
+// The footers of all the types. ex) Deprecated: old api
+struct Note<'a> {
+    title: &'static str,
+    commits: Vec<&'a Commit>,
+    footer: fn(&Commit) -> Option<String>,
+    // The bare URLs are linked in markdown
+    link_urls: bool,
+}
+
+// Max characters of a Discord message
+const DISCORD_LIMIT: usize = 2000;
+// Line between the Discord message blocks
+const DISCORD_MARKER: &str = "-- 8< --";
+
+#[derive(Debug, Clone, PartialEq, EnumString)]
+#[strum(serialize_all = "kebab-case")]
+pub enum RevertHandling {
+    // Show both the reverted and the revert commits
+    Keep,
+    // Hide both the reverted and the revert commits
+    Drop,
+    // Show both and mark the reverted commit with the revert commit
+    Annotate,
+}
 
 #[derive(Debug)]
 pub struct Config {
-    pub enable_email_link: bool,
-    pub reverse: bool,
+    pub author_link_style: AuthorLinkStyle,
+    // Email => GitHub username
+    pub author_map: HashMap<String, String>,
+    pub commit_chronology: Chronology,
     pub root_indent_level: u8,
     pub ignore_summary: Option<Regex>,
     pub ignore_types: Option<Vec<CommitType>>,
+    pub include_types: Option<Vec<CommitType>>,
+    pub show_deprecations: bool,
+    pub empty_message: Option<String>,
+    pub show_merge_parents: bool,
+    pub show_tag_aliases: bool,
+    pub drop_unbounded_first: bool,
+    // Only the tagged commit is kept in the oldest release spanning the whole history
+    pub drop_pre_first_tag: bool,
+    pub show_files_link: bool,
+    pub revert_handling: RevertHandling,
+    pub by_author: bool,
+    pub dedupe: bool,
+    pub dedupe_normalize: bool,
+    // Rendered for the authors without a name
+    pub unknown_author: String,
+    pub show_stats: bool,
+    pub group_by: GroupKey,
+    pub full_hash: bool,
+    // Hide the scope same as the type. ex) feat(feat)
+    pub hide_redundant_scope: bool,
+    // The listed types come first, the others follow in the default order
+    pub type_order: Option<Vec<CommitType>>,
+    pub link_references: bool,
+    // Drop the releases changing only the lower level than this. ex) 1.0.1 for minor
+    pub min_level: Level,
+    // Section titles instead of the sentence cased type. ex) feat => Features
+    pub type_titles: HashMap<CommitType, String>,
+    pub show_scope: bool,
+    pub drop_empty_subjects: bool,
+    pub custom_type_order: CustomOrder,
+    pub only_summary: Vec<Regex>,
+    // A link comparing the whole range at the bottom
+    pub full_changelog_footer: bool,
+    pub link_issues: bool,
+    pub show_breaking_changes: bool,
+    // The annotation of the release tag under the heading
+    pub show_tag_messages: bool,
+    // Breaking commits bypass the type filters
+    pub always_include_breaking: bool,
+    // Offset of the release dates, the offset of each tagged commit if None
+    pub timezone: Option<FixedOffset>,
+    // strftime format of the release dates. ex) %d/%m/%Y
+    pub date_format: Option<String>,
+    // Sort the commits in a section by the author, then the date
+    pub stable_author_sort: bool,
+    pub show_tag_signer: bool,
+    // Only the release commits to keep the output light
+    pub verify_release_commit_only: bool,
+    pub show_date: bool,
+    // A flat list of the type per release without the sub headings
+    pub type_only: Option<CommitType>,
+    // Extra fields of the front-matter, no front-matter if None
+    pub front_matter: Option<Vec<(String, String)>>,
+    // Only with the sections grouped by the scope
+    pub scopeless_position: ScopelessPosition,
+    // The links keep the full tag name. ex) component-v0.2.0
+    pub strip_tag_prefix_in_heading: bool,
+    // The body is indented under the list item
+    pub include_body: bool,
+    // Section title of the non-conventional commits. ex) Miscellaneous
+    pub others_title: Option<String>,
+    pub hide_others: bool,
+    // A line between the releases. ex) ---
+    pub release_separator: Option<String>,
+    // Hidden unless included explicitly. ex) chore, ci
+    pub hidden_types: Vec<CommitType>,
+    // The distinct authors by the email in the release heading
+    pub show_contributor_count: bool,
 }
 
 impl Default for Config {
     fn default() -> Self {
         Config {
-            enable_email_link: false,
-            reverse: false,
+            author_link_style: AuthorLinkStyle::None,
+            author_map: HashMap::new(),
+            commit_chronology: Chronology::NewestFirst,
             root_indent_level: 2u8,
             ignore_summary: None,
             ignore_types: None,
+            include_types: None,
+            show_deprecations: false,
+            empty_message: None,
+            show_merge_parents: false,
+            show_tag_aliases: false,
+            drop_unbounded_first: false,
+            drop_pre_first_tag: false,
+            show_files_link: false,
+            revert_handling: RevertHandling::Keep,
+            by_author: false,
+            dedupe: false,
+            dedupe_normalize: false,
+            unknown_author: String::from("Unknown"),
+            show_stats: false,
+            group_by: GroupKey::Type,
+            full_hash: false,
+            hide_redundant_scope: false,
+            type_order: None,
+            link_references: false,
+            min_level: Level::Patch,
+            type_titles: HashMap::new(),
+            show_scope: false,
+            drop_empty_subjects: true,
+            custom_type_order: CustomOrder::Alpha,
+            only_summary: Vec::new(),
+            full_changelog_footer: false,
+            link_issues: false,
+            show_breaking_changes: false,
+            show_tag_messages: false,
+            always_include_breaking: false,
+            timezone: None,
+            date_format: None,
+            stable_author_sort: false,
+            show_tag_signer: false,
+            verify_release_commit_only: false,
+            show_date: true,
+            type_only: None,
+            front_matter: None,
+            scopeless_position: ScopelessPosition::First,
+            strip_tag_prefix_in_heading: false,
+            include_body: false,
+            others_title: None,
+            hide_others: false,
+            release_separator: None,
+            hidden_types: vec![
+                CommitType::Chore,
+                CommitType::Ci,
+                CommitType::Style,
+                CommitType::Build,
+                CommitType::Test,
+            ],
+            show_contributor_count: false,
         }
     }
 }
@@ -42,33 +358,62 @@ impl Changelog {
         Changelog { conf: config }
     }
 
+    // Fails with the commits bucketed into Others. The filtered out commits aren't checked
+    pub fn strict(&self, commits: &Commits) -> Result<()> {
+        let offenders = self
+            .filter(commits.iter().collect())
+            .into_iter()
+            .filter(|c| !c.is_conventional())
+            .map(|c| c.short_hash())
+            .collect::<Vec<_>>();
+        if offenders.is_empty() {
+            return Ok(());
+        }
+        Err(Error::new(ExitError::DataErr(format!(
+            "The commits don't follow Conventional Commits. commits: {}",
+            offenders.join(", ")
+        ))))
+    }
+
     pub fn markdown(
         &self,
         url: Option<&GithubUrl>,
         commits: &Commits,
         tag_prefix: Option<&str>,
     ) -> Result<String> {
+        if self.conf.by_author {
+            return Ok(self.by_author(url, commits));
+        }
+
         let mut links = Vec::new();
+        let reverted = commits.reverted();
 
-        let func = |(range, mut vec): (ReleaseRange, BTreeMap<CommitType, Vec<&Commit>>)| {
-            let (heading, h_link) = self.heading(url, &range);
+        let func = |release: Release| {
+            let (heading, h_link) = self.heading(url, &release.range, &release.sections);
             if let Some(l) = h_link {
                 links.push(l)
             };
 
-            let (contents, c_link) = self.contents(url, &mut vec);
+            let (contents, c_link) =
+                self.contents(url, &release.notes, &release.sections, &reverted);
             if let Some(l) = c_link {
                 links.push(l)
             };
 
-            format!("{}\n{}", heading, contents)
+            match self.conf.empty_message.as_ref() {
+                Some(msg) if contents.is_empty() => format!("{}\n{}\n", heading, msg),
+                _ => format!("{}\n{}", heading, contents),
+            }
         };
 
-        let changelog = commits
-            .group_by(tag_prefix)
-            .into_iter()
-            .map(func)
-            .join("\n");
+        let releases = self.releases(commits, tag_prefix);
+        let front_matter = self.front_matter(releases.first().map(|r| &r.range))?;
+        // Blank lines around the separator, or `---` makes a setext heading
+        let separator = match &self.conf.release_separator {
+            Some(s) => format!("\n{}\n\n", s),
+            None => String::from("\n"),
+        };
+        let changelog = releases.into_iter().map(func).join(&separator);
 
         let changelog = if links.is_empty() {
             changelog
@@ -76,209 +421,1146 @@ impl Changelog {
             format!("{}\n{}\n", changelog, links.join("\n"))
         };
 
-        Ok(changelog)
-    }
-
-    fn heading(&self, url: Option<&GithubUrl>, range: &ReleaseRange) -> (String, Option<String>) {
-        let (subject, link) = match (url, range) {
-            (Some(u), ReleaseRange::Release(s, e)) => {
-                let sub = format!("[{}] - {}", e.name(), e.date());
-                let a = format!("[{}]: {}", e.name(), u.compare(s, Some(e)));
-                (sub, Some(a))
-            }
-            (Some(u), ReleaseRange::UnRelease(s)) => {
-                let sub = "[Unreleased]".to_string();
-                let a = format!("[Unreleased]: {}", u.compare(s, None));
-                (sub, Some(a))
+        let changelog = match url {
+            Some(u) if self.conf.full_changelog_footer => {
+                let (start, end) = commits.range(tag_prefix);
+                format!(
+                    "{}\n**Full Changelog**: {}\n",
+                    changelog,
+                    u.compare(&start, end.as_ref())
+                )
             }
-            (None, ReleaseRange::Release(_, e)) => (format!("{} - {}", e.name(), e.date()), None),
-            (None, ReleaseRange::UnRelease(_)) => (String::from("Unreleased"), None),
+            _ => changelog,
         };
-        let heading = format!("{} {}", self.heading_style(), subject);
-        (heading, link)
+
+        Ok(format!("{}{}", front_matter, changelog))
     }
 
-    fn sub_heading(&self, ct: &CommitType) -> String {
-        format!("{} {}", self.sub_heading_style(), ct.to_string())
+    // The same releases as the markdown without the formatting
+    pub fn json(
+        &self,
+        url: Option<&GithubUrl>,
+        commits: &Commits,
+        tag_prefix: Option<&str>,
+    ) -> Result<String> {
+        let releases = self.release_data(url, commits, tag_prefix);
+        let json = serde_json::to_string_pretty(&releases)?;
+        Ok(format!("{}\n", json))
     }
 
-    fn contents(
+    pub fn ndjson_releases(
         &self,
         url: Option<&GithubUrl>,
-        commits: &mut BTreeMap<CommitType, Vec<&Commit>>,
-    ) -> (String, Option<String>) {
-        let mut links = Vec::new();
+        commits: &Commits,
+        tag_prefix: Option<&str>,
+    ) -> Result<String> {
+        self.release_data(url, commits, tag_prefix)
+            .iter()
+            .map(|r| Ok(format!("{}\n", serde_json::to_string(r)?)))
+            .collect()
+    }
 
-        let contents = commits
-            .iter_mut()
-            .map(|(ct, vec)| {
-                if self.conf.reverse {
-                    vec.reverse();
-                }
+    // Render the Tera template with `releases`, the same structure as the json format
+    pub fn template(
+        &self,
+        url: Option<&GithubUrl>,
+        commits: &Commits,
+        tag_prefix: Option<&str>,
+        template: &str,
+    ) -> Result<String> {
+        let releases = self.release_data(url, commits, tag_prefix);
+        let mut context = tera::Context::new();
+        context.insert("releases", &releases);
+        tera::Tera::one_off(template, &context, false).context("Failed to render the template")
+    }
 
-                let (section, link) = self.section(url, ct, vec.to_vec());
-                if let Some(l) = link {
-                    links.push(l)
+    // AsciiDoc sections are fixed to `==` and `===`, so root_indent_level is ignored
+    pub fn asciidoc(
+        &self,
+        url: Option<&GithubUrl>,
+        commits: &Commits,
+        tag_prefix: Option<&str>,
+    ) -> Result<String> {
+        let changelog = self
+            .releases(commits, tag_prefix)
+            .iter()
+            .map(|release| {
+                let subject = match (url, &release.range) {
+                    (Some(u), ReleaseRange::Release(s, e)) => format!(
+                        "link:{}[{}]{}",
+                        u.compare(s, Some(e)),
+                        self.release_name(e),
+                        self.dated(e)
+                    ),
+                    (Some(u), ReleaseRange::UnRelease(s)) => {
+                        format!("link:{}[Unreleased]", u.compare(s, None))
+                    }
+                    (None, ReleaseRange::Release(_, e)) => {
+                        format!("{}{}", self.release_name(e), self.dated(e))
+                    }
+                    (None, ReleaseRange::UnRelease(_)) => String::from("Unreleased"),
                 };
 
-                section
+                let sections = self
+                    .entries(release)
+                    .into_iter()
+                    .map(|(title, v)| {
+                        let items = v
+                            .into_iter()
+                            .map(|(c, msg)| {
+                                let hash = match url {
+                                    Some(u) => format!("link:{}[{}]", u.commit(c), c.short_hash()),
+                                    None => c.short_hash(),
+                                };
+                                let au = escape_asciidoc(self.author_name(c.author()));
+                                format!("* {} {} ({})\n", hash, escape_asciidoc(&msg), au)
+                            })
+                            .collect::<String>();
+                        format!("=== {}\n{}", escape_asciidoc(&title), items)
+                    })
+                    .join("\n");
+                match self.empty_message(release) {
+                    Some(msg) => format!("== {}\n{}\n", subject, escape_asciidoc(msg)),
+                    None => format!("== {}\n{}", subject, sections),
+                }
             })
-            .flatten()
             .join("\n");
 
-        let links = links.first().map(|_| links.join("\n"));
-        (contents, links)
+        Ok(changelog)
     }
 
-    // TODO impl breaking change expressions
-    fn section(
-        &self,
-        url: Option<&GithubUrl>,
-        ct: &CommitType,
-        commits: Vec<&Commit>,
-    ) -> (Option<String>, Option<String>) {
-        let mut links = Vec::new();
-        let aggregate = |commit: &Commit| -> String {
-            let hash = commit.short_hash();
-            let msg = commit.message();
-            let au = self.author(commit.author());
-            match url {
-                Some(u) => {
-                    let item = format!("- [[{}]] {} ({})", &hash, &msg, &au);
-                    let link = format!("[{}]: {}", &hash, u.commit(commit));
-                    links.push(link);
-                    item
+    // The markdown without the markups and links. ex) Slack, email
+    pub fn plain(&self, commits: &Commits, tag_prefix: Option<&str>) -> String {
+        self.releases(commits, tag_prefix)
+            .iter()
+            .map(|release| {
+                let subject = match &release.range {
+                    ReleaseRange::Release(_, e) => {
+                        format!("{}{}", self.release_name(e), self.dated(e))
+                    }
+                    ReleaseRange::UnRelease(_) => String::from("Unreleased"),
+                };
+                let sections = self
+                    .entries(release)
+                    .into_iter()
+                    .map(|(title, v)| {
+                        let items = v
+                            .into_iter()
+                            .map(|(c, msg)| {
+                                format!(
+                                    "- {} {} ({})\n",
+                                    c.short_hash(),
+                                    msg,
+                                    self.author_name(c.author())
+                                )
+                            })
+                            .collect::<String>();
+                        format!("{}\n{}", title.to_uppercase(), items)
+                    })
+                    .join("\n");
+                match self.empty_message(release) {
+                    Some(msg) => format!("{}\n{}\n", subject.to_uppercase(), msg),
+                    None => format!("{}\n{}", subject.to_uppercase(), sections),
                 }
-                None => format!("- [{}] {} ({})", &hash, &msg, &au),
-            }
-        };
+            })
+            .join("\n")
+    }
 
-        let lines = commits
-            .into_iter()
-            .filter(self.ignore_summary())
-            .filter(self.ignore_types())
-            // This is exactly the same as --no-merge
-            // count == 0 is first commit
-            .filter(|c| c.parent_count() <= 1)
-            .map(aggregate)
-            .join("\n");
+    // Inline links only, split into the blocks fitting in a Discord message
+    pub fn discord(
+        &self,
+        url: Option<&GithubUrl>,
+        commits: &Commits,
+        tag_prefix: Option<&str>,
+    ) -> Result<String> {
+        let lines = self
+            .releases(commits, tag_prefix)
+            .iter()
+            .flat_map(|release| {
+                let subject = match (url, &release.range) {
+                    (Some(u), ReleaseRange::Release(s, e)) => {
+                        format!(
+                            "[{}](<{}>){}",
+                            self.release_name(e),
+                            u.compare(s, Some(e)),
+                            self.dated(e)
+                        )
+                    }
+                    (Some(u), ReleaseRange::UnRelease(s)) => {
+                        format!("[Unreleased](<{}>)", u.compare(s, None))
+                    }
+                    (None, ReleaseRange::Release(_, e)) => {
+                        format!("{}{}", self.release_name(e), self.dated(e))
+                    }
+                    (None, ReleaseRange::UnRelease(_)) => String::from("Unreleased"),
+                };
+                let heading = format!("{} {}", self.heading_style(), subject);
 
-        if lines.is_empty() {
-            return (None, None);
-        }
+                let items = self.entries(release).into_iter().flat_map(|(title, v)| {
+                    let items = v.into_iter().map(|(c, msg)| {
+                        // <> suppresses the embeds of the links
+                        let hash = match url {
+                            Some(u) => format!("[{}](<{}>)", c.short_hash(), u.commit(c)),
+                            None => c.short_hash(),
+                        };
+                        format!("- {} {} ({})", hash, msg, self.author_name(c.author()))
+                    });
+                    iter::once(format!("{} {}", self.sub_heading_style(), title))
+                        .chain(items)
+                        .collect::<Vec<_>>()
+                });
+                let empty = self.empty_message(release).map(String::from);
+                iter::once(heading)
+                    .chain(empty)
+                    .chain(items)
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
 
-        let heading = self.sub_heading(ct);
-        let section = format!("{}\n{}\n", heading, lines);
-        let links = links.first().map(|_| links.join("\n"));
+        let blocks = lines
+            .into_iter()
+            .fold(Vec::<String>::new(), |mut acc, line| {
+                let line = truncate(&line, DISCORD_LIMIT - 1);
+                match acc.last_mut() {
+                    // +1 for the newline
+                    Some(b) if b.chars().count() + line.chars().count() < DISCORD_LIMIT => {
+                        b.push_str(&line);
+                        b.push('\n');
+                    }
+                    _ => acc.push(format!("{}\n", line)),
+                }
+                acc
+            });
 
-        (Some(section), links)
+        Ok(blocks.join(&format!("{}\n", DISCORD_MARKER)))
     }
 
-    fn ignore_summary<'a>(&'a self) -> impl FnMut(&&'a Commit) -> bool {
-        move |commit: &&'a Commit| -> bool {
-            let regex = self.conf.ignore_summary.as_ref();
-            match regex {
-                Some(re) => !re.is_match(commit.message().as_ref()),
-                _ => true,
+    // The releases with the filtered and ordered sections, shared by all the formats
+    fn releases<'a>(&'a self, commits: &'a Commits, tag_prefix: Option<&str>) -> Vec<Release<'a>> {
+        let reverted = commits.reverted();
+
+        let mut releases = commits.group_by(tag_prefix, &|c| self.section_key(c));
+        releases.retain(|(range, _)| self.min_level(range));
+        self.type_only(&mut releases);
+        if self.conf.drop_unbounded_first && commits.is_unbounded() {
+            if let Some((ReleaseRange::Release(..), _)) = releases.last() {
+                releases.pop();
             }
         }
+        self.drop_pre_first_tag(commits, &mut releases, tag_prefix);
+
+        releases
+            .into_iter()
+            .map(|(range, vec)| {
+                let sections = self.ordered_sections(vec, &reverted);
+                Release {
+                    range,
+                    notes: self.notes(&sections),
+                    sections,
+                }
+            })
+            .collect()
     }
 
-    fn ignore_types<'a>(&'a self) -> impl FnMut(&&'a Commit) -> bool {
-        move |commit: &&'a Commit| -> bool {
-            let _types = self.conf.ignore_types.as_ref();
-            match _types {
-                Some(t) => !t.contains(&commit.raw_type()),
-                _ => true,
+    // The empty and hidden sections are dropped
+    fn ordered_sections<'a>(
+        &'a self,
+        mut commits: BTreeMap<SectionKey, Vec<&'a Commit>>,
+        reverted: &HashMap<Oid, &Commit>,
+    ) -> Sections<'a> {
+        self.drop_reverted(&mut commits, reverted);
+        let mut sections = commits
+            .into_iter()
+            .filter_map(|(key, mut v)| {
+                self.order(&mut v);
+                let items = self.filter(v);
+                if items.is_empty() || self.hidden(&key) {
+                    None
+                } else {
+                    Some((key, items))
+                }
+            })
+            .collect::<Sections>();
+
+        self.scopeless_last(&mut sections, |(key, _)| key);
+        if self.conf.custom_type_order == CustomOrder::FirstSeen {
+            // Custom types are adjacent in the map, so only their slice is reordered
+            let is_custom = |t: &CommitType| matches!(t, CommitType::Custom(_));
+            let start = sections.iter().position(|(key, _)| is_custom(&key._type));
+            let end = sections.iter().rposition(|(key, _)| is_custom(&key._type));
+            if let (Some(start), Some(end)) = (start, end) {
+                sections[start..=end]
+                    .sort_by_key(|(_, vec)| vec.iter().map(|c| *c.datetime()).min());
             }
         }
+        if let Some(order) = self.conf.type_order.as_ref() {
+            // Stable sort keeps the default order of the types not listed
+            sections.sort_by_key(|(key, _)| {
+                order
+                    .iter()
+                    .position(|t| *t == key._type)
+                    .unwrap_or(order.len())
+            });
+        }
+        sections
     }
 
-    fn author(&self, author: &Author) -> String {
-        let name = author.name();
-        match author.email() {
-            Some(email) if self.conf.enable_email_link => format!("[{}](mailto:{})", name, email),
-            _ => name.to_string(),
+    // Breaking Changes and Deprecations, none for a single type
+    fn notes<'a>(&self, sections: &Sections<'a>) -> Vec<Note<'a>> {
+        if self.conf.type_only.is_some() {
+            return Vec::new();
         }
+        let breaking = Note {
+            title: "Breaking Changes",
+            commits: Vec::new(),
+            footer: |c| c.breaking_change().map(String::from),
+            link_urls: true,
+        };
+        let deprecations = Note {
+            title: "Deprecations",
+            commits: Vec::new(),
+            footer: |c| c.deprecation().map(String::from),
+            link_urls: false,
+        };
+        let notes = match (self.conf.show_breaking_changes, self.conf.show_deprecations) {
+            (true, true) => vec![breaking, deprecations],
+            (true, false) => vec![breaking],
+            (false, true) => vec![deprecations],
+            (false, false) => Vec::new(),
+        };
+        notes
+            .into_iter()
+            .filter_map(|mut note| {
+                let mut commits = sections
+                    .iter()
+                    .flat_map(|(_, v)| v.iter().copied())
+                    .filter(|c| (note.footer)(c).is_some())
+                    .collect::<Vec<&Commit>>();
+                commits.sort_by(|a, b| b.cmp(a));
+                if self.conf.commit_chronology == Chronology::OldestFirst {
+                    commits.reverse();
+                }
+                note.commits = commits;
+                if note.commits.is_empty() {
+                    None
+                } else {
+                    Some(note)
+                }
+            })
+            .collect()
     }
 
-    fn heading_style(&self) -> String {
-        let indent = self.conf.root_indent_level;
-        "#".repeat(indent as usize)
+    // The titles and the messages of the notes and the sections for the formats without the details
+    fn entries<'a>(&self, release: &Release<'a>) -> Vec<(String, Vec<(&'a Commit, String)>)> {
+        let notes = release.notes.iter().map(|note| {
+            let items = note
+                .commits
+                .iter()
+                .map(|c| (*c, (note.footer)(c).unwrap_or_default()))
+                .collect();
+            (String::from(note.title), items)
+        });
+        let sections = release.sections.iter().map(|(key, v)| {
+            let items = v.iter().map(|c| (*c, c.message())).collect();
+            (self.title(key), items)
+        });
+        notes.chain(sections).collect()
     }
 
-    fn sub_heading_style(&self) -> String {
-        let indent = self.conf.root_indent_level + 1;
-        "#".repeat(indent as usize)
+    fn empty_message(&self, release: &Release) -> Option<&str> {
+        match self.conf.empty_message.as_deref() {
+            Some(msg) if release.is_empty() => Some(msg),
+            _ => None,
+        }
     }
-}
-#[cfg(test)]
-mod tests {
-    use anyhow::Result;
 
-    use super::*;
-    use crate::git::tests::*;
+    fn release_data(
+        &self,
+        url: Option<&GithubUrl>,
+        commits: &Commits,
+        tag_prefix: Option<&str>,
+    ) -> Vec<ReleaseData> {
+        self.releases(commits, tag_prefix)
+            .into_iter()
+            .map(|release| {
+                let (version, date, compare_url) = match &release.range {
+                    ReleaseRange::Release(s, e) => (
+                        self.release_name(e),
+                        Some(self.date(e)).filter(|_| self.conf.show_date),
+                        url.map(|u| u.compare(s, Some(e))),
+                    ),
+                    ReleaseRange::UnRelease(s) => (
+                        String::from("Unreleased"),
+                        None,
+                        url.map(|u| u.compare(s, None)),
+                    ),
+                };
+                let data = |v: &Vec<&Commit>| {
+                    v.iter()
+                        .map(|c| CommitData::new(c, self.author_name(c.author())))
+                        .collect::<Vec<_>>()
+                };
+                let commits = release
+                    .sections
+                    .iter()
+                    .map(|(key, v)| (key.token(), data(v)))
+                    .collect();
+                let sections = release
+                    .sections
+                    .iter()
+                    .map(|(key, v)| SectionData {
+                        _type: key._type.token(),
+                        scope: key.scope.clone(),
+                        title: self.title(key),
+                        commits: data(v),
+                    })
+                    .collect();
+                ReleaseData {
+                    version,
+                    date,
+                    compare_url,
+                    commits,
+                    sections,
+                }
+            })
+            .collect()
+    }
 
-    #[test]
-    fn all_commit_type_ok() -> Result<()> {
-        let mut commits = Vec::new();
-        let commit = dummy_commit(
-            "2e185faf719f12292414c88872e3397fc5dc4e62",
-            "security",
-            None,
-            false,
-            "fix security",
-            "Test User12 <test-user12@test.com>",
-            "Wed Apr 01 01:01:12 2020 +0000",
-            1,
-            Some("0.2.0"),
-        )?;
-        commits.push(commit);
+    // Stable format for scripts, one commit per line.
+    // The columns are `release<TAB>type<TAB>short hash<TAB>author<TAB>message`
+    pub fn porcelain(&self, commits: &Commits, tag_prefix: Option<&str>) -> String {
+        let escape = |s: &str| s.replace(['\t', '\n'], " ");
 
-        let commit = dummy_commit(
-            "1e185faf719f12292414c88872e3397fc5dc4e62",
-            "revert",
-            None,
-            false,
-            "add some",
-            "Test User11 <test-user11@test.com>",
-            "Wed Apr 01 01:01:11 2020 +0000",
-            1,
-            None,
-        )?;
-        commits.push(commit);
+        self.releases(commits, tag_prefix)
+            .into_iter()
+            .flat_map(|release| {
+                let release_name = match &release.range {
+                    ReleaseRange::Release(_, e) => e.name(),
+                    ReleaseRange::UnRelease(_) => String::from("Unreleased"),
+                };
+                release
+                    .sections
+                    .into_iter()
+                    .flat_map(|(_, v)| v)
+                    .map(|c| {
+                        format!(
+                            "{}\t{}\t{}\t{}\t{}\n",
+                            escape(&release_name),
+                            c.raw_type().token(),
+                            c.short_hash(),
+                            escape(self.author_name(c.author())),
+                            escape(&c.message())
+                        )
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
 
-        let commit = dummy_commit(
-            "0e185faf719f12292414c88872e3397fc5dc4e62",
-            "test",
-            None,
-            false,
-            "add test",
-            "Test User10 <test-user10@test.com>",
-            "Wed Apr 01 01:01:10 2020 +0000",
-            1,
-            None,
-        )?;
-        commits.push(commit);
+    fn min_level(&self, range: &ReleaseRange) -> bool {
+        let (start, end) = match range {
+            ReleaseRange::Release(s, e) => (s.version(), e.version()),
+            ReleaseRange::UnRelease(_) => return true,
+        };
+        let (prev, ver) = match (start, end) {
+            (Some(s), Some(e)) => (s.semver(), e.semver()),
+            _ => return true,
+        };
+        let level = if prev.major != ver.major {
+            Level::Major
+        } else if prev.minor != ver.minor {
+            Level::Minor
+        } else {
+            Level::Patch
+        };
+        level >= self.conf.min_level
+    }
 
-        let commit = dummy_commit(
-            "9d185faf719f12292414c88872e3397fc5dc4e62",
-            "perf",
-            None,
-            false,
-            "add perf",
-            "Test User9 <test-user9@test.com>",
-            "Wed Apr 01 01:01:09 2020 +0000",
-            1,
-            None,
-        )?;
-        commits.push(commit);
+    fn by_author(&self, url: Option<&GithubUrl>, commits: &Commits) -> String {
+        let mut links = Vec::new();
+        let reverted = commits.reverted();
 
-        let commit = dummy_commit(
-            "8d185faf719f12292414c88872e3397fc5dc4e62",
-            "refactor",
-            None,
-            false,
+        let changelog = commits
+            .group_by_author(&|c| self.section_key(c))
+            .into_iter()
+            .map(|(author, vec)| {
+                let heading = format!("{} {}", self.heading_style(), self.author(author));
+                let sections = self.ordered_sections(vec, &reverted);
+                let notes = self.notes(&sections);
+                let (contents, link) = self.contents(url, &notes, &sections, &reverted);
+                if let Some(l) = link {
+                    links.push(l)
+                };
+                format!("{}\n{}", heading, contents)
+            })
+            .join("\n");
+
+        if links.is_empty() {
+            changelog
+        } else {
+            format!("{}\n{}\n", changelog, links.join("\n"))
+        }
+    }
+
+    // Stable sort keeps the order of the types and the scopes
+    fn scopeless_last<T, F>(&self, entries: &mut [T], key: F)
+    where
+        F: Fn(&T) -> &SectionKey,
+    {
+        if self.conf.scopeless_position == ScopelessPosition::Last {
+            entries.sort_by_key(|e| (key(e)._type.clone(), key(e).scope.is_none()));
+        }
+    }
+
+    // The releases without the commits of the type are dropped
+    fn type_only(&self, releases: &mut Vec<(ReleaseRange, BTreeMap<SectionKey, Vec<&Commit>>)>) {
+        if let Some(t) = self.conf.type_only.as_ref() {
+            releases
+                .iter_mut()
+                .for_each(|(_, vec)| vec.retain(|key, _| key._type == *t));
+            releases.retain(|(_, vec)| vec.values().any(|v| !self.filter(v.clone()).is_empty()));
+        }
+    }
+
+    // The commits older than the first tag. ex) the pre-conventional commits
+    fn drop_pre_first_tag(
+        &self,
+        commits: &Commits,
+        releases: &mut Vec<(ReleaseRange, BTreeMap<SectionKey, Vec<&Commit>>)>,
+        tag_prefix: Option<&str>,
+    ) {
+        if !self.conf.drop_pre_first_tag || !commits.is_unbounded() {
+            return;
+        }
+        if let Some((ReleaseRange::Release(..), vec)) = releases.last_mut() {
+            vec.values_mut()
+                .for_each(|v| v.retain(|c| c.name_obj(tag_prefix).is_some()));
+            vec.retain(|_, v| !v.is_empty());
+        }
+    }
+
+    fn drop_reverted(
+        &self,
+        commits: &mut BTreeMap<SectionKey, Vec<&Commit>>,
+        reverted: &HashMap<Oid, &Commit>,
+    ) {
+        if self.conf.revert_handling != RevertHandling::Drop {
+            return;
+        }
+        commits.values_mut().for_each(|v| {
+            v.retain(|c| {
+                !reverted.contains_key(&c.id)
+                    && !c.reverts().is_some_and(|id| reverted.contains_key(id))
+            })
+        });
+    }
+
+    fn heading(
+        &self,
+        url: Option<&GithubUrl>,
+        range: &ReleaseRange,
+        sections: &Sections,
+    ) -> (String, Option<String>) {
+        let (subject, link) = match (url, range) {
+            (Some(u), ReleaseRange::Release(s, e)) => {
+                let sub = format!(
+                    "[{}]{}{}{}",
+                    self.release_name(e),
+                    self.aliases(e),
+                    self.dated(e),
+                    self.signer(e)
+                );
+                let a = format!("[{}]: {}", self.release_name(e), u.compare(s, Some(e)));
+                (sub, Some(a))
+            }
+            (Some(u), ReleaseRange::UnRelease(s)) => {
+                let sub = "[Unreleased]".to_string();
+                let a = format!("[Unreleased]: {}", u.compare(s, None));
+                (sub, Some(a))
+            }
+            (None, ReleaseRange::Release(_, e)) => (
+                format!(
+                    "{}{}{}{}",
+                    self.release_name(e),
+                    self.aliases(e),
+                    self.dated(e),
+                    self.signer(e)
+                ),
+                None,
+            ),
+            (None, ReleaseRange::UnRelease(_)) => (String::from("Unreleased"), None),
+        };
+        let heading = format!(
+            "{} {}{}",
+            self.heading_style(),
+            subject,
+            self.contributors(sections)
+        );
+        let heading = match range {
+            ReleaseRange::Release(_, e) if self.conf.show_tag_messages => match e.message() {
+                Some(msg) => format!("{}\n\n{}\n", heading, msg),
+                None => heading,
+            },
+            _ => heading,
+        };
+        (heading, link)
+    }
+
+    // YAML block of the latest release for the static site generators. ex) Hugo, Jekyll
+    fn front_matter(&self, range: Option<&ReleaseRange>) -> Result<String> {
+        let fields = match self.conf.front_matter.as_ref() {
+            Some(f) => f,
+            None => return Ok(String::new()),
+        };
+        let (title, date) = match range {
+            Some(ReleaseRange::Release(_, e)) => (self.release_name(e), Some(self.date(e))),
+            _ => (String::from("Unreleased"), None),
+        };
+        let mut map = serde_yaml::Mapping::new();
+        map.insert("title".into(), title.into());
+        if let Some(d) = date {
+            map.insert("date".into(), d.into());
+        }
+        // The same keys override the title and the date
+        for (key, value) in fields {
+            map.insert(key.as_str().into(), value.as_str().into());
+        }
+        let yaml = serde_yaml::to_string(&map)?;
+        Ok(format!("---\n{}---\n\n", yaml.trim_start_matches("---\n")))
+    }
+
+    // In the offset of the commit unless the timezone is specified
+    fn date(&self, obj: &NamableObj) -> String {
+        let format = self.conf.date_format.as_deref().unwrap_or(DATE_FORMAT);
+        match self.conf.timezone.as_ref() {
+            Some(tz) => obj.datetime().with_timezone(tz).format(format).to_string(),
+            None => obj.datetime().format(format).to_string(),
+        }
+    }
+
+    // The date part of the release headings. ex) ` - 2020-01-01`
+    fn dated(&self, obj: &NamableObj) -> String {
+        if self.conf.show_date {
+            format!(" - {}", self.date(obj))
+        } else {
+            String::new()
+        }
+    }
+
+    fn release_name(&self, obj: &NamableObj) -> String {
+        if self.conf.strip_tag_prefix_in_heading {
+            obj.bare_name()
+        } else {
+            obj.name()
+        }
+    }
+
+    fn signer(&self, obj: &NamableObj) -> String {
+        match obj.signer() {
+            Some(signer) if self.conf.show_tag_signer => format!(" (signed by {})", signer),
+            _ => String::new(),
+        }
+    }
+
+    // Only the rendered commits. The authors without an email are counted by the name. ex) ` (2 contributors)`
+    fn contributors(&self, sections: &Sections) -> String {
+        if !self.conf.show_contributor_count {
+            return String::new();
+        }
+        let count = sections
+            .iter()
+            .flat_map(|(_, v)| v)
+            .map(|c| c.author().email().or_else(|| c.author().name()))
+            .collect::<HashSet<_>>()
+            .len();
+        match count {
+            1 => String::from(" (1 contributor)"),
+            n => format!(" ({} contributors)", n),
+        }
+    }
+
+    fn aliases(&self, obj: &NamableObj) -> String {
+        let aliases = obj.aliases();
+        if !self.conf.show_tag_aliases || aliases.is_empty() {
+            return String::new();
+        }
+        format!(" ({})", aliases.join(", "))
+    }
+
+    fn section_key(&self, commit: &Commit) -> SectionKey {
+        let scope = match self.conf.group_by {
+            GroupKey::Type => None,
+            GroupKey::TypeScope => self.scope(commit),
+        };
+        SectionKey::new(commit.raw_type(), scope.map(String::from))
+    }
+
+    fn scope<'a>(&self, commit: &'a Commit) -> Option<&'a str> {
+        let scope = commit.scope();
+        match scope {
+            Some(s) if self.conf.hide_redundant_scope && s == commit.raw_type().token() => None,
+            _ => scope,
+        }
+    }
+
+    fn sub_heading(&self, key: &SectionKey) -> String {
+        format!("{} {}", self.sub_heading_style(), self.title(key))
+    }
+
+    fn title(&self, key: &SectionKey) -> String {
+        let title = match &key._type {
+            CommitType::Others => self.conf.others_title.as_ref(),
+            _ => None,
+        };
+        match (
+            title.or_else(|| self.conf.type_titles.get(&key._type)),
+            &key.scope,
+        ) {
+            (Some(t), Some(scope)) => format!("{} ({})", t, scope),
+            (Some(t), None) => t.clone(),
+            (None, _) => key.to_string(),
+        }
+    }
+
+    fn contents(
+        &self,
+        url: Option<&GithubUrl>,
+        notes: &[Note],
+        sections: &Sections,
+        reverted: &HashMap<Oid, &Commit>,
+    ) -> (String, Option<String>) {
+        let mut links = Vec::new();
+
+        // Links are made by the commit type sections
+        let notes = notes.iter().filter_map(|note| {
+            let heading = format!("{} {}", self.sub_heading_style(), note.title);
+            self.items(url, &heading, note.commits.clone(), false, |c| {
+                let footer = (note.footer)(c).unwrap_or_default();
+                if note.link_urls {
+                    link_urls(&footer)
+                } else {
+                    footer
+                }
+            })
+            .0
+        });
+
+        let sections = sections.iter().filter_map(|(key, vec)| {
+            let (section, link) = self.section(url, key, vec.to_vec(), reverted);
+            if let Some(l) = link {
+                links.push(l)
+            };
+
+            section
+        });
+        let contents = notes.chain(sections).join("\n");
+
+        let links = links.first().map(|_| links.join("\n"));
+        (contents, links)
+    }
+
+    // TODO impl breaking change expressions
+    fn section(
+        &self,
+        url: Option<&GithubUrl>,
+        key: &SectionKey,
+        commits: Vec<&Commit>,
+        reverted: &HashMap<Oid, &Commit>,
+    ) -> (Option<String>, Option<String>) {
+        let heading = match self.conf.type_only {
+            Some(_) => String::new(),
+            None => self.sub_heading(key),
+        };
+        self.items(url, &heading, commits, true, |c| {
+            let msg = match reverted.get(&c.id) {
+                Some(r) if self.conf.revert_handling == RevertHandling::Annotate => {
+                    format!("{} (reverted in {})", c.message(), r.short_hash())
+                }
+                _ => c.message(),
+            };
+            match self.scope(c) {
+                Some(scope) if self.conf.show_scope => format!("**{}:** {}", scope, msg),
+                _ => msg,
+            }
+        })
+    }
+
+    fn hidden(&self, key: &SectionKey) -> bool {
+        self.conf.hide_others && key._type == CommitType::Others
+    }
+
+    fn items<F>(
+        &self,
+        url: Option<&GithubUrl>,
+        heading: &str,
+        commits: Vec<&Commit>,
+        details: bool,
+        message: F,
+    ) -> (Option<String>, Option<String>)
+    where
+        F: Fn(&Commit) -> String,
+    {
+        let mut links = Vec::new();
+        let aggregate = |commit: &Commit| -> String {
+            let hash = if self.conf.full_hash {
+                commit.hash()
+            } else {
+                commit.short_hash()
+            };
+            let msg = match url {
+                Some(u) if self.conf.link_references => u.link_references(&message(commit)),
+                _ => message(commit),
+            };
+            let msg = match url {
+                Some(u) if self.conf.link_issues => u.link_issues(&msg),
+                _ => msg,
+            };
+            let msg = if msg.trim().is_empty() {
+                String::from(EMPTY_SUBJECT)
+            } else {
+                msg
+            };
+            let au = self.author(commit.author());
+            let merge = self.merge_parents(commit);
+            let verified = self.verified(commit);
+            let stats = self.stats(commit);
+            let body = match commit.body() {
+                Some(b) if details && self.conf.include_body => indent(b),
+                _ => String::new(),
+            };
+            let closes = match url {
+                Some(u) if details && self.conf.link_issues => self.closes(u, commit),
+                _ => String::new(),
+            };
+            let item = match url {
+                Some(u) => {
+                    let files = if self.conf.show_files_link {
+                        format!(" ([files]({}))", u.files(commit))
+                    } else {
+                        String::new()
+                    };
+                    let item = format!(
+                        "- [[{}]] {} ({}){}{}{}{}{}",
+                        &hash, &msg, &au, &merge, &verified, &stats, &files, &closes
+                    );
+                    let link = format!("[{}]: {}", &hash, u.commit(commit));
+                    links.push(link);
+                    item
+                }
+                None => format!(
+                    "- [{}] {} ({}){}{}{}",
+                    &hash, &msg, &au, &merge, &verified, &stats
+                ),
+            };
+            format!("{}{}", item, body)
+        };
+
+        let lines = commits.into_iter().map(aggregate).join("\n");
+
+        if lines.is_empty() {
+            return (None, None);
+        }
+
+        let section = if heading.is_empty() {
+            format!("{}\n", lines)
+        } else {
+            format!("{}\n{}\n", heading, lines)
+        };
+        let links = links.first().map(|_| links.join("\n"));
+
+        (Some(section), links)
+    }
+
+    // Commits in a section are newest first unless sorted or oldest first
+    fn order(&self, commits: &mut Vec<&Commit>) {
+        if self.conf.stable_author_sort {
+            // The hash breaks the ties of the same second
+            commits.sort_by(|a, b| {
+                self.author_name(a.author())
+                    .cmp(self.author_name(b.author()))
+                    .then_with(|| b.datetime().cmp(a.datetime()))
+                    .then_with(|| a.id.cmp(&b.id))
+            });
+        }
+        if self.conf.commit_chronology == Chronology::OldestFirst {
+            commits.reverse();
+        }
+    }
+
+    fn filter<'a>(&'a self, commits: Vec<&'a Commit>) -> Vec<&'a Commit> {
+        commits
+            .into_iter()
+            .filter(self.only_summary())
+            .filter(self.ignore_summary())
+            .filter(self.include_types())
+            .filter(self.ignore_types())
+            .filter(self.hidden_types())
+            // This is exactly the same as --no-merge
+            // count == 0 is first commit
+            .filter(|c| self.conf.show_merge_parents || c.parent_count() <= 1)
+            .filter(|c| !self.conf.drop_empty_subjects || !c.message().trim().is_empty())
+            .filter(self.dedupe())
+            .collect()
+    }
+
+    // Keeps all the commits if no patterns are given
+    fn only_summary<'a>(&'a self) -> impl FnMut(&&'a Commit) -> bool {
+        move |commit: &&'a Commit| -> bool {
+            let patterns = &self.conf.only_summary;
+            patterns.is_empty() || patterns.iter().any(|re| re.is_match(&commit.message()))
+        }
+    }
+
+    fn ignore_summary<'a>(&'a self) -> impl FnMut(&&'a Commit) -> bool {
+        move |commit: &&'a Commit| -> bool {
+            let regex = self.conf.ignore_summary.as_ref();
+            match regex {
+                Some(re) => !re.is_match(commit.message().as_ref()),
+                _ => true,
+            }
+        }
+    }
+
+    // Applied before ignore_types, so the ignored types are removed from the included ones
+    fn include_types<'a>(&'a self) -> impl FnMut(&&'a Commit) -> bool {
+        move |commit: &&'a Commit| -> bool {
+            if self.conf.always_include_breaking && commit.is_breaking() {
+                return true;
+            }
+            let _types = self.conf.include_types.as_ref();
+            match _types {
+                Some(t) => t.contains(&commit.raw_type()),
+                _ => true,
+            }
+        }
+    }
+
+    fn ignore_types<'a>(&'a self) -> impl FnMut(&&'a Commit) -> bool {
+        move |commit: &&'a Commit| -> bool {
+            if self.conf.always_include_breaking && commit.is_breaking() {
+                return true;
+            }
+            let _types = self.conf.ignore_types.as_ref();
+            match _types {
+                Some(t) => !t.contains(&commit.raw_type()),
+                _ => true,
+            }
+        }
+    }
+
+    // The types in --include-types are shown
+    fn hidden_types<'a>(&'a self) -> impl FnMut(&&'a Commit) -> bool {
+        move |commit: &&'a Commit| -> bool {
+            if self.conf.always_include_breaking && commit.is_breaking() {
+                return true;
+            }
+            let _type = commit.raw_type();
+            let included = self.conf.include_types.as_ref();
+            !self.conf.hidden_types.contains(&_type) || included.is_some_and(|t| t.contains(&_type))
+        }
+    }
+
+    // Keep the first commit of the same messages in a section
+    // Filtered per section, so the same message in other releases or types is kept
+    fn dedupe<'a>(&'a self) -> impl FnMut(&&'a Commit) -> bool {
+        let mut seen = HashSet::new();
+        move |commit: &&'a Commit| -> bool {
+            if !self.conf.dedupe {
+                return true;
+            }
+            let msg = commit.message();
+            let msg = if self.conf.dedupe_normalize {
+                msg.trim().to_lowercase()
+            } else {
+                msg
+            };
+            // The rebased or cherry-picked commits keep the author
+            seen.insert((msg, commit.author()))
+        }
+    }
+
+    fn merge_parents(&self, commit: &Commit) -> String {
+        if !self.conf.show_merge_parents || commit.parent_count() <= 1 {
+            return String::new();
+        }
+        format!(" (merge of {})", commit.parent_short_hashes().join(", "))
+    }
+
+    fn verified(&self, commit: &Commit) -> String {
+        if self.conf.verify_release_commit_only && commit.is_release() && commit.is_verified() {
+            String::from(" (verified)")
+        } else {
+            String::new()
+        }
+    }
+
+    fn closes(&self, url: &GithubUrl, commit: &Commit) -> String {
+        let refs = commit.closes();
+        if refs.is_empty() {
+            return String::new();
+        }
+        format!(" (closes {})", url.link_issues(&refs.join(", ")))
+    }
+
+    fn stats(&self, commit: &Commit) -> String {
+        match commit.stats() {
+            Some(s) if self.conf.show_stats => {
+                let unit = if s.files == 1 { "file" } else { "files" };
+                format!(
+                    " (+{} -{}, {} {})",
+                    s.insertions, s.deletions, s.files, unit
+                )
+            }
+            _ => String::new(),
+        }
+    }
+
+    fn author_name<'a>(&'a self, author: &'a Author) -> &'a str {
+        author.name().unwrap_or(&self.conf.unknown_author)
+    }
+
+    fn author(&self, author: &Author) -> String {
+        let name = self.author_name(author);
+        match (&self.conf.author_link_style, author.email()) {
+            (AuthorLinkStyle::Email, Some(email)) => format!("[{}](mailto:{})", name, email),
+            (AuthorLinkStyle::Profile, Some(email)) => match self.username(email) {
+                Some(user) => format!("[@{0}](https://github.com/{0})", user),
+                None => name.to_string(),
+            },
+            _ => name.to_string(),
+        }
+    }
+
+    // The noreply email has the username. ex) 12345+user@users.noreply.github.com
+    fn username<'a>(&'a self, email: &'a str) -> Option<&'a str> {
+        if let Some(user) = self.conf.author_map.get(email) {
+            return Some(user);
+        }
+        let local = email.strip_suffix("@users.noreply.github.com")?;
+        Some(local.split_once('+').map_or(local, |(_, user)| user))
+    }
+
+    fn heading_style(&self) -> String {
+        let indent = self.conf.root_indent_level;
+        "#".repeat(indent as usize)
+    }
+
+    fn sub_heading_style(&self) -> String {
+        let indent = self.conf.root_indent_level + 1;
+        "#".repeat(indent as usize)
+    }
+}
+
+// The bare URLs in the footers. ex) https://example.com/migration => [https://example.com/migration](https://example.com/migration)
+fn link_urls(s: &str) -> String {
+    lazy_static! {
+        static ref URL: Regex =
+            Regex::new(r"https?://[^\s<>()\[\]]*[^\s<>()\[\].,;:!?'\x22]").unwrap();
+    }
+    URL.replace_all(s, "[$0]($0)").to_string()
+}
+
+// Cut by characters, not bytes
+fn truncate(s: &str, max: usize) -> String {
+    if s.chars().count() <= max {
+        return s.to_string();
+    }
+    let mut cut = s.chars().take(max - 1).collect::<String>();
+    cut.push('…');
+    cut
+}
+
+// Lines under a list item, the blank lines are kept between the paragraphs
+fn indent(body: &str) -> String {
+    body.lines()
+        .map(|l| {
+            if l.trim().is_empty() {
+                String::from("\n")
+            } else {
+                format!("\n  {}", l)
+            }
+        })
+        .collect()
+}
+
+// Character references keep the inline formatting marks literal
+fn escape_asciidoc(s: &str) -> String {
+    s.chars()
+        .map(|c| match c {
+            '*' | '_' | '`' | '#' | '^' | '~' | '+' | '[' | ']' => format!("&#{};", c as u32),
+            _ => c.to_string(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use chrono::Utc;
+
+    use super::*;
+    use crate::git::tests::*;
+
+    #[test]
+    fn all_commit_type_ok() -> Result<()> {
+        let mut commits = Vec::new();
+        let commit = dummy_commit(
+            "2e185faf719f12292414c88872e3397fc5dc4e62",
+            "security",
+            None,
+            false,
+            "fix security",
+            "Test User12 <test-user12@test.com>",
+            "Wed Apr 01 01:01:12 2020 +0000",
+            1,
+            Some("0.2.0"),
+        )?;
+        commits.push(commit);
+
+        let commit = dummy_commit(
+            "1e185faf719f12292414c88872e3397fc5dc4e62",
+            "revert",
+            None,
+            false,
+            "add some",
+            "Test User11 <test-user11@test.com>",
+            "Wed Apr 01 01:01:11 2020 +0000",
+            1,
+            None,
+        )?;
+        commits.push(commit);
+
+        let commit = dummy_commit(
+            "0e185faf719f12292414c88872e3397fc5dc4e62",
+            "test",
+            None,
+            false,
+            "add test",
+            "Test User10 <test-user10@test.com>",
+            "Wed Apr 01 01:01:10 2020 +0000",
+            1,
+            None,
+        )?;
+        commits.push(commit);
+
+        let commit = dummy_commit(
+            "9d185faf719f12292414c88872e3397fc5dc4e62",
+            "perf",
+            None,
+            false,
+            "add perf",
+            "Test User9 <test-user9@test.com>",
+            "Wed Apr 01 01:01:09 2020 +0000",
+            1,
+            None,
+        )?;
+        commits.push(commit);
+
+        let commit = dummy_commit(
+            "8d185faf719f12292414c88872e3397fc5dc4e62",
+            "refactor",
+            None,
+            false,
             "add refactor",
             "Test User8 <test-user8@test.com>",
             "Wed Apr 01 01:01:08 2020 +0000",
@@ -288,210 +1570,2786 @@ mod tests {
         commits.push(commit);
 
         let commit = dummy_commit(
-            "7d185faf719f12292414c88872e3397fc5dc4e62",
-            "style",
+            "7d185faf719f12292414c88872e3397fc5dc4e62",
+            "style",
+            None,
+            false,
+            "add style",
+            "Test User7 <test-user7@test.com>",
+            "Wed Apr 01 01:01:07 2020 +0000",
+            1,
+            None,
+        )?;
+        commits.push(commit);
+
+        let commit = dummy_commit(
+            "6d185faf719f12292414c88872e3397fc5dc4e62",
+            "ci",
+            None,
+            false,
+            "add CI",
+            "Test User6 <test-user6@test.com>",
+            "Wed Apr 01 01:01:06 2020 +0000",
+            1,
+            None,
+        )?;
+        commits.push(commit);
+
+        let commit = dummy_commit(
+            "5d185faf719f12292414c88872e3397fc5dc4e62",
+            "chore",
+            None,
+            false,
+            "add chore",
+            "Test User5 <test-user5@test.com>",
+            "Wed Apr 01 01:01:05 2020 +0000",
+            1,
+            None,
+        )?;
+        commits.push(commit);
+
+        let commit = dummy_commit(
+            "4d185faf719f12292414c88872e3397fc5dc4e62",
+            "doc",
+            None,
+            false,
+            "add doc",
+            "Test User4 <test-user4@test.com>",
+            "Wed Apr 01 01:01:04 2020 +0000",
+            1,
+            None,
+        )?;
+        commits.push(commit);
+
+        let commit = dummy_commit(
+            "3d185faf719f12292414c88872e3397fc5dc4e62",
+            "build",
+            None,
+            false,
+            "add build script",
+            "Test User3 <test-user3@test.com>",
+            "Wed Apr 01 01:01:03 2020 +0000",
+            1,
+            None,
+        )?;
+        commits.push(commit);
+
+        let commit = dummy_commit(
+            "2d185faf719f12292414c88872e3397fc5dc4e62",
+            "fix",
+            None,
+            false,
+            "fix typo",
+            "Test User2 <test-user2@test.com>",
+            "Wed Apr 01 01:01:03 2020 +0000",
+            1,
+            None,
+        )?;
+        commits.push(commit);
+
+        let commit = dummy_commit(
+            "1d185faf719f12292414c88872e3397fc5dc4e62",
+            "feat",
+            None,
+            false,
+            "add README",
+            "Test User1 <test-user1@test.com>",
+            "Wed Apr 01 01:01:01 2020 +0000",
+            1,
+            None,
+        )?;
+        commits.push(commit);
+
+        let commit = dummy_commit(
+            "ad185faf719f12292414c88872e3397fc5dc4e62",
+            "custom",
+            None,
+            false,
+            "add custom",
+            "Test User <test-user@test.com>",
+            "Wed Apr 01 01:01:01 2020 +0000",
+            1,
+            None,
+        )?;
+        commits.push(commit);
+
+        let commit = dummy_invalid_commit(
+            "1d185faf719f12292414c88872e3397fc5dc4e62",
+            "add other",
+            "Test User <test-user1@test.com>",
+            "Wed Apr 01 01:01:01 2020 +0000",
+            None,
+        )?;
+        commits.push(commit);
+
+        let prev = prev()?;
+        let cms = Commits::new(prev, commits);
+        let gurl = GithubUrl::new("https://github.com/watawuwu/ccclog.git");
+        let changelog = Changelog::from(Config {
+            hidden_types: Vec::new(),
+            ..Default::default()
+        });
+        let markdown = changelog.markdown(Some(&gurl), &cms, None)?;
+        let expected = r#"## [0.2.0] - 2020-04-01
+### Feat
+- [[1d185fa]] add README (Test User1)
+
+### Fix
+- [[2d185fa]] fix typo (Test User2)
+
+### Build
+- [[3d185fa]] add build script (Test User3)
+
+### Doc
+- [[4d185fa]] add doc (Test User4)
+
+### Chore
+- [[5d185fa]] add chore (Test User5)
+
+### CI
+- [[6d185fa]] add CI (Test User6)
+
+### Style
+- [[7d185fa]] add style (Test User7)
+
+### Refactor
+- [[8d185fa]] add refactor (Test User8)
+
+### Perf
+- [[9d185fa]] add perf (Test User9)
+
+### Test
+- [[0e185fa]] add test (Test User10)
+
+### Revert
+- [[1e185fa]] add some (Test User11)
+
+### Security
+- [[2e185fa]] fix security (Test User12)
+
+### Custom
+- [[ad185fa]] add custom (Test User)
+
+### Others
+- [[1d185fa]] add other (Test User)
+
+[0.2.0]: https://github.com/watawuwu/ccclog/compare/0.0.0...0.2.0
+[1d185fa]: https://github.com/watawuwu/ccclog/commit/1d185faf719f12292414c88872e3397fc5dc4e62
+[2d185fa]: https://github.com/watawuwu/ccclog/commit/2d185faf719f12292414c88872e3397fc5dc4e62
+[3d185fa]: https://github.com/watawuwu/ccclog/commit/3d185faf719f12292414c88872e3397fc5dc4e62
+[4d185fa]: https://github.com/watawuwu/ccclog/commit/4d185faf719f12292414c88872e3397fc5dc4e62
+[5d185fa]: https://github.com/watawuwu/ccclog/commit/5d185faf719f12292414c88872e3397fc5dc4e62
+[6d185fa]: https://github.com/watawuwu/ccclog/commit/6d185faf719f12292414c88872e3397fc5dc4e62
+[7d185fa]: https://github.com/watawuwu/ccclog/commit/7d185faf719f12292414c88872e3397fc5dc4e62
+[8d185fa]: https://github.com/watawuwu/ccclog/commit/8d185faf719f12292414c88872e3397fc5dc4e62
+[9d185fa]: https://github.com/watawuwu/ccclog/commit/9d185faf719f12292414c88872e3397fc5dc4e62
+[0e185fa]: https://github.com/watawuwu/ccclog/commit/0e185faf719f12292414c88872e3397fc5dc4e62
+[1e185fa]: https://github.com/watawuwu/ccclog/commit/1e185faf719f12292414c88872e3397fc5dc4e62
+[2e185fa]: https://github.com/watawuwu/ccclog/commit/2e185faf719f12292414c88872e3397fc5dc4e62
+[ad185fa]: https://github.com/watawuwu/ccclog/commit/ad185faf719f12292414c88872e3397fc5dc4e62
+[1d185fa]: https://github.com/watawuwu/ccclog/commit/1d185faf719f12292414c88872e3397fc5dc4e62
+"#;
+        assert_eq!(markdown, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn multi_item_ok() -> Result<()> {
+        let mut commits = Vec::new();
+        let commit = dummy_commit(
+            "3d185faf719f12292414c88872e3397fc5dc4e62",
+            "feat",
+            None,
+            false,
+            "add feat3",
+            "Test User3 <test-user3@test.com>",
+            "Wed Apr 01 01:01:03 2020 +0000",
+            1,
+            Some("1.0.0"),
+        )?;
+        commits.push(commit);
+
+        let commit = dummy_commit(
+            "2d185faf719f12292414c88872e3397fc5dc4e62",
+            "feat",
+            None,
+            false,
+            "add feat2",
+            "Test User2 <test-user2@test.com>",
+            "Wed Apr 01 01:01:03 2020 +0000",
+            1,
+            None,
+        )?;
+        commits.push(commit);
+
+        let commit = dummy_commit(
+            "1d185faf719f12292414c88872e3397fc5dc4e62",
+            "feat",
+            None,
+            false,
+            "add feat1",
+            "Test User1 <test-user1@test.com>",
+            "Wed Apr 01 01:01:01 2020 +0000",
+            1,
+            None,
+        )?;
+        commits.push(commit);
+
+        let prev = prev()?;
+        let cms = Commits::new(prev, commits);
+        let changelog = Changelog::new();
+        let gurl = GithubUrl::new("https://github.com/watawuwu/ccclog.git");
+        let markdown = changelog.markdown(Some(&gurl), &cms, None)?;
+        let expected = r#"## [1.0.0] - 2020-04-01
+### Feat
+- [[3d185fa]] add feat3 (Test User3)
+- [[2d185fa]] add feat2 (Test User2)
+- [[1d185fa]] add feat1 (Test User1)
+
+[1.0.0]: https://github.com/watawuwu/ccclog/compare/0.0.0...1.0.0
+[3d185fa]: https://github.com/watawuwu/ccclog/commit/3d185faf719f12292414c88872e3397fc5dc4e62
+[2d185fa]: https://github.com/watawuwu/ccclog/commit/2d185faf719f12292414c88872e3397fc5dc4e62
+[1d185fa]: https://github.com/watawuwu/ccclog/commit/1d185faf719f12292414c88872e3397fc5dc4e62
+"#;
+        assert_eq!(markdown, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn sort_ok() -> Result<()> {
+        let mut commits = Vec::new();
+        let commit = dummy_commit(
+            "4d185faf719f12292414c88872e3397fc5dc4e62",
+            "feat",
+            None,
+            false,
+            "add 4",
+            "Test User <test-user@test.com>",
+            "Wed Apr 01 01:01:04 2020 +0000",
+            1,
+            Some("0.2.0"),
+        )?;
+        commits.push(commit);
+
+        let commit = dummy_commit(
+            "3d185faf719f12292414c88872e3397fc5dc4e62",
+            "feat",
+            None,
+            false,
+            "add 3",
+            "Test User <test-user@test.com>",
+            "Wed Apr 01 01:01:03 2020 +0000",
+            1,
+            None,
+        )?;
+        commits.push(commit);
+
+        let commit = dummy_commit(
+            "2d185faf719f12292414c88872e3397fc5dc4e62",
+            "feat",
+            None,
+            false,
+            "add 2",
+            "Test User <test-user@test.com>",
+            "Wed Apr 01 01:01:02 2020 +0000",
+            1,
+            Some("0.1.0"),
+        )?;
+        commits.push(commit);
+
+        let commit = dummy_commit(
+            "1d185faf719f12292414c88872e3397fc5dc4e62",
+            "feat",
+            None,
+            false,
+            "add 1",
+            "Test User <test-user@test.com>",
+            "Wed Apr 01 01:01:01 2020 +0000",
+            1,
+            None,
+        )?;
+        commits.push(commit);
+
+        let prev = prev()?;
+        let cms = Commits::new(prev, commits);
+        let changelog = Changelog::new();
+        let gurl = GithubUrl::new("https://github.com/watawuwu/ccclog.git");
+        let markdown = changelog.markdown(Some(&gurl), &cms, None)?;
+        let expected = r#"## [0.2.0] - 2020-04-01
+### Feat
+- [[4d185fa]] add 4 (Test User)
+- [[3d185fa]] add 3 (Test User)
+
+## [0.1.0] - 2020-04-01
+### Feat
+- [[2d185fa]] add 2 (Test User)
+- [[1d185fa]] add 1 (Test User)
+
+[0.2.0]: https://github.com/watawuwu/ccclog/compare/0.1.0...0.2.0
+[4d185fa]: https://github.com/watawuwu/ccclog/commit/4d185faf719f12292414c88872e3397fc5dc4e62
+[3d185fa]: https://github.com/watawuwu/ccclog/commit/3d185faf719f12292414c88872e3397fc5dc4e62
+[0.1.0]: https://github.com/watawuwu/ccclog/compare/0.0.0...0.1.0
+[2d185fa]: https://github.com/watawuwu/ccclog/commit/2d185faf719f12292414c88872e3397fc5dc4e62
+[1d185fa]: https://github.com/watawuwu/ccclog/commit/1d185faf719f12292414c88872e3397fc5dc4e62
+"#;
+        assert_eq!(markdown, expected);
+
+        let conf = Config {
+            commit_chronology: Chronology::OldestFirst,
+            ..Default::default()
+        };
+
+        let changelog = Changelog::from(conf);
+        let markdown = changelog.markdown(Some(&gurl), &cms, None)?;
+        let expected = r#"## [0.2.0] - 2020-04-01
+### Feat
+- [[3d185fa]] add 3 (Test User)
+- [[4d185fa]] add 4 (Test User)
+
+## [0.1.0] - 2020-04-01
+### Feat
+- [[1d185fa]] add 1 (Test User)
+- [[2d185fa]] add 2 (Test User)
+
+[0.2.0]: https://github.com/watawuwu/ccclog/compare/0.1.0...0.2.0
+[3d185fa]: https://github.com/watawuwu/ccclog/commit/3d185faf719f12292414c88872e3397fc5dc4e62
+[4d185fa]: https://github.com/watawuwu/ccclog/commit/4d185faf719f12292414c88872e3397fc5dc4e62
+[0.1.0]: https://github.com/watawuwu/ccclog/compare/0.0.0...0.1.0
+[1d185fa]: https://github.com/watawuwu/ccclog/commit/1d185faf719f12292414c88872e3397fc5dc4e62
+[2d185fa]: https://github.com/watawuwu/ccclog/commit/2d185faf719f12292414c88872e3397fc5dc4e62
+"#;
+
+        assert_eq!(markdown, expected);
+
+        Ok(())
+    }
+
+    #[test]
+    fn unreleased_ok() -> Result<()> {
+        let mut commits = Vec::new();
+        let commit = dummy_commit(
+            "1d185faf719f12292414c88872e3397fc5dc4e62",
+            "feat",
+            None,
+            false,
+            "add first",
+            "Test User <test-user@test.com>",
+            "Wed Apr 01 01:01:01 2020 +0000",
+            1,
+            None,
+        )?;
+        commits.push(commit);
+
+        let changelog = Changelog::new();
+        let prev = prev()?;
+        let cms = Commits::new(prev, commits);
+        let gurl = GithubUrl::new("https://github.com/watawuwu/ccclog.git");
+        let markdown = changelog.markdown(Some(&gurl), &cms, None)?;
+        let expected = r#"## [Unreleased]
+### Feat
+- [[1d185fa]] add first (Test User)
+
+[Unreleased]: https://github.com/watawuwu/ccclog/compare/0.0.0...HEAD
+[1d185fa]: https://github.com/watawuwu/ccclog/commit/1d185faf719f12292414c88872e3397fc5dc4e62
+"#;
+        assert_eq!(markdown, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn tag_and_unreleased_ok() -> Result<()> {
+        let mut commits = Vec::new();
+        let commit = dummy_commit(
+            "2d185faf719f12292414c88872e3397fc5dc4e62",
+            "feat",
+            None,
+            false,
+            "add second",
+            "Test User <test-user@test.com>",
+            "Wed Apr 01 01:01:02 2020 +0000",
+            1,
+            None,
+        )?;
+        commits.push(commit);
+
+        let commit = dummy_commit(
+            "1d185faf719f12292414c88872e3397fc5dc4e62",
+            "feat",
+            None,
+            false,
+            "add first",
+            "Test User <test-user@test.com>",
+            "Wed Apr 01 01:01:01 2020 +0000",
+            1,
+            Some("0.1.0"),
+        )?;
+        commits.push(commit);
+
+        let prev = prev()?;
+        let cms = Commits::new(prev, commits);
+        let changelog = Changelog::new();
+        let gurl = GithubUrl::new("https://github.com/watawuwu/ccclog.git");
+        let markdown = changelog.markdown(Some(&gurl), &cms, None)?;
+        let expected = r#"## [Unreleased]
+### Feat
+- [[2d185fa]] add second (Test User)
+
+## [0.1.0] - 2020-04-01
+### Feat
+- [[1d185fa]] add first (Test User)
+
+[Unreleased]: https://github.com/watawuwu/ccclog/compare/0.1.0...HEAD
+[2d185fa]: https://github.com/watawuwu/ccclog/commit/2d185faf719f12292414c88872e3397fc5dc4e62
+[0.1.0]: https://github.com/watawuwu/ccclog/compare/0.0.0...0.1.0
+[1d185fa]: https://github.com/watawuwu/ccclog/commit/1d185faf719f12292414c88872e3397fc5dc4e62
+"#;
+        assert_eq!(markdown, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn scope_ok() -> Result<()> {
+        let mut commits = Vec::new();
+        let commit = dummy_commit(
+            "1d185faf719f12292414c88872e3397fc5dc4e62",
+            "feat",
+            Some("test"),
+            false,
+            "add first",
+            "Test User <test-user@test.com>",
+            "Wed Apr 01 01:01:01 2020 +0000",
+            1,
+            Some("0.1.0"),
+        )?;
+        commits.push(commit);
+
+        let prev = prev()?;
+        let cms = Commits::new(prev, commits);
+        let changelog = Changelog::new();
+        let gurl = GithubUrl::new("https://github.com/watawuwu/ccclog.git");
+        let markdown = changelog.markdown(Some(&gurl), &cms, None)?;
+        let expected = r#"## [0.1.0] - 2020-04-01
+### Feat
+- [[1d185fa]] add first (Test User)
+
+[0.1.0]: https://github.com/watawuwu/ccclog/compare/0.0.0...0.1.0
+[1d185fa]: https://github.com/watawuwu/ccclog/commit/1d185faf719f12292414c88872e3397fc5dc4e62
+"#;
+        assert_eq!(markdown, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn show_scope_ok() -> Result<()> {
+        let mut commits = Vec::new();
+        let commit = dummy_message_commit(
+            "2d185faf719f12292414c88872e3397fc5dc4e62",
+            "feat(parser): add first",
+            "Test User <test-user@test.com>",
+            "Wed Apr 01 01:01:02 2020 +0000",
+            Some("0.1.0"),
+        )?;
+        commits.push(commit);
+
+        let commit = dummy_message_commit(
+            "1d185faf719f12292414c88872e3397fc5dc4e62",
+            "feat: add zero",
+            "Test User <test-user@test.com>",
+            "Wed Apr 01 01:01:01 2020 +0000",
+            None,
+        )?;
+        commits.push(commit);
+
+        let prev = prev()?;
+        let cms = Commits::new(prev, commits);
+        let conf = Config {
+            show_scope: true,
+            ..Default::default()
+        };
+        let changelog = Changelog::from(conf);
+        let gurl = GithubUrl::new("https://github.com/watawuwu/ccclog.git");
+        let markdown = changelog.markdown(Some(&gurl), &cms, None)?;
+        let expected = r#"## [0.1.0] - 2020-04-01
+### Feat
+- [[2d185fa]] **parser:** add first (Test User)
+- [[1d185fa]] add zero (Test User)
+
+[0.1.0]: https://github.com/watawuwu/ccclog/compare/0.0.0...0.1.0
+[2d185fa]: https://github.com/watawuwu/ccclog/commit/2d185faf719f12292414c88872e3397fc5dc4e62
+[1d185fa]: https://github.com/watawuwu/ccclog/commit/1d185faf719f12292414c88872e3397fc5dc4e62
+"#;
+        assert_eq!(markdown, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn no_conventional_commits_ok() -> Result<()> {
+        let mut commits = Vec::new();
+        let commit = dummy_invalid_commit(
+            "1d185faf719f12292414c88872e3397fc5dc4e62",
+            "add first",
+            "Test User <test-user@test.com>",
+            "Wed Apr 01 01:01:01 2020 +0000",
+            Some("0.1.0"),
+        )?;
+        commits.push(commit);
+        let prev = prev()?;
+        let cms = Commits::new(prev, commits);
+        let changelog = Changelog::new();
+        let gurl = GithubUrl::new("https://github.com/watawuwu/ccclog.git");
+        let markdown = changelog.markdown(Some(&gurl), &cms, None)?;
+        let expected = r#"## [0.1.0] - 2020-04-01
+### Others
+- [[1d185fa]] add first (Test User)
+
+[0.1.0]: https://github.com/watawuwu/ccclog/compare/0.0.0...0.1.0
+[1d185fa]: https://github.com/watawuwu/ccclog/commit/1d185faf719f12292414c88872e3397fc5dc4e62
+"#;
+        assert_eq!(markdown, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn others_title_ok() -> Result<()> {
+        let commits = vec![dummy_invalid_commit(
+            "1d185faf719f12292414c88872e3397fc5dc4e62",
+            "add first",
+            "Test User <test-user@test.com>",
+            "Wed Apr 01 01:01:01 2020 +0000",
+            Some("0.1.0"),
+        )?];
+        let cms = Commits::new(prev()?, commits);
+        let conf = Config {
+            others_title: Some(String::from("Miscellaneous")),
+            ..Default::default()
+        };
+        let changelog = Changelog::from(conf);
+        let markdown = changelog.markdown(None, &cms, None)?;
+        let expected = r#"## 0.1.0 - 2020-04-01
+### Miscellaneous
+- [1d185fa] add first (Test User)
+"#;
+        assert_eq!(markdown, expected);
+        assert_eq!(
+            changelog.plain(&cms, None),
+            "0.1.0 - 2020-04-01\nMISCELLANEOUS\n- 1d185fa add first (Test User)\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn hide_others_ok() -> Result<()> {
+        let commits = vec![
+            dummy_invalid_commit(
+                "2d185faf719f12292414c88872e3397fc5dc4e62",
+                "add second",
+                "Test User <test-user@test.com>",
+                "Wed Apr 01 01:01:02 2020 +0000",
+                Some("0.1.0"),
+            )?,
+            dummy_commit(
+                "1d185faf719f12292414c88872e3397fc5dc4e62",
+                "feat",
+                None,
+                false,
+                "add first",
+                "Test User <test-user@test.com>",
+                "Wed Apr 01 01:01:01 2020 +0000",
+                1,
+                None,
+            )?,
+        ];
+        let cms = Commits::new(prev()?, commits);
+        let conf = Config {
+            hide_others: true,
+            ..Default::default()
+        };
+        let changelog = Changelog::from(conf);
+        let gurl = GithubUrl::new("https://github.com/watawuwu/ccclog.git");
+        let markdown = changelog.markdown(Some(&gurl), &cms, None)?;
+        let expected = r#"## [0.1.0] - 2020-04-01
+### Feat
+- [[1d185fa]] add first (Test User)
+
+[0.1.0]: https://github.com/watawuwu/ccclog/compare/0.0.0...0.1.0
+[1d185fa]: https://github.com/watawuwu/ccclog/commit/1d185faf719f12292414c88872e3397fc5dc4e62
+"#;
+        assert_eq!(markdown, expected);
+        assert_eq!(
+            changelog.plain(&cms, None),
+            "0.1.0 - 2020-04-01\nFEAT\n- 1d185fa add first (Test User)\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn release_separator_ok() -> Result<()> {
+        let commits = vec![
+            dummy_commit(
+                "2d185faf719f12292414c88872e3397fc5dc4e62",
+                "fix",
+                None,
+                false,
+                "fix first",
+                "Test User <test-user@test.com>",
+                "Thu Apr 02 01:01:01 2020 +0000",
+                1,
+                Some("0.1.1"),
+            )?,
+            dummy_commit(
+                "1d185faf719f12292414c88872e3397fc5dc4e62",
+                "feat",
+                None,
+                false,
+                "add first",
+                "Test User <test-user@test.com>",
+                "Wed Apr 01 01:01:01 2020 +0000",
+                1,
+                Some("0.1.0"),
+            )?,
+        ];
+        let cms = Commits::new(prev()?, commits);
+        let conf = Config {
+            release_separator: Some(String::from("---")),
+            ..Default::default()
+        };
+        let changelog = Changelog::from(conf);
+        let markdown = changelog.markdown(None, &cms, None)?;
+        let expected = r#"## 0.1.1 - 2020-04-02
+### Fix
+- [2d185fa] fix first (Test User)
+
+---
+
+## 0.1.0 - 2020-04-01
+### Feat
+- [1d185fa] add first (Test User)
+"#;
+        assert_eq!(markdown, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn show_contributor_count_ok() -> Result<()> {
+        let commits = vec![
+            dummy_commit(
+                "3d185faf719f12292414c88872e3397fc5dc4e62",
+                "fix",
+                None,
+                false,
+                "fix 3",
+                "Test User <test-user@test.com>",
+                "Wed Apr 01 01:01:03 2020 +0000",
+                1,
+                Some("0.1.0"),
+            )?,
+            dummy_commit(
+                "2d185faf719f12292414c88872e3397fc5dc4e62",
+                "feat",
+                None,
+                false,
+                "add 2",
+                "Other Name <test-user@test.com>",
+                "Wed Apr 01 01:01:02 2020 +0000",
+                1,
+                None,
+            )?,
+            dummy_commit(
+                "1d185faf719f12292414c88872e3397fc5dc4e62",
+                "feat",
+                None,
+                false,
+                "add 1",
+                "Other User <other-user@test.com>",
+                "Wed Apr 01 01:01:01 2020 +0000",
+                1,
+                None,
+            )?,
+        ];
+        let cms = Commits::new(prev()?, commits);
+        let conf = Config {
+            show_contributor_count: true,
+            ..Default::default()
+        };
+        let changelog = Changelog::from(conf);
+        let markdown = changelog.markdown(None, &cms, None)?;
+        let expected = r#"## 0.1.0 - 2020-04-01 (2 contributors)
+### Feat
+- [2d185fa] add 2 (Other Name)
+- [1d185fa] add 1 (Other User)
+
+### Fix
+- [3d185fa] fix 3 (Test User)
+"#;
+        assert_eq!(markdown, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn show_contributor_count_hidden_ok() -> Result<()> {
+        let commits = vec![
+            dummy_commit(
+                "2d185faf719f12292414c88872e3397fc5dc4e62",
+                "chore",
+                None,
+                false,
+                "bump 2",
+                "Other User <other-user@test.com>",
+                "Wed Apr 01 01:01:02 2020 +0000",
+                1,
+                Some("0.1.0"),
+            )?,
+            dummy_commit(
+                "1d185faf719f12292414c88872e3397fc5dc4e62",
+                "feat",
+                None,
+                false,
+                "add 1",
+                "Test User <test-user@test.com>",
+                "Wed Apr 01 01:01:01 2020 +0000",
+                1,
+                None,
+            )?,
+        ];
+        let cms = Commits::new(prev()?, commits);
+        let conf = Config {
+            show_contributor_count: true,
+            ..Default::default()
+        };
+        // The author of the hidden chore isn't counted
+        let markdown = Changelog::from(conf).markdown(None, &cms, None)?;
+        let expected = r#"## 0.1.0 - 2020-04-01 (1 contributor)
+### Feat
+- [1d185fa] add 1 (Test User)
+"#;
+        assert_eq!(markdown, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn strict_ng() -> Result<()> {
+        let commits = vec![
+            dummy_invalid_commit(
+                "3d185faf719f12292414c88872e3397fc5dc4e62",
+                "add third",
+                "Test User <test-user@test.com>",
+                "Wed Apr 01 01:01:03 2020 +0000",
+                Some("0.1.0"),
+            )?,
+            dummy_commit(
+                "2d185faf719f12292414c88872e3397fc5dc4e62",
+                "feat",
+                None,
+                false,
+                "add 2",
+                "Test User <test-user@test.com>",
+                "Wed Apr 01 01:01:02 2020 +0000",
+                1,
+                None,
+            )?,
+            dummy_invalid_commit(
+                "1d185faf719f12292414c88872e3397fc5dc4e62",
+                "add first",
+                "Test User <test-user@test.com>",
+                "Wed Apr 01 01:01:01 2020 +0000",
+                None,
+            )?,
+        ];
+        let cms = Commits::new(prev()?, commits);
+        let err = Changelog::new().strict(&cms).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "The commits don't follow Conventional Commits. commits: 3d185fa, 1d185fa"
+        );
+        assert_eq!(
+            err.downcast_ref::<ExitError>().map(ExitError::code),
+            Some(exitcode::DATAERR)
+        );
+
+        let conf = Config {
+            ignore_summary: Some(Regex::new("^add (first|third)")?),
+            ..Default::default()
+        };
+        assert!(Changelog::from(conf).strict(&cms).is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn multi_release_ok() -> Result<()> {
+        let mut commits = Vec::new();
+        let commit = dummy_commit(
+            "3d185faf719f12292414c88872e3397fc5dc4e62",
+            "feat",
+            None,
+            false,
+            "add 3",
+            "Test User <test-user@test.com>",
+            "Wed Apr 01 01:01:03 2020 +0000",
+            1,
+            Some("0.3.0"),
+        )?;
+        commits.push(commit);
+
+        let commit = dummy_commit(
+            "2d185faf719f12292414c88872e3397fc5dc4e62",
+            "feat",
+            None,
+            false,
+            "add 2",
+            "Test User <test-user@test.com>",
+            "Wed Apr 01 01:01:02 2020 +0000",
+            1,
+            Some("0.2.0"),
+        )?;
+        commits.push(commit);
+
+        let commit = dummy_commit(
+            "1d185faf719f12292414c88872e3397fc5dc4e62",
+            "feat",
+            None,
+            false,
+            "add 1",
+            "Test User <test-user@test.com>",
+            "Wed Apr 01 01:01:01 2020 +0000",
+            1,
+            Some("0.1.0"),
+        )?;
+        commits.push(commit);
+
+        let prev = prev()?;
+        let cms = Commits::new(prev, commits);
+        let changelog = Changelog::new();
+        let gurl = GithubUrl::new("https://github.com/watawuwu/ccclog.git");
+        let markdown = changelog.markdown(Some(&gurl), &cms, None)?;
+        let expected = r#"## [0.3.0] - 2020-04-01
+### Feat
+- [[3d185fa]] add 3 (Test User)
+
+## [0.2.0] - 2020-04-01
+### Feat
+- [[2d185fa]] add 2 (Test User)
+
+## [0.1.0] - 2020-04-01
+### Feat
+- [[1d185fa]] add 1 (Test User)
+
+[0.3.0]: https://github.com/watawuwu/ccclog/compare/0.2.0...0.3.0
+[3d185fa]: https://github.com/watawuwu/ccclog/commit/3d185faf719f12292414c88872e3397fc5dc4e62
+[0.2.0]: https://github.com/watawuwu/ccclog/compare/0.1.0...0.2.0
+[2d185fa]: https://github.com/watawuwu/ccclog/commit/2d185faf719f12292414c88872e3397fc5dc4e62
+[0.1.0]: https://github.com/watawuwu/ccclog/compare/0.0.0...0.1.0
+[1d185fa]: https://github.com/watawuwu/ccclog/commit/1d185faf719f12292414c88872e3397fc5dc4e62
+"#;
+        assert_eq!(markdown, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn author_link_profile_ok() -> Result<()> {
+        let mut commits = Vec::new();
+        for (i, author) in [
+            "Test User <test-user@test.com>",
+            "Noreply User <12345+noreply@users.noreply.github.com>",
+            "Other User <other@test.com>",
+        ]
+        .iter()
+        .enumerate()
+        {
+            commits.push(dummy_commit(
+                &format!("{}d185faf719f12292414c88872e3397fc5dc4e62", 3 - i),
+                "feat",
+                None,
+                false,
+                &format!("add {}", 3 - i),
+                author,
+                &format!("Wed Apr 01 01:01:0{} 2020 +0000", 3 - i),
+                1,
+                if i == 0 { Some("0.3.0") } else { None },
+            )?);
+        }
+        let cms = Commits::new(prev()?, commits);
+        let conf = Config {
+            author_link_style: AuthorLinkStyle::Profile,
+            author_map: vec![(String::from("test-user@test.com"), String::from("octocat"))]
+                .into_iter()
+                .collect(),
+            ..Default::default()
+        };
+        let markdown = Changelog::from(conf).markdown(None, &cms, None)?;
+        let expected = r#"## 0.3.0 - 2020-04-01
+### Feat
+- [3d185fa] add 3 ([@octocat](https://github.com/octocat))
+- [2d185fa] add 2 ([@noreply](https://github.com/noreply))
+- [1d185fa] add 1 (Other User)
+"#;
+        assert_eq!(markdown, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn enable_email_link_ok() -> Result<()> {
+        let mut commits = Vec::new();
+        let commit = dummy_commit(
+            "3d185faf719f12292414c88872e3397fc5dc4e62",
+            "feat",
+            None,
+            false,
+            "add 3",
+            "Test User <test-user@test.com>",
+            "Wed Apr 01 01:01:03 2020 +0000",
+            1,
+            Some("0.3.0"),
+        )?;
+        commits.push(commit);
+
+        let prev = prev()?;
+        let cms = Commits::new(prev, commits);
+        let conf = Config {
+            author_link_style: AuthorLinkStyle::Email,
+            ..Default::default()
+        };
+
+        let changelog = Changelog::from(conf);
+        let gurl = GithubUrl::new("https://github.com/watawuwu/ccclog.git");
+        let markdown = changelog.markdown(Some(&gurl), &cms, None)?;
+        let expected = r#"## [0.3.0] - 2020-04-01
+### Feat
+- [[3d185fa]] add 3 ([Test User](mailto:test-user@test.com))
+
+[0.3.0]: https://github.com/watawuwu/ccclog/compare/0.0.0...0.3.0
+[3d185fa]: https://github.com/watawuwu/ccclog/commit/3d185faf719f12292414c88872e3397fc5dc4e62
+"#;
+        assert_eq!(markdown, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn root_indent_level_ok() -> Result<()> {
+        let mut commits = Vec::new();
+        let commit = dummy_commit(
+            "3d185faf719f12292414c88872e3397fc5dc4e62",
+            "feat",
+            None,
+            false,
+            "add 3",
+            "Test User <test-user@test.com>",
+            "Wed Apr 01 01:01:03 2020 +0000",
+            1,
+            Some("0.3.0"),
+        )?;
+        commits.push(commit);
+
+        let prev = prev()?;
+
+        let cms = Commits::new(prev, commits);
+        let conf = Config {
+            root_indent_level: 1,
+            ..Default::default()
+        };
+        let changelog = Changelog::from(conf);
+        let gurl = GithubUrl::new("https://github.com/watawuwu/ccclog.git");
+        let markdown = changelog.markdown(Some(&gurl), &cms, None)?;
+        let expected = r#"# [0.3.0] - 2020-04-01
+## Feat
+- [[3d185fa]] add 3 (Test User)
+
+[0.3.0]: https://github.com/watawuwu/ccclog/compare/0.0.0...0.3.0
+[3d185fa]: https://github.com/watawuwu/ccclog/commit/3d185faf719f12292414c88872e3397fc5dc4e62
+"#;
+        assert_eq!(markdown, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn no_remote_ok() -> Result<()> {
+        let mut commits = Vec::new();
+        let commit = dummy_commit(
+            "1d185faf719f12292414c88872e3397fc5dc4e62",
+            "feat",
+            None,
+            false,
+            "add 1",
+            "Test User <test-user@test.com>",
+            "Wed Apr 01 01:01:03 2020 +0000",
+            1,
+            Some("0.3.0"),
+        )?;
+        commits.push(commit);
+
+        let prev = prev()?;
+
+        let cms = Commits::new(prev, commits);
+        let conf = Config {
+            root_indent_level: 1,
+            ..Default::default()
+        };
+        let changelog = Changelog::from(conf);
+        let markdown = changelog.markdown(None, &cms, None)?;
+        let expected = r#"# 0.3.0 - 2020-04-01
+## Feat
+- [1d185fa] add 1 (Test User)
+"#;
+        assert_eq!(markdown, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn custom_ok() -> Result<()> {
+        let mut commits = Vec::new();
+        let commit = dummy_commit(
+            "4d185faf719f12292414c88872e3397fc5dc4e62",
+            "custom2",
+            None,
+            false,
+            "add 4",
+            "Test User <test-user@test.com>",
+            "Wed Apr 01 01:01:03 2020 +0000",
+            1,
+            Some("0.3.0"),
+        )?;
+        commits.push(commit);
+
+        let commit = dummy_commit(
+            "3d185faf719f12292414c88872e3397fc5dc4e62",
+            "custom2",
+            None,
+            false,
+            "add 3",
+            "Test User <test-user@test.com>",
+            "Wed Apr 01 01:01:03 2020 +0000",
+            1,
+            None,
+        )?;
+        commits.push(commit);
+
+        let commit = dummy_commit(
+            "2d185faf719f12292414c88872e3397fc5dc4e62",
+            "custom1",
+            None,
+            false,
+            "add 2",
+            "Test User <test-user@test.com>",
+            "Wed Apr 01 01:01:03 2020 +0000",
+            1,
+            None,
+        )?;
+        commits.push(commit);
+        let commit = dummy_commit(
+            "1d185faf719f12292414c88872e3397fc5dc4e62",
+            "custom1",
+            None,
+            false,
+            "add 1",
+            "Test User <test-user@test.com>",
+            "Wed Apr 01 01:01:03 2020 +0000",
+            1,
+            None,
+        )?;
+        commits.push(commit);
+
+        let prev = prev()?;
+        let cms = Commits::new(prev, commits);
+        let changelog = Changelog::new();
+        let markdown = changelog.markdown(None, &cms, None)?;
+        let expected = r#"## 0.3.0 - 2020-04-01
+### Custom1
+- [2d185fa] add 2 (Test User)
+- [1d185fa] add 1 (Test User)
+
+### Custom2
+- [4d185fa] add 4 (Test User)
+- [3d185fa] add 3 (Test User)
+"#;
+        assert_eq!(markdown, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn ignore_summary_ok() -> Result<()> {
+        let cms = dummy_commits()?;
+        let conf = Config {
+            ignore_summary: Some(Regex::new(r#"^add 3$"#)?),
+            ..Default::default()
+        };
+        let changelog = Changelog::from(conf);
+        let markdown = changelog.markdown(None, &cms, None)?;
+        let expected = r#"## 0.1.0 - 2020-04-01
+### Feat
+- [1d185fa] add 1 (Test User)
+
+### Fix
+- [2d185fa] add 2 (Test User)
+"#;
+        assert_eq!(markdown, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn only_summary_ok() -> Result<()> {
+        let cms = dummy_commits()?;
+        let conf = Config {
+            only_summary: vec![Regex::new(r#"^add 1$"#)?, Regex::new(r#"^add 3$"#)?],
+            hidden_types: Vec::new(),
+            ..Default::default()
+        };
+        let changelog = Changelog::from(conf);
+        let markdown = changelog.markdown(None, &cms, None)?;
+        let expected = r#"## 0.1.0 - 2020-04-01
+### Feat
+- [1d185fa] add 1 (Test User)
+
+### Test
+- [3d185fa] add 3 (Test User)
+"#;
+        assert_eq!(markdown, expected);
+
+        // Applied before the ignore filters
+        let conf = Config {
+            only_summary: vec![Regex::new(r#"^add"#)?],
+            ignore_summary: Some(Regex::new(r#"^add 3$"#)?),
+            hidden_types: Vec::new(),
+            ..Default::default()
+        };
+        let changelog = Changelog::from(conf);
+        let markdown = changelog.markdown(None, &cms, None)?;
+        let expected = r#"## 0.1.0 - 2020-04-01
+### Feat
+- [1d185fa] add 1 (Test User)
+
+### Fix
+- [2d185fa] add 2 (Test User)
+"#;
+        assert_eq!(markdown, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn ignore_types_ok() -> Result<()> {
+        let cms = dummy_commits()?;
+        let conf = Config {
+            ignore_types: Some(vec![CommitType::Fix, CommitType::Test]),
+            ..Default::default()
+        };
+        let changelog = Changelog::from(conf);
+        let markdown = changelog.markdown(None, &cms, None)?;
+        let expected = r#"## 0.1.0 - 2020-04-01
+### Feat
+- [1d185fa] add 1 (Test User)
+"#;
+        assert_eq!(markdown, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn show_breaking_changes_ok() -> Result<()> {
+        let mut commits = Vec::new();
+        let commit = dummy_message_commit(
+            "2d185faf719f12292414c88872e3397fc5dc4e62",
+            "feat!: drop v1 api\n\nBREAKING CHANGE: see https://example.com/migration/v2.",
+            "Test User <test-user@test.com>",
+            "Wed Apr 01 01:01:02 2020 +0000",
+            Some("0.1.0"),
+        )?;
+        commits.push(commit);
+
+        let commit = dummy_message_commit(
+            "1d185faf719f12292414c88872e3397fc5dc4e62",
+            "fix: fix old api",
+            "Test User <test-user@test.com>",
+            "Wed Apr 01 01:01:01 2020 +0000",
+            None,
+        )?;
+        commits.push(commit);
+
+        let cms = Commits::new(prev()?, commits);
+        let conf = Config {
+            show_breaking_changes: true,
+            ..Default::default()
+        };
+        let markdown = Changelog::from(conf).markdown(None, &cms, None)?;
+        let expected = r#"## 0.1.0 - 2020-04-01
+### Breaking Changes
+- [2d185fa] see [https://example.com/migration/v2](https://example.com/migration/v2). (Test User)
+
+### Feat
+- [2d185fa] drop v1 api (Test User)
+
+### Fix
+- [1d185fa] fix old api (Test User)
+"#;
+        assert_eq!(markdown, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn hidden_types_ok() -> Result<()> {
+        let cms = dummy_commits()?;
+        // The test commit is hidden by default
+        let markdown = Changelog::new().markdown(None, &cms, None)?;
+        let expected = r#"## 0.1.0 - 2020-04-01
+### Feat
+- [1d185fa] add 1 (Test User)
+
+### Fix
+- [2d185fa] add 2 (Test User)
+"#;
+        assert_eq!(markdown, expected);
+
+        // The included types are shown
+        let conf = Config {
+            include_types: Some(vec![CommitType::Test]),
+            ..Default::default()
+        };
+        let markdown = Changelog::from(conf).markdown(None, &cms, None)?;
+        let expected = r#"## 0.1.0 - 2020-04-01
+### Test
+- [3d185fa] add 3 (Test User)
+"#;
+        assert_eq!(markdown, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn always_include_breaking_footer_ok() -> Result<()> {
+        let commits = vec![dummy_message_commit(
+            "1d185faf719f12292414c88872e3397fc5dc4e62",
+            "refactor: rename config\n\nBREAKING-CHANGE: the old key is dropped",
+            "Test User <test-user@test.com>",
+            "Wed Apr 01 01:01:01 2020 +0000",
+            Some("0.1.0"),
+        )?];
+        let cms = Commits::new(prev()?, commits);
+        let conf = Config {
+            ignore_types: Some(vec![CommitType::Refactor]),
+            always_include_breaking: true,
+            ..Default::default()
+        };
+        let changelog = Changelog::from(conf);
+        let markdown = changelog.markdown(None, &cms, None)?;
+        let expected = r#"## 0.1.0 - 2020-04-01
+### Refactor
+- [1d185fa] rename config (Test User)
+"#;
+        assert_eq!(markdown, expected);
+        assert!(changelog
+            .json(None, &cms, None)?
+            .contains(r#""breaking": true"#));
+        Ok(())
+    }
+
+    #[test]
+    fn always_include_breaking_ok() -> Result<()> {
+        let mut commits = Vec::new();
+        let commit = dummy_message_commit(
+            "3d185faf719f12292414c88872e3397fc5dc4e62",
+            "refactor!: rename config",
+            "Test User <test-user@test.com>",
+            "Wed Apr 01 01:01:03 2020 +0000",
+            Some("0.1.0"),
+        )?;
+        commits.push(commit);
+
+        let commit = dummy_message_commit(
+            "2d185faf719f12292414c88872e3397fc5dc4e62",
+            "refactor: tidy up",
+            "Test User <test-user@test.com>",
+            "Wed Apr 01 01:01:02 2020 +0000",
+            None,
+        )?;
+        commits.push(commit);
+
+        let commit = dummy_message_commit(
+            "1d185faf719f12292414c88872e3397fc5dc4e62",
+            "feat: add x",
+            "Test User <test-user@test.com>",
+            "Wed Apr 01 01:01:01 2020 +0000",
+            None,
+        )?;
+        commits.push(commit);
+
+        let cms = Commits::new(prev()?, commits);
+        let conf = Config {
+            ignore_types: Some(vec![CommitType::Refactor]),
+            always_include_breaking: true,
+            ..Default::default()
+        };
+        let markdown = Changelog::from(conf).markdown(None, &cms, None)?;
+        let expected = r#"## 0.1.0 - 2020-04-01
+### Feat
+- [1d185fa] add x (Test User)
+
+### Refactor
+- [3d185fa] rename config (Test User)
+"#;
+        assert_eq!(markdown, expected);
+
+        let conf = Config {
+            include_types: Some(vec![CommitType::Feat]),
+            always_include_breaking: true,
+            ..Default::default()
+        };
+        let markdown = Changelog::from(conf).markdown(None, &cms, None)?;
+        assert_eq!(markdown, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn include_types_ok() -> Result<()> {
+        let cms = dummy_commits()?;
+        let conf = Config {
+            include_types: Some(vec![CommitType::Feat, CommitType::Fix]),
+            ..Default::default()
+        };
+        let markdown = Changelog::from(conf).markdown(None, &cms, None)?;
+        let expected = r#"## 0.1.0 - 2020-04-01
+### Feat
+- [1d185fa] add 1 (Test User)
+
+### Fix
+- [2d185fa] add 2 (Test User)
+"#;
+        assert_eq!(markdown, expected);
+
+        let conf = Config {
+            include_types: Some(vec![CommitType::Feat, CommitType::Fix]),
+            ignore_types: Some(vec![CommitType::Fix]),
+            ..Default::default()
+        };
+        let markdown = Changelog::from(conf).markdown(None, &cms, None)?;
+        let expected = r#"## 0.1.0 - 2020-04-01
+### Feat
+- [1d185fa] add 1 (Test User)
+"#;
+        assert_eq!(markdown, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn show_deprecations_ok() -> Result<()> {
+        let mut commits = Vec::new();
+        let commit = dummy_message_commit(
+            "2d185faf719f12292414c88872e3397fc5dc4e62",
+            "feat: add new api\n\nDeprecated: old api is deprecated",
+            "Test User <test-user@test.com>",
+            "Wed Apr 01 01:01:02 2020 +0000",
+            Some("0.1.0"),
+        )?;
+        commits.push(commit);
+
+        let commit = dummy_message_commit(
+            "1d185faf719f12292414c88872e3397fc5dc4e62",
+            "fix: fix old api",
+            "Test User <test-user@test.com>",
+            "Wed Apr 01 01:01:01 2020 +0000",
+            None,
+        )?;
+        commits.push(commit);
+
+        let prev = prev()?;
+        let cms = Commits::new(prev, commits);
+        let conf = Config {
+            show_deprecations: true,
+            ..Default::default()
+        };
+        let changelog = Changelog::from(conf);
+        let gurl = GithubUrl::new("https://github.com/watawuwu/ccclog.git");
+        let markdown = changelog.markdown(Some(&gurl), &cms, None)?;
+        let expected = r#"## [0.1.0] - 2020-04-01
+### Deprecations
+- [[2d185fa]] old api is deprecated (Test User)
+
+### Feat
+- [[2d185fa]] add new api (Test User)
+
+### Fix
+- [[1d185fa]] fix old api (Test User)
+
+[0.1.0]: https://github.com/watawuwu/ccclog/compare/0.0.0...0.1.0
+[2d185fa]: https://github.com/watawuwu/ccclog/commit/2d185faf719f12292414c88872e3397fc5dc4e62
+[1d185fa]: https://github.com/watawuwu/ccclog/commit/1d185faf719f12292414c88872e3397fc5dc4e62
+"#;
+        assert_eq!(markdown, expected);
+
+        let changelog = Changelog::new();
+        let markdown = changelog.markdown(None, &cms, None)?;
+        assert!(!markdown.contains("Deprecations"));
+        Ok(())
+    }
+
+    #[test]
+    fn empty_message_ok() -> Result<()> {
+        let cms = dummy_commits()?;
+        let conf = Config {
+            ignore_types: Some(vec![CommitType::Feat, CommitType::Fix, CommitType::Test]),
+            empty_message: Some(String::from("No notable changes.")),
+            ..Default::default()
+        };
+        let changelog = Changelog::from(conf);
+        let markdown = changelog.markdown(None, &cms, None)?;
+        let expected = r#"## 0.1.0 - 2020-04-01
+No notable changes.
+"#;
+        assert_eq!(markdown, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn show_merge_parents_ok() -> Result<()> {
+        let mut commits = Vec::new();
+        let commit = dummy_invalid_commit(
+            "3d185faf719f12292414c88872e3397fc5dc4e62",
+            "Merge branch 'feature'",
+            "Test User <test-user@test.com>",
+            "Wed Apr 01 01:01:03 2020 +0000",
+            Some("0.1.0"),
+        )?
+        .with_parents(vec![
+            Oid::from_str("1d185faf719f12292414c88872e3397fc5dc4e62")?,
+            Oid::from_str("2d185faf719f12292414c88872e3397fc5dc4e62")?,
+        ]);
+        commits.push(commit);
+
+        let commit = dummy_commit(
+            "2d185faf719f12292414c88872e3397fc5dc4e62",
+            "feat",
+            None,
+            false,
+            "add 2",
+            "Test User <test-user@test.com>",
+            "Wed Apr 01 01:01:02 2020 +0000",
+            1,
+            None,
+        )?;
+        commits.push(commit);
+
+        let prev = prev()?;
+        let cms = Commits::new(prev, commits);
+        let conf = Config {
+            show_merge_parents: true,
+            ..Default::default()
+        };
+        let changelog = Changelog::from(conf);
+        let markdown = changelog.markdown(None, &cms, None)?;
+        let expected = r#"## 0.1.0 - 2020-04-01
+### Feat
+- [2d185fa] add 2 (Test User)
+
+### Others
+- [3d185fa] Merge branch 'feature' (Test User) (merge of 1d185fa, 2d185fa)
+"#;
+        assert_eq!(markdown, expected);
+
+        let changelog = Changelog::new();
+        let markdown = changelog.markdown(None, &cms, None)?;
+        assert!(!markdown.contains("Merge branch"));
+        Ok(())
+    }
+
+    #[test]
+    fn drop_unbounded_first_ok() -> Result<()> {
+        let mut commits = Vec::new();
+        let commit = dummy_commit(
+            "2d185faf719f12292414c88872e3397fc5dc4e62",
+            "feat",
+            None,
+            false,
+            "add second",
+            "Test User <test-user@test.com>",
+            "Wed Apr 01 01:01:02 2020 +0000",
+            1,
+            None,
+        )?;
+        commits.push(commit);
+
+        let commit = dummy_commit(
+            "1d185faf719f12292414c88872e3397fc5dc4e62",
+            "feat",
+            None,
+            false,
+            "add first",
+            "Test User <test-user@test.com>",
+            "Wed Apr 01 01:01:01 2020 +0000",
+            1,
+            Some("0.1.0"),
+        )?;
+        commits.push(commit);
+
+        let conf = Config {
+            drop_unbounded_first: true,
+            ..Default::default()
+        };
+        let changelog = Changelog::from(conf);
+
+        let cms = Commits::new(Commit::empty(Utc::now())?, commits.clone());
+        let markdown = changelog.markdown(None, &cms, None)?;
+        let expected = r#"## Unreleased
+### Feat
+- [2d185fa] add second (Test User)
+"#;
+        assert_eq!(markdown, expected);
+
+        let cms = Commits::new(prev()?, commits);
+        let markdown = changelog.markdown(None, &cms, None)?;
+        let expected = r#"## Unreleased
+### Feat
+- [2d185fa] add second (Test User)
+
+## 0.1.0 - 2020-04-01
+### Feat
+- [1d185fa] add first (Test User)
+"#;
+        assert_eq!(markdown, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn show_files_link_ok() -> Result<()> {
+        let cms = dummy_commits()?;
+        let conf = Config {
+            show_files_link: true,
+            ignore_types: Some(vec![CommitType::Fix, CommitType::Test]),
+            ..Default::default()
+        };
+        let changelog = Changelog::from(conf);
+        let gurl = GithubUrl::new("https://github.com/watawuwu/ccclog.git");
+        let markdown = changelog.markdown(Some(&gurl), &cms, None)?;
+        let expected = r#"## [0.1.0] - 2020-04-01
+### Feat
+- [[1d185fa]] add 1 (Test User) ([files](https://github.com/watawuwu/ccclog/commit/1d185faf719f12292414c88872e3397fc5dc4e62.diff))
+
+[0.1.0]: https://github.com/watawuwu/ccclog/compare/0.0.0...0.1.0
+[1d185fa]: https://github.com/watawuwu/ccclog/commit/1d185faf719f12292414c88872e3397fc5dc4e62
+"#;
+        assert_eq!(markdown, expected);
+
+        // No link without remote
+        let markdown = changelog.markdown(None, &cms, None)?;
+        assert!(!markdown.contains("files"));
+        Ok(())
+    }
+
+    fn revert_commits() -> Result<Commits> {
+        let mut commits = Vec::new();
+        let commit = dummy_message_commit(
+            "3d185faf719f12292414c88872e3397fc5dc4e62",
+            "Revert \"feat: add 1\"\n\nThis reverts commit 1d185faf719f12292414c88872e3397fc5dc4e62.",
+            "Test User <test-user@test.com>",
+            "Wed Apr 01 01:01:03 2020 +0000",
+            Some("0.1.0"),
+        )?;
+        commits.push(commit);
+
+        let commit = dummy_commit(
+            "2d185faf719f12292414c88872e3397fc5dc4e62",
+            "fix",
+            None,
+            false,
+            "add 2",
+            "Test User <test-user@test.com>",
+            "Wed Apr 01 01:01:02 2020 +0000",
+            1,
+            None,
+        )?;
+        commits.push(commit);
+
+        let commit = dummy_commit(
+            "1d185faf719f12292414c88872e3397fc5dc4e62",
+            "feat",
+            None,
+            false,
+            "add 1",
+            "Test User <test-user@test.com>",
+            "Wed Apr 01 01:01:01 2020 +0000",
+            1,
+            None,
+        )?;
+        commits.push(commit);
+
+        Ok(Commits::new(prev()?, commits))
+    }
+
+    #[test]
+    fn revert_handling_ok() -> Result<()> {
+        let cms = revert_commits()?;
+
+        let changelog = Changelog::new();
+        let markdown = changelog.markdown(None, &cms, None)?;
+        let expected = r#"## 0.1.0 - 2020-04-01
+### Feat
+- [1d185fa] add 1 (Test User)
+
+### Fix
+- [2d185fa] add 2 (Test User)
+
+### Revert
+- [3d185fa] Revert "feat: add 1" (Test User)
+"#;
+        assert_eq!(markdown, expected);
+
+        let conf = Config {
+            revert_handling: RevertHandling::Drop,
+            ..Default::default()
+        };
+        let changelog = Changelog::from(conf);
+        let markdown = changelog.markdown(None, &cms, None)?;
+        let expected = r#"## 0.1.0 - 2020-04-01
+### Fix
+- [2d185fa] add 2 (Test User)
+"#;
+        assert_eq!(markdown, expected);
+
+        let conf = Config {
+            revert_handling: RevertHandling::Annotate,
+            ..Default::default()
+        };
+        let changelog = Changelog::from(conf);
+        let markdown = changelog.markdown(None, &cms, None)?;
+        let expected = r#"## 0.1.0 - 2020-04-01
+### Feat
+- [1d185fa] add 1 (reverted in 3d185fa) (Test User)
+
+### Fix
+- [2d185fa] add 2 (Test User)
+
+### Revert
+- [3d185fa] Revert "feat: add 1" (Test User)
+"#;
+        assert_eq!(markdown, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn revert_out_of_range_ok() -> Result<()> {
+        let commits = vec![dummy_message_commit(
+            "3d185faf719f12292414c88872e3397fc5dc4e62",
+            "Revert \"feat: add 0\"\n\nThis reverts commit 0d185faf719f12292414c88872e3397fc5dc4e62.",
+            "Test User <test-user@test.com>",
+            "Wed Apr 01 01:01:03 2020 +0000",
+            Some("0.1.0"),
+        )?];
+        let cms = Commits::new(prev()?, commits);
+
+        // Nothing to cancel out, the revert is kept
+        let conf = Config {
+            revert_handling: RevertHandling::Drop,
+            ..Default::default()
+        };
+        let markdown = Changelog::from(conf).markdown(None, &cms, None)?;
+        let expected = r#"## 0.1.0 - 2020-04-01
+### Revert
+- [3d185fa] Revert "feat: add 0" (Test User)
+"#;
+        assert_eq!(markdown, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn by_author_ok() -> Result<()> {
+        let mut commits = Vec::new();
+        let commit = dummy_commit(
+            "3d185faf719f12292414c88872e3397fc5dc4e62",
+            "fix",
+            None,
+            false,
+            "fix 1",
+            "Alice <alice@test.com>",
+            "Wed Apr 01 01:01:03 2020 +0000",
+            1,
+            Some("0.2.0"),
+        )?;
+        commits.push(commit);
+
+        let commit = dummy_commit(
+            "2d185faf719f12292414c88872e3397fc5dc4e62",
+            "feat",
+            None,
+            false,
+            "add 2",
+            "Bob <bob@test.com>",
+            "Wed Apr 01 01:01:02 2020 +0000",
+            1,
+            None,
+        )?;
+        commits.push(commit);
+
+        let commit = dummy_commit(
+            "1d185faf719f12292414c88872e3397fc5dc4e62",
+            "feat",
+            None,
+            false,
+            "add 1",
+            "Alice <alice@test.com>",
+            "Wed Apr 01 01:01:01 2020 +0000",
+            1,
+            Some("0.1.0"),
+        )?;
+        commits.push(commit);
+
+        let conf = Config {
+            by_author: true,
+            ..Default::default()
+        };
+        let changelog = Changelog::from(conf);
+        let markdown = changelog.markdown(None, &Commits::new(prev()?, commits), None)?;
+        let expected = r#"## Alice
+### Feat
+- [1d185fa] add 1 (Alice)
+
+### Fix
+- [3d185fa] fix 1 (Alice)
+
+## Bob
+### Feat
+- [2d185fa] add 2 (Bob)
+"#;
+        assert_eq!(markdown, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn dedupe_ok() -> Result<()> {
+        let mut commits = Vec::new();
+        let commit = dummy_commit(
+            "2d185faf719f12292414c88872e3397fc5dc4e62",
+            "feat",
+            None,
+            false,
+            "add x ",
+            "Test User <test-user@test.com>",
+            "Wed Apr 01 01:01:02 2020 +0000",
+            1,
+            Some("0.1.0"),
+        )?;
+        commits.push(commit);
+
+        let commit = dummy_commit(
+            "1d185faf719f12292414c88872e3397fc5dc4e62",
+            "feat",
+            None,
+            false,
+            "Add X",
+            "Test User <test-user@test.com>",
+            "Wed Apr 01 01:01:01 2020 +0000",
+            1,
+            None,
+        )?;
+        commits.push(commit);
+        let cms = Commits::new(prev()?, commits);
+
+        let conf = Config {
+            dedupe: true,
+            ..Default::default()
+        };
+        let changelog = Changelog::from(conf);
+        let markdown = changelog.markdown(None, &cms, None)?;
+        assert!(markdown.contains("[1d185fa] Add X"));
+
+        let conf = Config {
+            dedupe: true,
+            dedupe_normalize: true,
+            ..Default::default()
+        };
+        let changelog = Changelog::from(conf);
+        let markdown = changelog.markdown(None, &cms, None)?;
+        let expected = r#"## 0.1.0 - 2020-04-01
+### Feat
+- [2d185fa] add x  (Test User)
+"#;
+        assert_eq!(markdown, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn dedupe_per_release_ok() -> Result<()> {
+        let mut commits = Vec::new();
+        for (id, author, tag) in [
+            ("4", "Test User <test-user@test.com>", Some("0.2.0")),
+            ("3", "Test User <test-user@test.com>", None),
+            ("2", "Other User <other-user@test.com>", None),
+            ("1", "Test User <test-user@test.com>", Some("0.1.0")),
+        ] {
+            commits.push(dummy_message_commit(
+                &format!("{}d185faf719f12292414c88872e3397fc5dc4e62", id),
+                "fix: fix typo",
+                author,
+                &format!("Wed Apr 01 01:01:0{} 2020 +0000", id),
+                tag,
+            )?);
+        }
+        let conf = Config {
+            dedupe: true,
+            ..Default::default()
+        };
+        let markdown =
+            Changelog::from(conf).markdown(None, &Commits::new(prev()?, commits), None)?;
+        let expected = r#"## 0.2.0 - 2020-04-01
+### Fix
+- [4d185fa] fix typo (Test User)
+- [2d185fa] fix typo (Other User)
+
+## 0.1.0 - 2020-04-01
+### Fix
+- [1d185fa] fix typo (Test User)
+"#;
+        assert_eq!(markdown, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn porcelain_ok() -> Result<()> {
+        let mut commits = Vec::new();
+        let commit = dummy_commit(
+            "3d185faf719f12292414c88872e3397fc5dc4e62",
+            "fix",
+            Some("api"),
+            false,
+            "fix\ttab",
+            "Test User <test-user@test.com>",
+            "Wed Apr 01 01:01:03 2020 +0000",
+            1,
+            None,
+        )?;
+        commits.push(commit);
+
+        let commit = dummy_commit(
+            "2d185faf719f12292414c88872e3397fc5dc4e62",
+            "deps",
+            None,
+            false,
+            "bump x",
+            "Test User <test-user@test.com>",
+            "Wed Apr 01 01:01:02 2020 +0000",
+            1,
+            Some("0.1.0"),
+        )?;
+        commits.push(commit);
+
+        let commit = dummy_invalid_commit(
+            "1d185faf719f12292414c88872e3397fc5dc4e62",
+            "add 1",
+            "Test User <test-user@test.com>",
+            "Wed Apr 01 01:01:01 2020 +0000",
+            None,
+        )?;
+        commits.push(commit);
+
+        let changelog = Changelog::new();
+        let a = changelog.porcelain(&Commits::new(prev()?, commits), None);
+        let e = "Unreleased\tfix\t3d185fa\tTest User\tfix tab
+0.1.0\tdeps\t2d185fa\tTest User\tbump x
+0.1.0\tothers\t1d185fa\tTest User\tadd 1
+";
+        assert_eq!(a, e);
+        Ok(())
+    }
+
+    #[test]
+    fn porcelain_filtered_ok() -> Result<()> {
+        let mut commits = Vec::new();
+        let commit = dummy_commit(
+            "3d185faf719f12292414c88872e3397fc5dc4e62",
+            "chore",
+            None,
+            false,
+            "bump 3",
+            "Test User <test-user@test.com>",
+            "Wed Apr 01 01:01:03 2020 +0000",
+            1,
+            None,
+        )?;
+        commits.push(commit);
+
+        let commit = dummy_commit(
+            "2d185faf719f12292414c88872e3397fc5dc4e62",
+            "fix",
+            None,
+            false,
+            "fix 2",
+            "Test User <test-user@test.com>",
+            "Wed Apr 01 01:01:02 2020 +0000",
+            1,
+            Some("0.1.0"),
+        )?;
+        commits.push(commit);
+
+        let commit = dummy_invalid_commit(
+            "1d185faf719f12292414c88872e3397fc5dc4e62",
+            "add 1",
+            "Test User <test-user@test.com>",
+            "Wed Apr 01 01:01:01 2020 +0000",
+            None,
+        )?;
+        commits.push(commit);
+
+        // The hidden chore and the hidden others are dropped like the markdown
+        let conf = Config {
+            hide_others: true,
+            ..Default::default()
+        };
+        let changelog = Changelog::from(conf);
+        let a = changelog.porcelain(&Commits::new(prev()?, commits), None);
+        let e = "0.1.0\tfix\t2d185fa\tTest User\tfix 2\n";
+        assert_eq!(a, e);
+        Ok(())
+    }
+
+    #[test]
+    fn unreleased_highest_version_ok() -> Result<()> {
+        let mut commits = Vec::new();
+        let commit = dummy_commit(
+            "3d185faf719f12292414c88872e3397fc5dc4e62",
+            "feat",
             None,
             false,
-            "add style",
-            "Test User7 <test-user7@test.com>",
-            "Wed Apr 01 01:01:07 2020 +0000",
+            "add 3",
+            "Test User <test-user@test.com>",
+            "Wed Apr 01 01:01:03 2020 +0000",
             1,
             None,
         )?;
         commits.push(commit);
 
         let commit = dummy_commit(
-            "6d185faf719f12292414c88872e3397fc5dc4e62",
-            "ci",
+            "2d185faf719f12292414c88872e3397fc5dc4e62",
+            "fix",
             None,
             false,
-            "add CI",
-            "Test User6 <test-user6@test.com>",
-            "Wed Apr 01 01:01:06 2020 +0000",
+            "fix 2",
+            "Test User <test-user@test.com>",
+            "Wed Apr 01 01:01:02 2020 +0000",
+            1,
+            Some("1.0.1"),
+        )?;
+        commits.push(commit);
+
+        let commit = dummy_commit(
+            "1d185faf719f12292414c88872e3397fc5dc4e62",
+            "feat",
+            None,
+            false,
+            "add 1",
+            "Test User <test-user@test.com>",
+            "Wed Apr 01 01:01:01 2020 +0000",
             1,
+            Some("2.0.0"),
+        )?;
+        commits.push(commit);
+
+        let cms = Commits::new(prev()?, commits);
+        assert_eq!(
+            cms.unreleased_base(None).map(|o| o.name()),
+            Some(String::from("2.0.0"))
+        );
+
+        let changelog = Changelog::new();
+        let gurl = GithubUrl::new("https://github.com/watawuwu/ccclog.git");
+        let markdown = changelog.markdown(Some(&gurl), &cms, None)?;
+        assert!(markdown
+            .contains("[Unreleased]: https://github.com/watawuwu/ccclog/compare/2.0.0...HEAD\n"));
+        Ok(())
+    }
+
+    #[test]
+    fn unknown_author_ok() -> Result<()> {
+        let commits = vec![dummy_commit(
+            "1d185faf719f12292414c88872e3397fc5dc4e62",
+            "feat",
             None,
+            false,
+            "add 1",
+            "",
+            "Wed Apr 01 01:01:01 2020 +0000",
+            1,
+            Some("0.1.0"),
+        )?];
+        let cms = Commits::new(prev()?, commits);
+
+        let markdown = Changelog::new().markdown(None, &cms, None)?;
+        assert!(markdown.contains("- [1d185fa] add 1 (Unknown)\n"));
+
+        let conf = Config {
+            unknown_author: String::from("Anonymous"),
+            ..Default::default()
+        };
+        let markdown = Changelog::from(conf).markdown(None, &cms, None)?;
+        assert!(markdown.contains("- [1d185fa] add 1 (Anonymous)\n"));
+        Ok(())
+    }
+
+    #[test]
+    fn group_by_complex_scope_ok() -> Result<()> {
+        let commits = vec![
+            dummy_message_commit(
+                "3d185faf719f12292414c88872e3397fc5dc4e62",
+                r#"feat("user profile"): add 3"#,
+                "Test User <test-user@test.com>",
+                "Wed Apr 01 01:01:03 2020 +0000",
+                Some("0.1.0"),
+            )?,
+            dummy_message_commit(
+                "2d185faf719f12292414c88872e3397fc5dc4e62",
+                "feat(ui/login): add 2",
+                "Test User <test-user@test.com>",
+                "Wed Apr 01 01:01:02 2020 +0000",
+                None,
+            )?,
+            dummy_message_commit(
+                "1d185faf719f12292414c88872e3397fc5dc4e62",
+                "feat(user profile): add 1",
+                "Test User <test-user@test.com>",
+                "Wed Apr 01 01:01:01 2020 +0000",
+                None,
+            )?,
+        ];
+
+        let conf = Config {
+            group_by: GroupKey::TypeScope,
+            ..Default::default()
+        };
+        let changelog = Changelog::from(conf);
+        let markdown = changelog.markdown(None, &Commits::new(prev()?, commits), None)?;
+        let expected = r#"## 0.1.0 - 2020-04-01
+### Feat (ui/login)
+- [2d185fa] add 2 (Test User)
+
+### Feat (user profile)
+- [3d185fa] add 3 (Test User)
+- [1d185fa] add 1 (Test User)
+"#;
+        assert_eq!(markdown, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn group_by_type_scope_ok() -> Result<()> {
+        let mut commits = Vec::new();
+        let commit = dummy_commit(
+            "3d185faf719f12292414c88872e3397fc5dc4e62",
+            "feat",
+            Some("cli"),
+            false,
+            "add 3",
+            "Test User <test-user@test.com>",
+            "Wed Apr 01 01:01:03 2020 +0000",
+            1,
+            Some("0.1.0"),
         )?;
         commits.push(commit);
 
         let commit = dummy_commit(
-            "5d185faf719f12292414c88872e3397fc5dc4e62",
-            "chore",
+            "2d185faf719f12292414c88872e3397fc5dc4e62",
+            "feat",
             None,
             false,
-            "add chore",
-            "Test User5 <test-user5@test.com>",
-            "Wed Apr 01 01:01:05 2020 +0000",
+            "add 2",
+            "Test User <test-user@test.com>",
+            "Wed Apr 01 01:01:02 2020 +0000",
             1,
             None,
         )?;
         commits.push(commit);
 
         let commit = dummy_commit(
-            "4d185faf719f12292414c88872e3397fc5dc4e62",
-            "doc",
+            "1d185faf719f12292414c88872e3397fc5dc4e62",
+            "feat",
+            Some("api"),
+            false,
+            "add 1",
+            "Test User <test-user@test.com>",
+            "Wed Apr 01 01:01:01 2020 +0000",
+            1,
+            None,
+        )?;
+        commits.push(commit);
+
+        let conf = Config {
+            group_by: GroupKey::TypeScope,
+            ..Default::default()
+        };
+        let changelog = Changelog::from(conf);
+        let markdown = changelog.markdown(None, &Commits::new(prev()?, commits.clone()), None)?;
+        let expected = r#"## 0.1.0 - 2020-04-01
+### Feat
+- [2d185fa] add 2 (Test User)
+
+### Feat (api)
+- [1d185fa] add 1 (Test User)
+
+### Feat (cli)
+- [3d185fa] add 3 (Test User)
+"#;
+        assert_eq!(markdown, expected);
+
+        let conf = Config {
+            group_by: GroupKey::TypeScope,
+            scopeless_position: ScopelessPosition::Last,
+            ..Default::default()
+        };
+        let changelog = Changelog::from(conf);
+        let markdown = changelog.markdown(None, &Commits::new(prev()?, commits), None)?;
+        let expected = r#"## 0.1.0 - 2020-04-01
+### Feat (api)
+- [1d185fa] add 1 (Test User)
+
+### Feat (cli)
+- [3d185fa] add 3 (Test User)
+
+### Feat
+- [2d185fa] add 2 (Test User)
+"#;
+        assert_eq!(markdown, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn full_hash_ok() -> Result<()> {
+        let commits = vec![dummy_commit(
+            "1d185faf719f12292414c88872e3397fc5dc4e62",
+            "feat",
             None,
             false,
-            "add doc",
-            "Test User4 <test-user4@test.com>",
-            "Wed Apr 01 01:01:04 2020 +0000",
+            "add 1",
+            "Test User <test-user@test.com>",
+            "Wed Apr 01 01:01:01 2020 +0000",
             1,
+            Some("0.1.0"),
+        )?];
+
+        let conf = Config {
+            full_hash: true,
+            ..Default::default()
+        };
+        let changelog = Changelog::from(conf);
+        let gurl = GithubUrl::new("https://github.com/watawuwu/ccclog.git");
+        let markdown = changelog.markdown(Some(&gurl), &Commits::new(prev()?, commits), None)?;
+        let expected = r#"## [0.1.0] - 2020-04-01
+### Feat
+- [[1d185faf719f12292414c88872e3397fc5dc4e62]] add 1 (Test User)
+
+[0.1.0]: https://github.com/watawuwu/ccclog/compare/0.0.0...0.1.0
+[1d185faf719f12292414c88872e3397fc5dc4e62]: https://github.com/watawuwu/ccclog/commit/1d185faf719f12292414c88872e3397fc5dc4e62
+"#;
+        assert_eq!(markdown, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn asciidoc_ok() -> Result<()> {
+        let mut commits = Vec::new();
+        let commit = dummy_commit(
+            "2d185faf719f12292414c88872e3397fc5dc4e62",
+            "feat",
             None,
+            false,
+            "add *bold* [x]",
+            "Test User <test-user@test.com>",
+            "Wed Apr 01 01:01:02 2020 +0000",
+            1,
+            Some("0.2.0"),
         )?;
         commits.push(commit);
 
         let commit = dummy_commit(
-            "3d185faf719f12292414c88872e3397fc5dc4e62",
-            "build",
+            "1d185faf719f12292414c88872e3397fc5dc4e62",
+            "fix",
             None,
             false,
-            "add build script",
-            "Test User3 <test-user3@test.com>",
+            "fix 1",
+            "Test User <test-user@test.com>",
+            "Wed Apr 01 01:01:01 2020 +0000",
+            1,
+            None,
+        )?;
+        commits.push(commit);
+
+        let changelog = Changelog::new();
+        let gurl = GithubUrl::new("https://github.com/watawuwu/ccclog.git");
+        let a = changelog.asciidoc(Some(&gurl), &Commits::new(prev()?, commits), None)?;
+        let e = r#"== link:https://github.com/watawuwu/ccclog/compare/0.0.0...0.2.0[0.2.0] - 2020-04-01
+=== Feat
+* link:https://github.com/watawuwu/ccclog/commit/2d185faf719f12292414c88872e3397fc5dc4e62[2d185fa] add &#42;bold&#42; &#91;x&#93; (Test User)
+
+=== Fix
+* link:https://github.com/watawuwu/ccclog/commit/1d185faf719f12292414c88872e3397fc5dc4e62[1d185fa] fix 1 (Test User)
+"#;
+        assert_eq!(a, e);
+        Ok(())
+    }
+
+    #[test]
+    fn asciidoc_sections_ok() -> Result<()> {
+        let mut commits = Vec::new();
+        let commit = dummy_message_commit(
+            "3d185faf719f12292414c88872e3397fc5dc4e62",
+            "chore: bump x",
+            "Test User <test-user@test.com>",
             "Wed Apr 01 01:01:03 2020 +0000",
+            Some("0.2.0"),
+        )?;
+        commits.push(commit);
+
+        let commit = dummy_message_commit(
+            "2d185faf719f12292414c88872e3397fc5dc4e62",
+            "feat: add x\n\nDeprecated: the old *x*",
+            "Test User <test-user@test.com>",
+            "Wed Apr 01 01:01:02 2020 +0000",
+            Some("0.1.0"),
+        )?;
+        commits.push(commit);
+
+        let commit = dummy_message_commit(
+            "1d185faf719f12292414c88872e3397fc5dc4e62",
+            "fix: fix y",
+            "Test User <test-user@test.com>",
+            "Wed Apr 01 01:01:01 2020 +0000",
+            None,
+        )?;
+        commits.push(commit);
+
+        let conf = Config {
+            show_deprecations: true,
+            type_order: Some(vec![CommitType::Fix]),
+            empty_message: Some(String::from("No notable changes.")),
+            ..Default::default()
+        };
+        let a = Changelog::from(conf).asciidoc(None, &Commits::new(prev()?, commits), None)?;
+        let e = r#"== 0.2.0 - 2020-04-01
+No notable changes.
+
+== 0.1.0 - 2020-04-01
+=== Deprecations
+* 2d185fa the old &#42;x&#42; (Test User)
+
+=== Fix
+* 1d185fa fix y (Test User)
+
+=== Feat
+* 2d185fa add x (Test User)
+"#;
+        assert_eq!(a, e);
+        Ok(())
+    }
+
+    #[test]
+    fn full_changelog_footer_ok() -> Result<()> {
+        let mut commits = Vec::new();
+        let commit = dummy_commit(
+            "2d185faf719f12292414c88872e3397fc5dc4e62",
+            "feat",
+            None,
+            false,
+            "add 2",
+            "Test User <test-user@test.com>",
+            "Wed Apr 01 01:01:02 2020 +0000",
             1,
-            None,
+            Some("0.2.0"),
         )?;
         commits.push(commit);
 
         let commit = dummy_commit(
-            "2d185faf719f12292414c88872e3397fc5dc4e62",
+            "1d185faf719f12292414c88872e3397fc5dc4e62",
             "fix",
             None,
             false,
-            "fix typo",
-            "Test User2 <test-user2@test.com>",
-            "Wed Apr 01 01:01:03 2020 +0000",
+            "fix 1",
+            "Test User <test-user@test.com>",
+            "Wed Apr 01 01:01:01 2020 +0000",
             1,
-            None,
+            Some("0.1.0"),
         )?;
         commits.push(commit);
 
-        let commit = dummy_commit(
+        let conf = Config {
+            full_changelog_footer: true,
+            ..Default::default()
+        };
+        let gurl = GithubUrl::new("https://github.com/watawuwu/ccclog.git");
+        let cms = Commits::new(prev()?, commits);
+        let markdown = Changelog::from(conf).markdown(Some(&gurl), &cms, None)?;
+        let expected = r#"## [0.2.0] - 2020-04-01
+### Feat
+- [[2d185fa]] add 2 (Test User)
+
+## [0.1.0] - 2020-04-01
+### Fix
+- [[1d185fa]] fix 1 (Test User)
+
+[0.2.0]: https://github.com/watawuwu/ccclog/compare/0.1.0...0.2.0
+[2d185fa]: https://github.com/watawuwu/ccclog/commit/2d185faf719f12292414c88872e3397fc5dc4e62
+[0.1.0]: https://github.com/watawuwu/ccclog/compare/0.0.0...0.1.0
+[1d185fa]: https://github.com/watawuwu/ccclog/commit/1d185faf719f12292414c88872e3397fc5dc4e62
+
+**Full Changelog**: https://github.com/watawuwu/ccclog/compare/0.0.0...0.2.0
+"#;
+        assert_eq!(markdown, expected);
+
+        // Nothing to link without the remote
+        let markdown = Changelog::from(Config {
+            full_changelog_footer: true,
+            ..Default::default()
+        })
+        .markdown(None, &cms, None)?;
+        assert!(!markdown.contains("Full Changelog"));
+        Ok(())
+    }
+
+    #[test]
+    fn discord_ok() -> Result<()> {
+        let mut commits = Vec::new();
+        for i in (1..=40).rev() {
+            let commit = dummy_commit(
+                &format!("{:02}185faf719f12292414c88872e3397fc5dc4e62", i),
+                "feat",
+                None,
+                false,
+                &format!("add {}", i),
+                "Test User <test-user@test.com>",
+                &format!("Wed Apr 01 01:01:{:02} 2020 +0000", i),
+                1,
+                if i == 40 { Some("0.2.0") } else { None },
+            )?;
+            commits.push(commit);
+        }
+
+        let changelog = Changelog::new();
+        let gurl = GithubUrl::new("https://github.com/watawuwu/ccclog.git");
+        let a = changelog.discord(Some(&gurl), &Commits::new(prev()?, commits), None)?;
+        assert!(a.starts_with(
+            "## [0.2.0](<https://github.com/watawuwu/ccclog/compare/0.0.0...0.2.0>) - 2020-04-01\n### Feat\n- [40185fa](<https://github.com/watawuwu/ccclog/commit/40185faf719f12292414c88872e3397fc5dc4e62>) add 40 (Test User)\n"
+        ));
+        assert!(!a.contains("]: "));
+
+        let blocks = a
+            .split(&format!("{}\n", DISCORD_MARKER))
+            .collect::<Vec<_>>();
+        assert_eq!(blocks.len(), 3);
+        assert!(blocks.iter().all(|b| b.chars().count() <= DISCORD_LIMIT));
+        assert_eq!(blocks.concat().lines().count(), 42);
+        Ok(())
+    }
+
+    #[test]
+    fn timezone_ok() -> Result<()> {
+        // 2020-04-01T23:00:00Z
+        let commits = vec![dummy_commit(
             "1d185faf719f12292414c88872e3397fc5dc4e62",
             "feat",
             None,
             false,
-            "add README",
-            "Test User1 <test-user1@test.com>",
-            "Wed Apr 01 01:01:01 2020 +0000",
+            "add 1",
+            "Test User <test-user@test.com>",
+            "Thu Apr 02 08:00:00 2020 +0900",
             1,
-            None,
+            Some("0.1.0"),
+        )?];
+        let cms = Commits::new(prev()?, commits);
+
+        let markdown = Changelog::new().markdown(None, &cms, None)?;
+        assert!(markdown.starts_with("## 0.1.0 - 2020-04-02\n"));
+
+        let conf = Config {
+            timezone: Some(FixedOffset::east(0)),
+            ..Default::default()
+        };
+        let markdown = Changelog::from(conf).markdown(None, &cms, None)?;
+        assert!(markdown.starts_with("## 0.1.0 - 2020-04-01\n"));
+        Ok(())
+    }
+
+    #[test]
+    fn date_format_ok() -> Result<()> {
+        let cms = dummy_commits()?;
+        let conf = Config {
+            date_format: Some(String::from("%d/%m/%Y")),
+            ..Default::default()
+        };
+        let markdown = Changelog::from(conf).markdown(None, &cms, None)?;
+        assert!(markdown.starts_with("## 0.1.0 - 01/04/2020\n"));
+
+        let conf = Config {
+            date_format: Some(String::from("%B %e, %Y")),
+            ..Default::default()
+        };
+        let markdown = Changelog::from(conf).markdown(None, &cms, None)?;
+        assert!(markdown.starts_with("## 0.1.0 - April  1, 2020\n"));
+        Ok(())
+    }
+
+    #[test]
+    fn type_only_ok() -> Result<()> {
+        let mut commits = Vec::new();
+        for (id, summary, tag) in [
+            ("5", "fix: fix 5", Some("0.3.0")),
+            ("4", "feat: add 4", None),
+            ("3", "feat: add 3", Some("0.2.0")),
+            ("2", "fix: fix 2", Some("0.1.0")),
+            ("1", "fix: fix 1", None),
+        ] {
+            commits.push(dummy_message_commit(
+                &format!("{}d185faf719f12292414c88872e3397fc5dc4e62", id),
+                summary,
+                "Test User <test-user@test.com>",
+                &format!("Wed Apr 01 01:01:0{} 2020 +0000", id),
+                tag,
+            )?);
+        }
+        let conf = Config {
+            type_only: Some(CommitType::Fix),
+            ..Default::default()
+        };
+        let markdown =
+            Changelog::from(conf).markdown(None, &Commits::new(prev()?, commits), None)?;
+        let expected = r#"## 0.3.0 - 2020-04-01
+- [5d185fa] fix 5 (Test User)
+
+## 0.1.0 - 2020-04-01
+- [2d185fa] fix 2 (Test User)
+- [1d185fa] fix 1 (Test User)
+"#;
+        assert_eq!(markdown, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn front_matter_ok() -> Result<()> {
+        let cms = dummy_commits()?;
+        let conf = Config {
+            front_matter: Some(vec![
+                (String::from("layout"), String::from("post")),
+                (String::from("title"), String::from("Release: 0.1.0")),
+            ]),
+            ..Default::default()
+        };
+        let markdown = Changelog::from(conf).markdown(None, &cms, None)?;
+        let expected = r#"---
+date: 2020-04-01
+layout: post
+title: "Release: 0.1.0"
+---
+
+## 0.1.0 - 2020-04-01
+"#;
+        assert!(markdown.starts_with(expected));
+
+        let block = markdown.split("---\n").nth(1).context("No front-matter")?;
+        let yaml: serde_yaml::Mapping = serde_yaml::from_str(block)?;
+        assert_eq!(yaml.get(&"layout".into()), Some(&"post".into()));
+        Ok(())
+    }
+
+    #[test]
+    fn plain_ok() -> Result<()> {
+        let cms = dummy_commits()?;
+        let plain = Changelog::from(Config {
+            hidden_types: Vec::new(),
+            ..Default::default()
+        })
+        .plain(&cms, None);
+        let expected = r#"0.1.0 - 2020-04-01
+FEAT
+- 1d185fa add 1 (Test User)
+
+FIX
+- 2d185fa add 2 (Test User)
+
+TEST
+- 3d185fa add 3 (Test User)
+"#;
+        assert_eq!(plain, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn plain_sections_ok() -> Result<()> {
+        let mut commits = Vec::new();
+        let commit = dummy_message_commit(
+            "3d185faf719f12292414c88872e3397fc5dc4e62",
+            "chore: bump x",
+            "Test User <test-user@test.com>",
+            "Wed Apr 01 01:01:03 2020 +0000",
+            Some("0.2.0"),
         )?;
         commits.push(commit);
 
-        let commit = dummy_commit(
-            "ad185faf719f12292414c88872e3397fc5dc4e62",
-            "custom",
-            None,
-            false,
-            "add custom",
+        let commit = dummy_message_commit(
+            "2d185faf719f12292414c88872e3397fc5dc4e62",
+            "feat!: drop v1 api\n\nBREAKING CHANGE: see the migration guide.",
             "Test User <test-user@test.com>",
-            "Wed Apr 01 01:01:01 2020 +0000",
-            1,
-            None,
+            "Wed Apr 01 01:01:02 2020 +0000",
+            Some("0.1.0"),
         )?;
         commits.push(commit);
 
-        let commit = dummy_invalid_commit(
+        let commit = dummy_message_commit(
             "1d185faf719f12292414c88872e3397fc5dc4e62",
-            "add other",
-            "Test User <test-user1@test.com>",
+            "fix: fix old api",
+            "Test User <test-user@test.com>",
             "Wed Apr 01 01:01:01 2020 +0000",
             None,
         )?;
         commits.push(commit);
 
-        let prev = prev()?;
-        let cms = Commits::new(prev, commits);
+        let conf = Config {
+            show_breaking_changes: true,
+            type_order: Some(vec![CommitType::Fix]),
+            empty_message: Some(String::from("No notable changes.")),
+            ..Default::default()
+        };
+        let plain = Changelog::from(conf).plain(&Commits::new(prev()?, commits), None);
+        let expected = r#"0.2.0 - 2020-04-01
+No notable changes.
+
+0.1.0 - 2020-04-01
+BREAKING CHANGES
+- 2d185fa see the migration guide. (Test User)
+
+FIX
+- 1d185fa fix old api (Test User)
+
+FEAT
+- 2d185fa drop v1 api (Test User)
+"#;
+        assert_eq!(plain, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn strip_tag_prefix_in_heading_ok() -> Result<()> {
+        let commits = vec![
+            dummy_commit(
+                "2d185faf719f12292414c88872e3397fc5dc4e62",
+                "feat",
+                None,
+                false,
+                "add 2",
+                "Test User <test-user@test.com>",
+                "Wed Apr 01 01:01:02 2020 +0000",
+                1,
+                Some("component-v0.2.0"),
+            )?,
+            dummy_commit(
+                "1d185faf719f12292414c88872e3397fc5dc4e62",
+                "feat",
+                None,
+                false,
+                "add 1",
+                "Test User <test-user@test.com>",
+                "Wed Apr 01 01:01:01 2020 +0000",
+                1,
+                Some("component-v0.1.0"),
+            )?,
+        ];
+        let conf = Config {
+            strip_tag_prefix_in_heading: true,
+            ..Default::default()
+        };
         let gurl = GithubUrl::new("https://github.com/watawuwu/ccclog.git");
-        let changelog = Changelog::new();
-        let markdown = changelog.markdown(Some(&gurl), &cms, None)?;
+        let markdown = Changelog::from(conf).markdown(
+            Some(&gurl),
+            &Commits::new(prev()?, commits),
+            Some("component-v"),
+        )?;
         let expected = r#"## [0.2.0] - 2020-04-01
 ### Feat
-- [[1d185fa]] add README (Test User1)
-
-### Fix
-- [[2d185fa]] fix typo (Test User2)
-
-### Build
-- [[3d185fa]] add build script (Test User3)
+- [[2d185fa]] add 2 (Test User)
 
-### Doc
-- [[4d185fa]] add doc (Test User4)
+## [0.1.0] - 2020-04-01
+### Feat
+- [[1d185fa]] add 1 (Test User)
 
-### Chore
-- [[5d185fa]] add chore (Test User5)
+[0.2.0]: https://github.com/watawuwu/ccclog/compare/component-v0.1.0...component-v0.2.0
+[2d185fa]: https://github.com/watawuwu/ccclog/commit/2d185faf719f12292414c88872e3397fc5dc4e62
+[0.1.0]: https://github.com/watawuwu/ccclog/compare/0.0.0...component-v0.1.0
+[1d185fa]: https://github.com/watawuwu/ccclog/commit/1d185faf719f12292414c88872e3397fc5dc4e62
+"#;
+        assert_eq!(markdown, expected);
+        Ok(())
+    }
 
-### CI
-- [[6d185fa]] add CI (Test User6)
+    #[test]
+    fn include_body_ok() -> Result<()> {
+        let commit = dummy_message_commit(
+            "2d185faf719f12292414c88872e3397fc5dc4e62",
+            "fix: fix 2\n\nThe detail\nof the fix\n\nThe next paragraph\n\nRefs: #12",
+            "Test User <test-user@test.com>",
+            "Wed Apr 01 01:01:02 2020 +0000",
+            Some("0.1.0"),
+        )?;
+        let commits = Commits::new(prev()?, vec![commit]);
+        let conf = Config {
+            include_body: true,
+            ..Default::default()
+        };
+        let markdown = Changelog::from(conf).markdown(None, &commits, None)?;
+        let expected = r#"## 0.1.0 - 2020-04-01
+### Fix
+- [2d185fa] fix 2 (Test User)
+  The detail
+  of the fix
 
-### Style
-- [[7d185fa]] add style (Test User7)
+  The next paragraph
+"#;
+        assert_eq!(markdown, expected);
 
-### Refactor
-- [[8d185fa]] add refactor (Test User8)
+        let markdown = Changelog::new().markdown(None, &commits, None)?;
+        assert!(!markdown.contains("The detail"));
+        Ok(())
+    }
 
-### Perf
-- [[9d185fa]] add perf (Test User9)
+    #[test]
+    fn show_date_ok() -> Result<()> {
+        let cms = dummy_commits()?;
+        let conf = Config {
+            show_date: false,
+            ..Default::default()
+        };
+        let changelog = Changelog::from(conf);
+        let markdown = changelog.markdown(None, &cms, None)?;
+        assert!(markdown.starts_with("## 0.1.0\n"));
 
-### Test
-- [[0e185fa]] add test (Test User10)
+        let gurl = GithubUrl::new("https://github.com/watawuwu/ccclog.git");
+        let markdown = changelog.markdown(Some(&gurl), &cms, None)?;
+        assert!(markdown.starts_with("## [0.1.0]\n"));
 
-### Revert
-- [[1e185fa]] add some (Test User11)
+        let asciidoc = changelog.asciidoc(Some(&gurl), &cms, None)?;
+        assert!(asciidoc.starts_with(
+            "== link:https://github.com/watawuwu/ccclog/compare/0.0.0...0.1.0[0.1.0]\n"
+        ));
+        Ok(())
+    }
 
-### Security
-- [[2e185fa]] fix security (Test User12)
+    #[test]
+    fn oldest_first_sections_ok() -> Result<()> {
+        let mut commits = Vec::new();
+        for (i, t) in ["fix", "feat", "fix", "feat"].iter().enumerate() {
+            commits.push(dummy_commit(
+                &format!("{}d185faf719f12292414c88872e3397fc5dc4e62", 4 - i),
+                t,
+                None,
+                false,
+                &format!("add {}", 4 - i),
+                "Test User <test-user@test.com>",
+                &format!("Wed Apr 01 01:01:0{} 2020 +0000", 4 - i),
+                1,
+                if i == 0 { Some("0.1.0") } else { None },
+            )?);
+        }
+        let cms = Commits::new(prev()?, commits);
+        let conf = Config {
+            commit_chronology: Chronology::OldestFirst,
+            ..Default::default()
+        };
+        let markdown = Changelog::from(conf).markdown(None, &cms, None)?;
+        let expected = r#"## 0.1.0 - 2020-04-01
+### Feat
+- [1d185fa] add 1 (Test User)
+- [3d185fa] add 3 (Test User)
 
-### Custom
-- [[ad185fa]] add custom (Test User)
+### Fix
+- [2d185fa] add 2 (Test User)
+- [4d185fa] add 4 (Test User)
+"#;
+        assert_eq!(markdown, expected);
+        Ok(())
+    }
 
-### Others
-- [[1d185fa]] add other (Test User)
+    #[test]
+    fn verify_release_commit_only_ok() -> Result<()> {
+        let commits = vec![
+            dummy_commit(
+                "2d185faf719f12292414c88872e3397fc5dc4e62",
+                "feat",
+                None,
+                false,
+                "add 2",
+                "Test User <test-user@test.com>",
+                "Wed Apr 01 01:01:02 2020 +0000",
+                1,
+                Some("0.1.0"),
+            )?
+            .with_verified(),
+            dummy_commit(
+                "1d185faf719f12292414c88872e3397fc5dc4e62",
+                "feat",
+                None,
+                false,
+                "add 1",
+                "Test User <test-user@test.com>",
+                "Wed Apr 01 01:01:01 2020 +0000",
+                1,
+                None,
+            )?
+            .with_verified(),
+        ];
+        let cms = Commits::new(prev()?, commits);
+        let conf = Config {
+            verify_release_commit_only: true,
+            ..Default::default()
+        };
+        let markdown = Changelog::from(conf).markdown(None, &cms, None)?;
+        let expected = r#"## 0.1.0 - 2020-04-01
+### Feat
+- [2d185fa] add 2 (Test User) (verified)
+- [1d185fa] add 1 (Test User)
+"#;
+        assert_eq!(markdown, expected);
 
-[0.2.0]: https://github.com/watawuwu/ccclog/compare/0.0.0...0.2.0
-[1d185fa]: https://github.com/watawuwu/ccclog/commit/1d185faf719f12292414c88872e3397fc5dc4e62
-[2d185fa]: https://github.com/watawuwu/ccclog/commit/2d185faf719f12292414c88872e3397fc5dc4e62
-[3d185fa]: https://github.com/watawuwu/ccclog/commit/3d185faf719f12292414c88872e3397fc5dc4e62
-[4d185fa]: https://github.com/watawuwu/ccclog/commit/4d185faf719f12292414c88872e3397fc5dc4e62
-[5d185fa]: https://github.com/watawuwu/ccclog/commit/5d185faf719f12292414c88872e3397fc5dc4e62
-[6d185fa]: https://github.com/watawuwu/ccclog/commit/6d185faf719f12292414c88872e3397fc5dc4e62
-[7d185fa]: https://github.com/watawuwu/ccclog/commit/7d185faf719f12292414c88872e3397fc5dc4e62
-[8d185fa]: https://github.com/watawuwu/ccclog/commit/8d185faf719f12292414c88872e3397fc5dc4e62
-[9d185fa]: https://github.com/watawuwu/ccclog/commit/9d185faf719f12292414c88872e3397fc5dc4e62
-[0e185fa]: https://github.com/watawuwu/ccclog/commit/0e185faf719f12292414c88872e3397fc5dc4e62
-[1e185fa]: https://github.com/watawuwu/ccclog/commit/1e185faf719f12292414c88872e3397fc5dc4e62
-[2e185fa]: https://github.com/watawuwu/ccclog/commit/2e185faf719f12292414c88872e3397fc5dc4e62
-[ad185fa]: https://github.com/watawuwu/ccclog/commit/ad185faf719f12292414c88872e3397fc5dc4e62
-[1d185fa]: https://github.com/watawuwu/ccclog/commit/1d185faf719f12292414c88872e3397fc5dc4e62
+        let markdown = Changelog::new().markdown(None, &cms, None)?;
+        assert!(!markdown.contains("(verified)"));
+        Ok(())
+    }
+
+    #[test]
+    fn stable_author_sort_ok() -> Result<()> {
+        let mut commits = Vec::new();
+        for (id, author, tag) in [
+            ("3", "Carol <carol@test.com>", Some("0.1.0")),
+            ("1", "Alice <alice@test.com>", None),
+            ("2", "Bob <bob@test.com>", None),
+            ("4", "Alice <alice@test.com>", None),
+        ] {
+            let commit = dummy_commit(
+                &format!("{}d185faf719f12292414c88872e3397fc5dc4e62", id),
+                "feat",
+                None,
+                false,
+                &format!("add {}", id),
+                author,
+                "Wed Apr 01 01:01:01 2020 +0000",
+                1,
+                tag,
+            )?;
+            commits.push(commit);
+        }
+        let cms = Commits::new(prev()?, commits);
+
+        let conf = Config {
+            stable_author_sort: true,
+            ..Default::default()
+        };
+        let markdown = Changelog::from(conf).markdown(None, &cms, None)?;
+        let expected = r#"## 0.1.0 - 2020-04-01
+### Feat
+- [1d185fa] add 1 (Alice)
+- [4d185fa] add 4 (Alice)
+- [2d185fa] add 2 (Bob)
+- [3d185fa] add 3 (Carol)
 "#;
         assert_eq!(markdown, expected);
         Ok(())
     }
 
     #[test]
-    fn multi_item_ok() -> Result<()> {
+    fn json_ok() -> Result<()> {
         let mut commits = Vec::new();
-        let commit = dummy_commit(
-            "3d185faf719f12292414c88872e3397fc5dc4e62",
-            "feat",
-            None,
-            false,
-            "add feat3",
-            "Test User3 <test-user3@test.com>",
-            "Wed Apr 01 01:01:03 2020 +0000",
-            1,
-            Some("1.0.0"),
-        )?;
-        commits.push(commit);
-
         let commit = dummy_commit(
             "2d185faf719f12292414c88872e3397fc5dc4e62",
             "feat",
             None,
-            false,
-            "add feat2",
-            "Test User2 <test-user2@test.com>",
-            "Wed Apr 01 01:01:03 2020 +0000",
+            true,
+            "add 2",
+            "Test User <test-user@test.com>",
+            "Wed Apr 01 01:01:02 2020 +0000",
             1,
             None,
         )?;
@@ -499,72 +4357,308 @@ mod tests {
 
         let commit = dummy_commit(
             "1d185faf719f12292414c88872e3397fc5dc4e62",
-            "feat",
+            "fix",
             None,
             false,
-            "add feat1",
-            "Test User1 <test-user1@test.com>",
+            "fix 1",
+            "Test User <test-user@test.com>",
             "Wed Apr 01 01:01:01 2020 +0000",
             1,
-            None,
+            Some("0.1.0"),
         )?;
         commits.push(commit);
 
-        let prev = prev()?;
-        let cms = Commits::new(prev, commits);
         let changelog = Changelog::new();
         let gurl = GithubUrl::new("https://github.com/watawuwu/ccclog.git");
-        let markdown = changelog.markdown(Some(&gurl), &cms, None)?;
-        let expected = r#"## [1.0.0] - 2020-04-01
-### Feat
-- [[3d185fa]] add feat3 (Test User3)
-- [[2d185fa]] add feat2 (Test User2)
-- [[1d185fa]] add feat1 (Test User1)
-
-[1.0.0]: https://github.com/watawuwu/ccclog/compare/0.0.0...1.0.0
-[3d185fa]: https://github.com/watawuwu/ccclog/commit/3d185faf719f12292414c88872e3397fc5dc4e62
-[2d185fa]: https://github.com/watawuwu/ccclog/commit/2d185faf719f12292414c88872e3397fc5dc4e62
-[1d185fa]: https://github.com/watawuwu/ccclog/commit/1d185faf719f12292414c88872e3397fc5dc4e62
+        let a = changelog.json(Some(&gurl), &Commits::new(prev()?, commits), None)?;
+        let e = r#"[
+  {
+    "version": "Unreleased",
+    "date": null,
+    "compare_url": "https://github.com/watawuwu/ccclog/compare/0.1.0...HEAD",
+    "commits": {
+      "feat": [
+        {
+          "hash": "2d185faf719f12292414c88872e3397fc5dc4e62",
+          "short_hash": "2d185fa",
+          "message": "add 2",
+          "author": "Test User",
+          "author_name": "Test User",
+          "author_email": "test-user@test.com",
+          "breaking": true,
+          "footers": []
+        }
+      ]
+    },
+    "sections": [
+      {
+        "type": "feat",
+        "scope": null,
+        "title": "Feat",
+        "commits": [
+          {
+            "hash": "2d185faf719f12292414c88872e3397fc5dc4e62",
+            "short_hash": "2d185fa",
+            "message": "add 2",
+            "author": "Test User",
+            "author_name": "Test User",
+            "author_email": "test-user@test.com",
+            "breaking": true,
+            "footers": []
+          }
+        ]
+      }
+    ]
+  },
+  {
+    "version": "0.1.0",
+    "date": "2020-04-01",
+    "compare_url": "https://github.com/watawuwu/ccclog/compare/0.0.0...0.1.0",
+    "commits": {
+      "fix": [
+        {
+          "hash": "1d185faf719f12292414c88872e3397fc5dc4e62",
+          "short_hash": "1d185fa",
+          "message": "fix 1",
+          "author": "Test User",
+          "author_name": "Test User",
+          "author_email": "test-user@test.com",
+          "breaking": false,
+          "footers": []
+        }
+      ]
+    },
+    "sections": [
+      {
+        "type": "fix",
+        "scope": null,
+        "title": "Fix",
+        "commits": [
+          {
+            "hash": "1d185faf719f12292414c88872e3397fc5dc4e62",
+            "short_hash": "1d185fa",
+            "message": "fix 1",
+            "author": "Test User",
+            "author_name": "Test User",
+            "author_email": "test-user@test.com",
+            "breaking": false,
+            "footers": []
+          }
+        ]
+      }
+    ]
+  }
+]
 "#;
-        assert_eq!(markdown, expected);
+        assert_eq!(a, e);
         Ok(())
     }
 
     #[test]
-    fn sort_ok() -> Result<()> {
+    fn json_sections_match_markdown_ok() -> Result<()> {
+        let specs = [
+            ("4", "fix", "fix 4", Some("0.1.1")),
+            ("3", "chore", "bump 3", Some("0.1.0")),
+            ("2", "feat", "add 2", None),
+            ("1", "fix", "fix 1", None),
+        ];
+        let commits = specs
+            .iter()
+            .enumerate()
+            .map(|(i, (n, t, m, tag))| {
+                dummy_commit(
+                    &format!("{}d185faf719f12292414c88872e3397fc5dc4e62", n),
+                    t,
+                    None,
+                    false,
+                    m,
+                    "Test User <test-user@test.com>",
+                    &format!("Wed Apr 01 01:01:0{} 2020 +0000", 4 - i),
+                    1,
+                    *tag,
+                )
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let commits = Commits::new(prev()?, commits);
+
+        let conf = Config {
+            min_level: Level::Minor,
+            ..Default::default()
+        };
+        let changelog = Changelog::from(conf);
+        let markdown = changelog.markdown(None, &commits, None)?;
+        let json: serde_json::Value = serde_json::from_str(&changelog.json(None, &commits, None)?)?;
+
+        // The patch release and the hidden chore are dropped from both
+        let headings = |prefix: &str| {
+            markdown
+                .lines()
+                .filter_map(|l| l.strip_prefix(prefix))
+                .map(String::from)
+                .collect::<Vec<_>>()
+        };
+        let releases = json.as_array().unwrap();
+        let versions = releases
+            .iter()
+            .map(|r| {
+                format!(
+                    "{} - {}",
+                    r["version"].as_str().unwrap(),
+                    r["date"].as_str().unwrap()
+                )
+            })
+            .collect::<Vec<_>>();
+        let titles = releases
+            .iter()
+            .flat_map(|r| r["sections"].as_array().unwrap())
+            .map(|s| String::from(s["title"].as_str().unwrap()))
+            .collect::<Vec<_>>();
+        let keys = releases
+            .iter()
+            .flat_map(|r| r["commits"].as_object().unwrap().keys())
+            .cloned()
+            .collect::<Vec<_>>();
+        assert_eq!(versions, headings("## "));
+        assert_eq!(versions, vec!["0.1.0 - 2020-04-01"]);
+        assert_eq!(titles, headings("### "));
+        assert_eq!(titles, vec!["Feat", "Fix"]);
+        assert_eq!(keys, vec!["feat", "fix"]);
+        Ok(())
+    }
+
+    #[test]
+    fn ndjson_releases_ok() -> Result<()> {
+        let mut commits = Vec::new();
+        for (i, tag) in [Some("0.2.0"), None, Some("0.1.0")].iter().enumerate() {
+            commits.push(dummy_commit(
+                &format!("{}d185faf719f12292414c88872e3397fc5dc4e62", 3 - i),
+                "feat",
+                None,
+                false,
+                &format!("add {}", 3 - i),
+                "Test User <test-user@test.com>",
+                &format!("Wed Apr 01 01:01:0{} 2020 +0000", 3 - i),
+                1,
+                *tag,
+            )?);
+        }
+        let cms = Commits::new(prev()?, commits);
+        let a = Changelog::new().ndjson_releases(None, &cms, None)?;
+        assert_eq!(a.lines().count(), 2);
+
+        let versions = a
+            .lines()
+            .map(|l| Ok(serde_json::from_str::<serde_json::Value>(l)?["version"].clone()))
+            .collect::<Result<Vec<_>>>()?;
+        assert_eq!(versions, vec!["0.2.0", "0.1.0"]);
+        assert!(a
+            .lines()
+            .next()
+            .unwrap_or_default()
+            .contains(r#""message":"add 2""#));
+        Ok(())
+    }
+
+    #[test]
+    fn ndjson_releases_filtered_ok() -> Result<()> {
+        let mut commits = Vec::new();
+        for (i, tag) in [Some("0.2.1"), Some("0.2.0"), Some("0.1.0")]
+            .iter()
+            .enumerate()
+        {
+            commits.push(dummy_commit(
+                &format!("{}d185faf719f12292414c88872e3397fc5dc4e62", 3 - i),
+                "feat",
+                None,
+                false,
+                &format!("add {}", 3 - i),
+                "Test User <test-user@test.com>",
+                &format!("Wed Apr 01 01:01:0{} 2020 +0000", 3 - i),
+                1,
+                *tag,
+            )?);
+        }
+        let cms = Commits::new(prev()?, commits);
+        let conf = Config {
+            min_level: Level::Minor,
+            ..Default::default()
+        };
+        let changelog = Changelog::from(conf);
+        let a = changelog.ndjson_releases(None, &cms, None)?;
+        let markdown = changelog.markdown(None, &cms, None)?;
+
+        // One line per release heading of the markdown
+        let headings = markdown.lines().filter(|l| l.starts_with("## ")).count();
+        assert_eq!(a.lines().count(), headings);
+        let versions = a
+            .lines()
+            .map(|l| Ok(serde_json::from_str::<serde_json::Value>(l)?["version"].clone()))
+            .collect::<Result<Vec<_>>>()?;
+        assert_eq!(versions, vec!["0.2.0", "0.1.0"]);
+        Ok(())
+    }
+
+    #[test]
+    fn hide_redundant_scope_ok() -> Result<()> {
         let mut commits = Vec::new();
         let commit = dummy_commit(
-            "4d185faf719f12292414c88872e3397fc5dc4e62",
+            "2d185faf719f12292414c88872e3397fc5dc4e62",
             "feat",
-            None,
+            Some("feat"),
             false,
-            "add 4",
+            "x",
             "Test User <test-user@test.com>",
-            "Wed Apr 01 01:01:04 2020 +0000",
+            "Wed Apr 01 01:01:02 2020 +0000",
             1,
-            Some("0.2.0"),
+            Some("0.1.0"),
         )?;
         commits.push(commit);
 
         let commit = dummy_commit(
-            "3d185faf719f12292414c88872e3397fc5dc4e62",
+            "1d185faf719f12292414c88872e3397fc5dc4e62",
             "feat",
             None,
             false,
-            "add 3",
+            "y",
             "Test User <test-user@test.com>",
-            "Wed Apr 01 01:01:03 2020 +0000",
+            "Wed Apr 01 01:01:01 2020 +0000",
             1,
             None,
         )?;
         commits.push(commit);
+        let cms = Commits::new(prev()?, commits);
+
+        let conf = Config {
+            group_by: GroupKey::TypeScope,
+            ..Default::default()
+        };
+        let markdown = Changelog::from(conf).markdown(None, &cms, None)?;
+        assert!(markdown.contains("### Feat (feat)\n- [2d185fa] x (Test User)\n"));
+
+        let conf = Config {
+            group_by: GroupKey::TypeScope,
+            hide_redundant_scope: true,
+            ..Default::default()
+        };
+        let markdown = Changelog::from(conf).markdown(None, &cms, None)?;
+        let expected = r#"## 0.1.0 - 2020-04-01
+### Feat
+- [2d185fa] x (Test User)
+- [1d185fa] y (Test User)
+"#;
+        assert_eq!(markdown, expected);
+        Ok(())
+    }
 
+    #[test]
+    fn template_ok() -> Result<()> {
+        let mut commits = Vec::new();
         let commit = dummy_commit(
             "2d185faf719f12292414c88872e3397fc5dc4e62",
-            "feat",
+            "fix",
             None,
             false,
-            "add 2",
+            "fix 2",
             "Test User <test-user@test.com>",
             "Wed Apr 01 01:01:02 2020 +0000",
             1,
@@ -585,101 +4679,119 @@ mod tests {
         )?;
         commits.push(commit);
 
-        let prev = prev()?;
-        let cms = Commits::new(prev, commits);
+        let template = r#"{% for release in releases -%}
+# {{ release.version }} ({{ release.date }})
+{% for type, commits in release.commits -%}
+{% for c in commits -%}
+* {{ type }}: {{ c.message }} [{{ c.short_hash }}] by {{ c.author_name }}
+{% endfor -%}
+{% endfor -%}
+{% endfor -%}"#;
         let changelog = Changelog::new();
-        let gurl = GithubUrl::new("https://github.com/watawuwu/ccclog.git");
-        let markdown = changelog.markdown(Some(&gurl), &cms, None)?;
-        let expected = r#"## [0.2.0] - 2020-04-01
-### Feat
-- [[4d185fa]] add 4 (Test User)
-- [[3d185fa]] add 3 (Test User)
-
-## [0.1.0] - 2020-04-01
-### Feat
-- [[2d185fa]] add 2 (Test User)
-- [[1d185fa]] add 1 (Test User)
-
-[0.2.0]: https://github.com/watawuwu/ccclog/compare/0.1.0...0.2.0
-[4d185fa]: https://github.com/watawuwu/ccclog/commit/4d185faf719f12292414c88872e3397fc5dc4e62
-[3d185fa]: https://github.com/watawuwu/ccclog/commit/3d185faf719f12292414c88872e3397fc5dc4e62
-[0.1.0]: https://github.com/watawuwu/ccclog/compare/0.0.0...0.1.0
-[2d185fa]: https://github.com/watawuwu/ccclog/commit/2d185faf719f12292414c88872e3397fc5dc4e62
-[1d185fa]: https://github.com/watawuwu/ccclog/commit/1d185faf719f12292414c88872e3397fc5dc4e62
+        let a = changelog.template(None, &Commits::new(prev()?, commits), None, template)?;
+        let e = r#"# 0.1.0 (2020-04-01)
+* feat: add 1 [1d185fa] by Test User
+* fix: fix 2 [2d185fa] by Test User
 "#;
-        assert_eq!(markdown, expected);
+        assert_eq!(a, e);
+        Ok(())
+    }
+
+    #[test]
+    fn template_sections_ok() -> Result<()> {
+        let commits = [
+            ("3", "chore", "bump 3"),
+            ("2", "fix", "fix 2"),
+            ("1", "feat", "add 1"),
+        ]
+        .iter()
+        .enumerate()
+        .map(|(i, (n, t, m))| {
+            dummy_commit(
+                &format!("{}d185faf719f12292414c88872e3397fc5dc4e62", n),
+                t,
+                None,
+                false,
+                m,
+                "Test User <test-user@test.com>",
+                &format!("Wed Apr 01 01:01:0{} 2020 +0000", 3 - i),
+                1,
+                if i == 0 { Some("0.1.0") } else { None },
+            )
+        })
+        .collect::<Result<Vec<_>>>()?;
 
+        let template = r#"{% for release in releases -%}
+# {{ release.version }}{% if release.date %} ({{ release.date }}){% endif %}
+{% for section in release.sections -%}
+## {{ section.title }}
+{% for c in section.commits -%}
+* {{ c.message }} by {{ c.author }}
+{% endfor -%}
+{% endfor -%}
+{% endfor -%}"#;
         let conf = Config {
-            reverse: true,
+            type_order: Some(vec![CommitType::Fix, CommitType::Feat]),
+            show_date: false,
             ..Default::default()
         };
-
         let changelog = Changelog::from(conf);
-        let markdown = changelog.markdown(Some(&gurl), &cms, None)?;
-        let expected = r#"## [0.2.0] - 2020-04-01
-### Feat
-- [[3d185fa]] add 3 (Test User)
-- [[4d185fa]] add 4 (Test User)
-
-## [0.1.0] - 2020-04-01
-### Feat
-- [[1d185fa]] add 1 (Test User)
-- [[2d185fa]] add 2 (Test User)
-
-[0.2.0]: https://github.com/watawuwu/ccclog/compare/0.1.0...0.2.0
-[3d185fa]: https://github.com/watawuwu/ccclog/commit/3d185faf719f12292414c88872e3397fc5dc4e62
-[4d185fa]: https://github.com/watawuwu/ccclog/commit/4d185faf719f12292414c88872e3397fc5dc4e62
-[0.1.0]: https://github.com/watawuwu/ccclog/compare/0.0.0...0.1.0
-[1d185fa]: https://github.com/watawuwu/ccclog/commit/1d185faf719f12292414c88872e3397fc5dc4e62
-[2d185fa]: https://github.com/watawuwu/ccclog/commit/2d185faf719f12292414c88872e3397fc5dc4e62
+        let commits = Commits::new(prev()?, commits);
+        let a = changelog.template(None, &commits, None, template)?;
+        let e = r#"# 0.1.0
+## Fix
+* fix 2 by Test User
+## Feat
+* add 1 by Test User
 "#;
+        assert_eq!(a, e);
+        Ok(())
+    }
 
-        assert_eq!(markdown, expected);
-
+    #[test]
+    fn template_footers_ok() -> Result<()> {
+        let commit = dummy_message_commit(
+            "2d185faf719f12292414c88872e3397fc5dc4e62",
+            "fix: fix 2\n\nReviewed-by: Alice\nRefs #12",
+            "Test User <test-user@test.com>",
+            "Wed Apr 01 01:01:02 2020 +0000",
+            Some("0.1.0"),
+        )?;
+        let template = r#"{% for release in releases -%}
+{% for type, commits in release.commits -%}
+{% for c in commits -%}
+* {{ c.message }}{% for f in c.footers %} ({{ f.token }}: {{ f.value }}){% endfor %}
+{% endfor -%}
+{% endfor -%}
+{% endfor -%}"#;
+        let changelog = Changelog::new();
+        let a = changelog.template(None, &Commits::new(prev()?, vec![commit]), None, template)?;
+        assert_eq!(a, "* fix 2 (Reviewed-by: Alice) (Refs: 12)\n");
         Ok(())
     }
 
     #[test]
-    fn unreleased_ok() -> Result<()> {
+    fn custom_type_order_ok() -> Result<()> {
         let mut commits = Vec::new();
         let commit = dummy_commit(
-            "1d185faf719f12292414c88872e3397fc5dc4e62",
-            "feat",
+            "3d185faf719f12292414c88872e3397fc5dc4e62",
+            "alpha",
             None,
             false,
-            "add first",
+            "add 3",
             "Test User <test-user@test.com>",
-            "Wed Apr 01 01:01:01 2020 +0000",
+            "Wed Apr 01 01:01:03 2020 +0000",
             1,
-            None,
+            Some("0.1.0"),
         )?;
         commits.push(commit);
 
-        let changelog = Changelog::new();
-        let prev = prev()?;
-        let cms = Commits::new(prev, commits);
-        let gurl = GithubUrl::new("https://github.com/watawuwu/ccclog.git");
-        let markdown = changelog.markdown(Some(&gurl), &cms, None)?;
-        let expected = r#"## [Unreleased]
-### Feat
-- [[1d185fa]] add first (Test User)
-
-[Unreleased]: https://github.com/watawuwu/ccclog/compare/0.0.0...HEAD
-[1d185fa]: https://github.com/watawuwu/ccclog/commit/1d185faf719f12292414c88872e3397fc5dc4e62
-"#;
-        assert_eq!(markdown, expected);
-        Ok(())
-    }
-
-    #[test]
-    fn tag_and_unreleased_ok() -> Result<()> {
-        let mut commits = Vec::new();
         let commit = dummy_commit(
             "2d185faf719f12292414c88872e3397fc5dc4e62",
-            "feat",
+            "zeta",
             None,
             false,
-            "add second",
+            "add 2",
             "Test User <test-user@test.com>",
             "Wed Apr 01 01:01:02 2020 +0000",
             1,
@@ -692,121 +4804,86 @@ mod tests {
             "feat",
             None,
             false,
-            "add first",
+            "add 1",
             "Test User <test-user@test.com>",
             "Wed Apr 01 01:01:01 2020 +0000",
             1,
-            Some("0.1.0"),
+            None,
         )?;
         commits.push(commit);
+        let cms = Commits::new(prev()?, commits);
 
-        let prev = prev()?;
-        let cms = Commits::new(prev, commits);
-        let changelog = Changelog::new();
-        let gurl = GithubUrl::new("https://github.com/watawuwu/ccclog.git");
-        let markdown = changelog.markdown(Some(&gurl), &cms, None)?;
-        let expected = r#"## [Unreleased]
+        let markdown = Changelog::new().markdown(None, &cms, None)?;
+        let expected = r#"## 0.1.0 - 2020-04-01
 ### Feat
-- [[2d185fa]] add second (Test User)
+- [1d185fa] add 1 (Test User)
 
-## [0.1.0] - 2020-04-01
-### Feat
-- [[1d185fa]] add first (Test User)
+### Alpha
+- [3d185fa] add 3 (Test User)
 
-[Unreleased]: https://github.com/watawuwu/ccclog/compare/0.1.0...HEAD
-[2d185fa]: https://github.com/watawuwu/ccclog/commit/2d185faf719f12292414c88872e3397fc5dc4e62
-[0.1.0]: https://github.com/watawuwu/ccclog/compare/0.0.0...0.1.0
-[1d185fa]: https://github.com/watawuwu/ccclog/commit/1d185faf719f12292414c88872e3397fc5dc4e62
+### Zeta
+- [2d185fa] add 2 (Test User)
 "#;
         assert_eq!(markdown, expected);
-        Ok(())
-    }
-
-    #[test]
-    fn scope_ok() -> Result<()> {
-        let mut commits = Vec::new();
-        let commit = dummy_commit(
-            "1d185faf719f12292414c88872e3397fc5dc4e62",
-            "feat",
-            Some("test"),
-            false,
-            "add first",
-            "Test User <test-user@test.com>",
-            "Wed Apr 01 01:01:01 2020 +0000",
-            1,
-            Some("0.1.0"),
-        )?;
-        commits.push(commit);
 
-        let prev = prev()?;
-        let cms = Commits::new(prev, commits);
-        let changelog = Changelog::new();
-        let gurl = GithubUrl::new("https://github.com/watawuwu/ccclog.git");
-        let markdown = changelog.markdown(Some(&gurl), &cms, None)?;
-        let expected = r#"## [0.1.0] - 2020-04-01
+        let conf = Config {
+            custom_type_order: CustomOrder::FirstSeen,
+            ..Default::default()
+        };
+        let markdown = Changelog::from(conf).markdown(None, &cms, None)?;
+        let expected = r#"## 0.1.0 - 2020-04-01
 ### Feat
-- [[1d185fa]] add first (Test User)
+- [1d185fa] add 1 (Test User)
 
-[0.1.0]: https://github.com/watawuwu/ccclog/compare/0.0.0...0.1.0
-[1d185fa]: https://github.com/watawuwu/ccclog/commit/1d185faf719f12292414c88872e3397fc5dc4e62
+### Zeta
+- [2d185fa] add 2 (Test User)
+
+### Alpha
+- [3d185fa] add 3 (Test User)
 "#;
         assert_eq!(markdown, expected);
         Ok(())
     }
 
     #[test]
-    fn no_conventional_commits_ok() -> Result<()> {
+    fn type_order_ok() -> Result<()> {
         let mut commits = Vec::new();
-        let commit = dummy_invalid_commit(
-            "1d185faf719f12292414c88872e3397fc5dc4e62",
-            "add first",
+        let commit = dummy_commit(
+            "4d185faf719f12292414c88872e3397fc5dc4e62",
+            "deps",
+            None,
+            false,
+            "bump x",
             "Test User <test-user@test.com>",
-            "Wed Apr 01 01:01:01 2020 +0000",
+            "Wed Apr 01 01:01:04 2020 +0000",
+            1,
             Some("0.1.0"),
         )?;
         commits.push(commit);
-        let prev = prev()?;
-        let cms = Commits::new(prev, commits);
-        let changelog = Changelog::new();
-        let gurl = GithubUrl::new("https://github.com/watawuwu/ccclog.git");
-        let markdown = changelog.markdown(Some(&gurl), &cms, None)?;
-        let expected = r#"## [0.1.0] - 2020-04-01
-### Others
-- [[1d185fa]] add first (Test User)
-
-[0.1.0]: https://github.com/watawuwu/ccclog/compare/0.0.0...0.1.0
-[1d185fa]: https://github.com/watawuwu/ccclog/commit/1d185faf719f12292414c88872e3397fc5dc4e62
-"#;
-        assert_eq!(markdown, expected);
-        Ok(())
-    }
 
-    #[test]
-    fn multi_release_ok() -> Result<()> {
-        let mut commits = Vec::new();
         let commit = dummy_commit(
             "3d185faf719f12292414c88872e3397fc5dc4e62",
-            "feat",
+            "build",
             None,
             false,
-            "add 3",
+            "build 3",
             "Test User <test-user@test.com>",
             "Wed Apr 01 01:01:03 2020 +0000",
             1,
-            Some("0.3.0"),
+            None,
         )?;
         commits.push(commit);
 
         let commit = dummy_commit(
             "2d185faf719f12292414c88872e3397fc5dc4e62",
-            "feat",
+            "fix",
             None,
             false,
-            "add 2",
+            "fix 2",
             "Test User <test-user@test.com>",
             "Wed Apr 01 01:01:02 2020 +0000",
             1,
-            Some("0.2.0"),
+            None,
         )?;
         commits.push(commit);
 
@@ -819,250 +4896,271 @@ mod tests {
             "Test User <test-user@test.com>",
             "Wed Apr 01 01:01:01 2020 +0000",
             1,
-            Some("0.1.0"),
+            None,
         )?;
         commits.push(commit);
 
-        let prev = prev()?;
-        let cms = Commits::new(prev, commits);
-        let changelog = Changelog::new();
-        let gurl = GithubUrl::new("https://github.com/watawuwu/ccclog.git");
-        let markdown = changelog.markdown(Some(&gurl), &cms, None)?;
-        let expected = r#"## [0.3.0] - 2020-04-01
-### Feat
-- [[3d185fa]] add 3 (Test User)
+        let conf = Config {
+            type_order: Some(vec![
+                CommitType::Fix,
+                CommitType::Custom(String::from("deps")),
+                CommitType::Feat,
+            ]),
+            hidden_types: Vec::new(),
+            ..Default::default()
+        };
+        let changelog = Changelog::from(conf);
+        let markdown = changelog.markdown(None, &Commits::new(prev()?, commits), None)?;
+        let expected = r#"## 0.1.0 - 2020-04-01
+### Fix
+- [2d185fa] fix 2 (Test User)
 
-## [0.2.0] - 2020-04-01
-### Feat
-- [[2d185fa]] add 2 (Test User)
+### Deps
+- [4d185fa] bump x (Test User)
 
-## [0.1.0] - 2020-04-01
 ### Feat
-- [[1d185fa]] add 1 (Test User)
+- [1d185fa] add 1 (Test User)
 
-[0.3.0]: https://github.com/watawuwu/ccclog/compare/0.2.0...0.3.0
-[3d185fa]: https://github.com/watawuwu/ccclog/commit/3d185faf719f12292414c88872e3397fc5dc4e62
-[0.2.0]: https://github.com/watawuwu/ccclog/compare/0.1.0...0.2.0
-[2d185fa]: https://github.com/watawuwu/ccclog/commit/2d185faf719f12292414c88872e3397fc5dc4e62
-[0.1.0]: https://github.com/watawuwu/ccclog/compare/0.0.0...0.1.0
-[1d185fa]: https://github.com/watawuwu/ccclog/commit/1d185faf719f12292414c88872e3397fc5dc4e62
+### Build
+- [3d185fa] build 3 (Test User)
 "#;
         assert_eq!(markdown, expected);
         Ok(())
     }
 
     #[test]
-    fn enable_email_link_ok() -> Result<()> {
-        let mut commits = Vec::new();
-        let commit = dummy_commit(
-            "3d185faf719f12292414c88872e3397fc5dc4e62",
-            "feat",
+    fn link_references_ok() -> Result<()> {
+        let commits = vec![dummy_commit(
+            "1d185faf719f12292414c88872e3397fc5dc4e62",
+            "fix",
             None,
             false,
-            "add 3",
+            "fix x (!45)",
             "Test User <test-user@test.com>",
-            "Wed Apr 01 01:01:03 2020 +0000",
+            "Wed Apr 01 01:01:01 2020 +0000",
             1,
-            Some("0.3.0"),
-        )?;
-        commits.push(commit);
+            Some("0.1.0"),
+        )?];
+        let cms = Commits::new(prev()?, commits);
+        let gurl = GithubUrl::new("git@gitlab.example.com:team/repo.git");
+
+        let markdown = Changelog::new().markdown(Some(&gurl), &cms, None)?;
+        assert!(markdown.contains("- [[1d185fa]] fix x (!45) (Test User)\n"));
 
-        let prev = prev()?;
-        let cms = Commits::new(prev, commits);
         let conf = Config {
-            enable_email_link: true,
+            link_references: true,
             ..Default::default()
         };
-
-        let changelog = Changelog::from(conf);
-        let gurl = GithubUrl::new("https://github.com/watawuwu/ccclog.git");
-        let markdown = changelog.markdown(Some(&gurl), &cms, None)?;
-        let expected = r#"## [0.3.0] - 2020-04-01
-### Feat
-- [[3d185fa]] add 3 ([Test User](mailto:test-user@test.com))
-
-[0.3.0]: https://github.com/watawuwu/ccclog/compare/0.0.0...0.3.0
-[3d185fa]: https://github.com/watawuwu/ccclog/commit/3d185faf719f12292414c88872e3397fc5dc4e62
-"#;
-        assert_eq!(markdown, expected);
+        let markdown = Changelog::from(conf).markdown(Some(&gurl), &cms, None)?;
+        assert!(markdown.contains(
+            "- [[1d185fa]] fix x ([!45](https://gitlab.example.com/team/repo/-/merge_requests/45)) (Test User)\n"
+        ));
         Ok(())
     }
 
     #[test]
-    fn root_indent_level_ok() -> Result<()> {
-        let mut commits = Vec::new();
-        let commit = dummy_commit(
-            "3d185faf719f12292414c88872e3397fc5dc4e62",
-            "feat",
+    fn link_issues_ok() -> Result<()> {
+        let commits = vec![dummy_commit(
+            "1d185faf719f12292414c88872e3397fc5dc4e62",
+            "fix",
             None,
             false,
-            "add 3",
+            "fix `#x` (#12)",
             "Test User <test-user@test.com>",
-            "Wed Apr 01 01:01:03 2020 +0000",
+            "Wed Apr 01 01:01:01 2020 +0000",
             1,
-            Some("0.3.0"),
-        )?;
-        commits.push(commit);
-
-        let prev = prev()?;
-
-        let cms = Commits::new(prev, commits);
+            Some("0.1.0"),
+        )?];
+        let cms = Commits::new(prev()?, commits);
         let conf = Config {
-            root_indent_level: 1,
+            link_issues: true,
             ..Default::default()
         };
         let changelog = Changelog::from(conf);
+
         let gurl = GithubUrl::new("https://github.com/watawuwu/ccclog.git");
         let markdown = changelog.markdown(Some(&gurl), &cms, None)?;
-        let expected = r#"# [0.3.0] - 2020-04-01
-## Feat
-- [[3d185fa]] add 3 (Test User)
+        assert!(markdown.contains(
+            "- [[1d185fa]] fix `#x` ([#12](https://github.com/watawuwu/ccclog/issues/12)) (Test User)\n"
+        ));
 
-[0.3.0]: https://github.com/watawuwu/ccclog/compare/0.0.0...0.3.0
-[3d185fa]: https://github.com/watawuwu/ccclog/commit/3d185faf719f12292414c88872e3397fc5dc4e62
-"#;
-        assert_eq!(markdown, expected);
+        let markdown = changelog.markdown(None, &cms, None)?;
+        assert!(markdown.contains("- [1d185fa] fix `#x` (#12) (Test User)\n"));
         Ok(())
     }
 
     #[test]
-    fn no_remote_ok() -> Result<()> {
-        let mut commits = Vec::new();
-        let commit = dummy_commit(
+    fn link_closing_footers_ok() -> Result<()> {
+        let commit = dummy_message_commit(
             "1d185faf719f12292414c88872e3397fc5dc4e62",
-            "feat",
-            None,
-            false,
-            "add 1",
+            "fix: fix x\n\nCloses #12, #13\nFixes: org/repo#3\nResolves: GH-4\nRefs: #5",
             "Test User <test-user@test.com>",
-            "Wed Apr 01 01:01:03 2020 +0000",
-            1,
-            Some("0.3.0"),
+            "Wed Apr 01 01:01:01 2020 +0000",
+            Some("0.1.0"),
         )?;
-        commits.push(commit);
+        let cms = Commits::new(prev()?, vec![commit]);
+        let conf = Config {
+            link_issues: true,
+            ..Default::default()
+        };
+        let gurl = GithubUrl::new("https://github.com/watawuwu/ccclog.git");
+        let markdown = Changelog::from(conf).markdown(Some(&gurl), &cms, None)?;
+        let base = "https://github.com/watawuwu/ccclog/issues";
+        let expected = format!(
+            "- [[1d185fa]] fix x (Test User) (closes [#12]({0}/12), [#13]({0}/13), [org/repo#3](https://github.com/org/repo/issues/3), [GH-4]({0}/4))\n",
+            base
+        );
+        assert!(markdown.contains(&expected), "{}", markdown);
 
-        let prev = prev()?;
+        let markdown = Changelog::new().markdown(Some(&gurl), &cms, None)?;
+        assert!(markdown.contains("- [[1d185fa]] fix x (Test User)\n"));
+        Ok(())
+    }
+
+    #[test]
+    fn min_level_ok() -> Result<()> {
+        let mut commits = Vec::new();
+        for (i, tag) in ["0.2.1", "0.2.0", "0.1.1", "0.1.0"].iter().enumerate() {
+            let commit = dummy_commit(
+                &format!("{}d185faf719f12292414c88872e3397fc5dc4e62", 4 - i),
+                "fix",
+                None,
+                false,
+                &format!("fix {}", tag),
+                "Test User <test-user@test.com>",
+                &format!("Wed Apr 01 01:01:0{} 2020 +0000", 4 - i),
+                1,
+                Some(tag),
+            )?;
+            commits.push(commit);
+        }
 
-        let cms = Commits::new(prev, commits);
         let conf = Config {
-            root_indent_level: 1,
+            min_level: Level::Minor,
             ..Default::default()
         };
         let changelog = Changelog::from(conf);
-        let markdown = changelog.markdown(None, &cms, None)?;
-        let expected = r#"# 0.3.0 - 2020-04-01
-## Feat
-- [1d185fa] add 1 (Test User)
+        let markdown = changelog.markdown(None, &Commits::new(prev()?, commits), None)?;
+        let expected = r#"## 0.2.0 - 2020-04-01
+### Fix
+- [3d185fa] fix 0.2.0 (Test User)
+
+## 0.1.0 - 2020-04-01
+### Fix
+- [1d185fa] fix 0.1.0 (Test User)
 "#;
         assert_eq!(markdown, expected);
         Ok(())
     }
 
     #[test]
-    fn custom_ok() -> Result<()> {
+    fn type_titles_ok() -> Result<()> {
         let mut commits = Vec::new();
-        let commit = dummy_commit(
-            "4d185faf719f12292414c88872e3397fc5dc4e62",
-            "custom2",
-            None,
-            false,
-            "add 4",
-            "Test User <test-user@test.com>",
-            "Wed Apr 01 01:01:03 2020 +0000",
-            1,
-            Some("0.3.0"),
-        )?;
-        commits.push(commit);
-
         let commit = dummy_commit(
             "3d185faf719f12292414c88872e3397fc5dc4e62",
-            "custom2",
+            "deps",
             None,
             false,
-            "add 3",
+            "bump x",
             "Test User <test-user@test.com>",
             "Wed Apr 01 01:01:03 2020 +0000",
             1,
-            None,
+            Some("0.1.0"),
         )?;
         commits.push(commit);
 
         let commit = dummy_commit(
             "2d185faf719f12292414c88872e3397fc5dc4e62",
-            "custom1",
+            "fix",
             None,
             false,
-            "add 2",
+            "fix 2",
             "Test User <test-user@test.com>",
-            "Wed Apr 01 01:01:03 2020 +0000",
+            "Wed Apr 01 01:01:02 2020 +0000",
             1,
             None,
         )?;
         commits.push(commit);
+
         let commit = dummy_commit(
             "1d185faf719f12292414c88872e3397fc5dc4e62",
-            "custom1",
+            "feat",
             None,
             false,
             "add 1",
             "Test User <test-user@test.com>",
-            "Wed Apr 01 01:01:03 2020 +0000",
+            "Wed Apr 01 01:01:01 2020 +0000",
             1,
             None,
         )?;
         commits.push(commit);
+        let cms = Commits::new(prev()?, commits);
 
-        let prev = prev()?;
-        let cms = Commits::new(prev, commits);
-        let changelog = Changelog::new();
-        let markdown = changelog.markdown(None, &cms, None)?;
-        let expected = r#"## 0.3.0 - 2020-04-01
-### Custom1
-- [2d185fa] add 2 (Test User)
-- [1d185fa] add 1 (Test User)
-
-### Custom2
-- [4d185fa] add 4 (Test User)
-- [3d185fa] add 3 (Test User)
-"#;
-        assert_eq!(markdown, expected);
-        Ok(())
-    }
-
-    #[test]
-    fn ignore_summary_ok() -> Result<()> {
-        let cms = dummy_commits()?;
+        let mut type_titles = HashMap::new();
+        type_titles.insert(CommitType::Feat, String::from("Features"));
+        type_titles.insert(CommitType::Fix, String::from("Bug Fixes"));
         let conf = Config {
-            ignore_summary: Some(Regex::new(r#"^add 3$"#)?),
+            type_titles,
             ..Default::default()
         };
-        let changelog = Changelog::from(conf);
-        let markdown = changelog.markdown(None, &cms, None)?;
+        let markdown = Changelog::from(conf).markdown(None, &cms, None)?;
         let expected = r#"## 0.1.0 - 2020-04-01
-### Feat
+### Features
 - [1d185fa] add 1 (Test User)
 
-### Fix
-- [2d185fa] add 2 (Test User)
+### Bug Fixes
+- [2d185fa] fix 2 (Test User)
+
+### Deps
+- [3d185fa] bump x (Test User)
 "#;
         assert_eq!(markdown, expected);
+
+        let markdown = Changelog::new().markdown(None, &cms, None)?;
+        assert!(markdown.contains("### Feat\n"));
+        assert!(markdown.contains("### Fix\n"));
         Ok(())
     }
 
     #[test]
-    fn ignore_types_ok() -> Result<()> {
-        let cms = dummy_commits()?;
-        let conf = Config {
-            ignore_types: Some(vec![CommitType::Fix, CommitType::Test]),
-            ..Default::default()
-        };
-        let changelog = Changelog::from(conf);
-        let markdown = changelog.markdown(None, &cms, None)?;
+    fn drop_empty_subjects_ok() -> Result<()> {
+        let mut commits = Vec::new();
+        let commit = dummy_invalid_commit(
+            "2d185faf719f12292414c88872e3397fc5dc4e62",
+            "",
+            "Test User <test-user@test.com>",
+            "Wed Apr 01 01:01:02 2020 +0000",
+            Some("0.1.0"),
+        )?;
+        commits.push(commit);
+
+        let commit = dummy_commit(
+            "1d185faf719f12292414c88872e3397fc5dc4e62",
+            "feat",
+            None,
+            false,
+            "add 1",
+            "Test User <test-user@test.com>",
+            "Wed Apr 01 01:01:01 2020 +0000",
+            1,
+            None,
+        )?;
+        commits.push(commit);
+        let cms = Commits::new(prev()?, commits);
+
+        let markdown = Changelog::new().markdown(None, &cms, None)?;
         let expected = r#"## 0.1.0 - 2020-04-01
 ### Feat
 - [1d185fa] add 1 (Test User)
 "#;
         assert_eq!(markdown, expected);
+
+        let conf = Config {
+            drop_empty_subjects: false,
+            ..Default::default()
+        };
+        let markdown = Changelog::from(conf).markdown(None, &cms, None)?;
+        assert!(markdown.contains("### Others\n- [2d185fa] (no subject) (Test User)\n"));
         Ok(())
     }
 
@@ -0,0 +1,115 @@
+use crate::git::CommitType;
+use anyhow::*;
+use regex::Regex;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+const FILE_NAME: &str = ".ccclog.toml";
+
+// Defaults read from `.ccclog.toml` in the repository root. The command line flags take precedence
+#[derive(Debug, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FileConfig {
+    pub enable_email_link: Option<bool>,
+    pub reverse: Option<bool>,
+    pub root_indent_level: Option<u8>,
+    ignore_summary: Option<String>,
+    ignore_types: Option<Vec<String>>,
+}
+
+impl FileConfig {
+    // A missing file is the same as an empty one
+    pub fn load(dir: &Path) -> Result<Self> {
+        let path = dir.join(FILE_NAME);
+        if !path.is_file() {
+            return Ok(FileConfig::default());
+        }
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read the config. path: {}", path.display()))?;
+        toml::from_str(&content)
+            .with_context(|| format!("Failed to parse the config. path: {}", path.display()))
+    }
+
+    pub fn ignore_summary(&self) -> Result<Option<Regex>> {
+        self.ignore_summary
+            .as_deref()
+            .map(Regex::new)
+            .transpose()
+            .context("Invalid ignore_summary in the config")
+    }
+
+    pub fn ignore_types(&self) -> Result<Option<Vec<CommitType>>> {
+        self.ignore_types
+            .as_ref()
+            .map(|types| types.iter().map(|t| CommitType::from_str(t)).collect())
+            .transpose()
+            .context("Invalid ignore_types in the config")
+    }
+}
+
+// Email => GitHub username. ex) "user@example.com" = "octocat"
+pub fn author_map(path: &Path) -> Result<HashMap<String, String>> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read the author map. path: {}", path.display()))?;
+    toml::from_str(&content)
+        .with_context(|| format!("Failed to parse the author map. path: {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn load_ok() -> Result<()> {
+        let tmp = tempfile::tempdir()?;
+        assert_eq!(FileConfig::load(tmp.path())?, FileConfig::default());
+
+        let content = r#"
+reverse = true
+root_indent_level = 3
+ignore_summary = "^wip"
+ignore_types = ["chore", "deps"]
+"#;
+        fs::write(tmp.path().join(FILE_NAME), content)?;
+        let conf = FileConfig::load(tmp.path())?;
+        assert_eq!(conf.enable_email_link, None);
+        assert_eq!(conf.reverse, Some(true));
+        assert_eq!(conf.root_indent_level, Some(3));
+        assert!(conf.ignore_summary()?.unwrap().is_match("wip: x"));
+        assert_eq!(
+            conf.ignore_types()?,
+            Some(vec![
+                CommitType::Chore,
+                CommitType::Custom(String::from("deps"))
+            ])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn author_map_ok() -> Result<()> {
+        let tmp = tempfile::tempdir()?;
+        let path = tmp.path().join("authors.toml");
+        fs::write(&path, "\"user@example.com\" = \"octocat\"\n")?;
+        let map = author_map(&path)?;
+        assert_eq!(
+            map.get("user@example.com").map(String::as_str),
+            Some("octocat")
+        );
+
+        fs::write(&path, "\"user@example.com\" = 1\n")?;
+        assert!(author_map(&path).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn load_unknown_field_ng() -> Result<()> {
+        let tmp = tempfile::tempdir()?;
+        fs::write(tmp.path().join(FILE_NAME), "revers = true\n")?;
+        assert!(FileConfig::load(tmp.path()).is_err());
+        Ok(())
+    }
+}
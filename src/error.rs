@@ -0,0 +1,40 @@
+use std::fmt;
+
+// Errors with a dedicated exit code, the others are the usage errors
+#[derive(Debug, PartialEq)]
+pub enum ExitError {
+    // The input doesn't exist. ex) the repository path
+    NoInput(String),
+    // The input is invalid. ex) the revspec
+    DataErr(String),
+    // The checked file differs from the output. Same as `diff`
+    OutOfDate(String),
+}
+
+impl ExitError {
+    pub fn code(&self) -> exitcode::ExitCode {
+        match self {
+            ExitError::NoInput(_) => exitcode::NOINPUT,
+            ExitError::DataErr(_) => exitcode::DATAERR,
+            ExitError::OutOfDate(_) => 1,
+        }
+    }
+}
+
+impl fmt::Display for ExitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExitError::NoInput(msg) | ExitError::DataErr(msg) | ExitError::OutOfDate(msg) => {
+                write!(f, "{}", msg)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ExitError {}
+
+// The contexts are looked through. ex) Invalid revspec
+pub fn exit_code(err: &anyhow::Error) -> exitcode::ExitCode {
+    err.downcast_ref::<ExitError>()
+        .map_or(exitcode::USAGE, ExitError::code)
+}
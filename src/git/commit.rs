@@ -1,20 +1,26 @@
+use super::conventional_commit::strip_leading_emoji;
 use super::ConventionalCommits;
 
-use chrono::{DateTime, NaiveDateTime, Utc};
-use git2::{Commit as LibCommit, DescribeOptions, Oid as LibOid, Oid, Signature};
+use chrono::{DateTime, FixedOffset, TimeZone, Utc};
+use git2::{Commit as LibCommit, Diff, Mailmap, Oid as LibOid, Oid, Repository, Signature};
+use glob::Pattern;
 
 use std::cmp::Ordering;
 
 use std::convert::From;
 use std::hash::Hash;
 
-use crate::git::version::Version;
-use crate::git::CommitType;
+use crate::git::version::{Tags, Version};
+use crate::git::{CommitType, Footer};
 use anyhow::*;
 use lazy_static::*;
+use log::*;
 use regex::Regex;
-use std::collections::BTreeMap;
+use std::cell::Cell;
+use std::collections::{BTreeMap, HashMap};
 use std::option::Option;
+use std::path::Path;
+use std::process::{Command, Stdio};
 use std::str::FromStr;
 
 const EMPTY_HASH: &str = "4b825dc642cb6eb9a060e54bf8d69288fbee4904";
@@ -24,9 +30,42 @@ pub struct Commits {
     // TODO remove this struct
     prev: Commit,
     commits: Vec<Commit>,
+    // Inclusive dates of the shown commits, the tags outside them still bound the releases
+    since: Option<DateTime<Utc>>,
+    until: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Eq, Clone, PartialEq, Hash, PartialOrd, Ord)]
+pub struct SectionKey {
+    pub _type: CommitType,
+    pub scope: Option<String>,
+}
+
+impl SectionKey {
+    pub fn new(_type: CommitType, scope: Option<String>) -> Self {
+        SectionKey { _type, scope }
+    }
+
+    // The key as written in the commit header. ex) feat(api)
+    pub fn token(&self) -> String {
+        match &self.scope {
+            Some(scope) => format!("{}({})", self._type.token(), scope),
+            None => self._type.token(),
+        }
+    }
+}
+
+impl std::fmt::Display for SectionKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.scope {
+            Some(scope) => write!(f, "{} ({})", self._type, scope),
+            None => write!(f, "{}", self._type),
+        }
+    }
 }
 
 #[derive(Debug, Eq, PartialEq, Hash, PartialOrd, Ord)]
+#[allow(clippy::large_enum_variant)]
 pub enum ReleaseRange {
     Release(NamableObj, NamableObj),
     UnRelease(NamableObj),
@@ -34,15 +73,35 @@ pub enum ReleaseRange {
 
 impl Commits {
     pub(crate) fn new(prev: Commit, commits: Vec<Commit>) -> Self {
-        Commits { prev, commits }
+        Commits {
+            prev,
+            commits,
+            since: None,
+            until: None,
+        }
+    }
+
+    pub(crate) fn with_window(
+        mut self,
+        since: Option<DateTime<Utc>>,
+        until: Option<DateTime<Utc>>,
+    ) -> Self {
+        self.since = since;
+        self.until = until;
+        self
     }
 
     // TODO refactor
     pub fn group_by(
         &self,
         tag_prefix: Option<&str>,
-    ) -> Vec<(ReleaseRange, BTreeMap<CommitType, Vec<&Commit>>)> {
-        let mut releases: Vec<(ReleaseRange, BTreeMap<CommitType, Vec<&Commit>>)> = Vec::new();
+        key: &dyn Fn(&Commit) -> SectionKey,
+    ) -> Vec<(ReleaseRange, BTreeMap<SectionKey, Vec<&Commit>>)> {
+        // No release without the commits. ex) 0.2.0..0.2.0
+        if self.commits.is_empty() {
+            return Vec::new();
+        }
+        let mut releases: Vec<(ReleaseRange, BTreeMap<SectionKey, Vec<&Commit>>)> = Vec::new();
 
         let (obj, vec) =
             self.commits
@@ -52,15 +111,16 @@ impl Commits {
                         (Some(latest_obj), Some(current_obj)) => {
                             releases.push((
                                 ReleaseRange::Release(current_obj.clone(), latest_obj),
-                                self.group_by_commit_type(acc),
+                                self.group_by_commit_type(acc, key),
                             ));
                             (Some(current_obj.clone()), vec![commit])
                         }
                         (None, Some(current_obj)) => {
                             if !acc.is_empty() {
+                                let base = self.unreleased_base(tag_prefix).unwrap_or(current_obj);
                                 releases.push((
-                                    ReleaseRange::UnRelease(current_obj.clone()),
-                                    self.group_by_commit_type(acc),
+                                    ReleaseRange::UnRelease(base.clone()),
+                                    self.group_by_commit_type(acc, key),
                                 ));
                             }
                             (Some(current_obj.clone()), vec![commit])
@@ -72,28 +132,112 @@ impl Commits {
                     }
                 });
 
-        let bmap = self.group_by_commit_type(vec);
+        let bmap = self.group_by_commit_type(vec, key);
         let prev = self.prev_obj();
         match obj {
             Some(n) => releases.push((ReleaseRange::Release(prev, n), bmap)),
             None => releases.push((ReleaseRange::UnRelease(prev), bmap)),
         };
+        // No commit left in the date window
+        releases.retain(|(_, bmap)| !bmap.is_empty());
 
         releases
     }
 
+    // Name the untagged latest commit as the upcoming release
+    pub(crate) fn stamp(&mut self, version: Version, datetime: DateTime<FixedOffset>) {
+        if let Some(commit) = self.commits.first_mut().filter(|c| c.obj.is_none()) {
+            commit.obj = Some(NamableObj::Tag {
+                version,
+                datetime,
+                aliases: Vec::new(),
+                message: None,
+                signer: None,
+            });
+        }
+    }
+
+    // Unreleased commits are compared with the highest version in the range,
+    // not the nearest tag. ex) 1.0.1 tagged on a maintenance branch after 2.0.0
+    pub fn unreleased_base(&self, tag_prefix: Option<&str>) -> Option<&NamableObj> {
+        self.commits
+            .iter()
+            .filter_map(|c| c.name_obj(tag_prefix))
+            .filter_map(|obj| match obj {
+                NamableObj::Tag { version, .. } => Some((version.semver(), obj)),
+                _ => None,
+            })
+            .max_by(|(a, _), (b, _)| a.cmp(b))
+            .map(|(_, obj)| obj)
+    }
+
+    // Bounds of the whole scanned range. None of the end is HEAD
+    pub fn range(&self, tag_prefix: Option<&str>) -> (NamableObj, Option<NamableObj>) {
+        let end = self
+            .commits
+            .first()
+            .and_then(|c| c.name_obj(tag_prefix))
+            .cloned();
+        (self.prev_obj(), end)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Commit> {
+        self.commits.iter().filter(move |c| self.in_window(c))
+    }
+
+    // Original commit id => the commit reverting it, only when both are in the range
+    pub fn reverted(&self) -> HashMap<LibOid, &Commit> {
+        self.commits
+            .iter()
+            .filter_map(|c| c.reverts().map(|id| (*id, c)))
+            .filter(|(id, _)| self.commits.iter().any(|c| c.id == *id))
+            .collect()
+    }
+
+    // All commits in the range grouped by the author, ordered by the author name
+    pub fn group_by_author(
+        &self,
+        key: &dyn Fn(&Commit) -> SectionKey,
+    ) -> BTreeMap<&Author, BTreeMap<SectionKey, Vec<&Commit>>> {
+        self.commits
+            .iter()
+            .fold(
+                BTreeMap::new(),
+                |mut acc: BTreeMap<&Author, Vec<&Commit>>, c| {
+                    acc.entry(c.author()).or_default().push(c);
+                    acc
+                },
+            )
+            .into_iter()
+            .map(|(author, vec)| (author, self.group_by_commit_type(vec, key)))
+            .filter(|(_, bmap)| !bmap.is_empty())
+            .collect()
+    }
+
+    // The oldest release spans the whole history
+    pub fn is_unbounded(&self) -> bool {
+        self.prev.is_empty()
+    }
+
     fn group_by_commit_type<'a>(
         &self,
         vec: Vec<&'a Commit>,
-    ) -> BTreeMap<CommitType, Vec<&'a Commit>> {
+        key: &dyn Fn(&Commit) -> SectionKey,
+    ) -> BTreeMap<SectionKey, Vec<&'a Commit>> {
         vec.into_iter()
-            .map(|x| (x.raw_type(), x))
+            .filter(|x| self.in_window(x))
+            .map(|x| (key(x), x))
             .fold(BTreeMap::new(), |mut acc, (k, v)| {
                 acc.entry(k).or_insert_with(Vec::new).push(v);
                 acc
             })
     }
 
+    fn in_window(&self, commit: &Commit) -> bool {
+        self.since.is_none_or(|since| commit.datetime >= since)
+            && self.until.is_none_or(|until| commit.datetime <= until)
+    }
+
     fn prev_obj(&self) -> NamableObj {
         match self.prev.obj.as_ref() {
             Some(n) => n.clone(),
@@ -108,11 +252,17 @@ impl Commits {
 pub enum NamableObj {
     Commit {
         short_hash: String,
-        datetime: DateTime<Utc>,
+        datetime: DateTime<FixedOffset>,
     },
     Tag {
         version: Version,
-        datetime: DateTime<Utc>,
+        datetime: DateTime<FixedOffset>,
+        // Non version tags on the same commit. ex) stable
+        aliases: Vec<String>,
+        // Annotation of the version tag, None for lightweight tags
+        message: Option<String>,
+        // Tagger of the signed tag, not the owner of the signing key
+        signer: Option<String>,
     },
 }
 
@@ -127,11 +277,23 @@ impl NamableObj {
             NamableObj::Tag {
                 version: v,
                 datetime: _,
+                aliases: _,
+                message: _,
+                signer: _,
             } => v.to_string(),
         }
     }
-    pub fn date(&self) -> String {
-        let datetime = match self {
+
+    // The name without the tag prefix. ex) v0.2.0 => 0.2.0
+    pub fn bare_name(&self) -> String {
+        match self {
+            NamableObj::Tag { version, .. } => version.bare().to_string(),
+            _ => self.name(),
+        }
+    }
+
+    pub fn datetime(&self) -> &DateTime<FixedOffset> {
+        match self {
             NamableObj::Commit {
                 short_hash: _,
                 datetime: d,
@@ -139,21 +301,51 @@ impl NamableObj {
             NamableObj::Tag {
                 version: _,
                 datetime: d,
+                aliases: _,
+                message: _,
+                signer: _,
             } => d,
-        };
-        datetime.format("%Y-%m-%d").to_string()
+        }
+    }
+
+    pub fn version(&self) -> Option<&Version> {
+        match self {
+            NamableObj::Tag { version, .. } => Some(version),
+            _ => None,
+        }
+    }
+
+    pub fn aliases(&self) -> &[String] {
+        match self {
+            NamableObj::Tag { aliases, .. } => aliases,
+            _ => &[],
+        }
+    }
+
+    pub fn message(&self) -> Option<&str> {
+        match self {
+            NamableObj::Tag { message, .. } => message.as_deref(),
+            _ => None,
+        }
+    }
+
+    pub fn signer(&self) -> Option<&str> {
+        match self {
+            NamableObj::Tag { signer, .. } => signer.as_deref(),
+            _ => None,
+        }
     }
 }
 
-#[derive(Debug, Eq, Clone, PartialEq, Hash, Default)]
+#[derive(Debug, Eq, Clone, PartialEq, Hash, Default, PartialOrd, Ord)]
 pub struct Author {
     name: Option<String>,
     email: Option<String>,
 }
 
 impl Author {
-    pub fn name(&self) -> &str {
-        self.name.as_deref().unwrap_or("Unknown")
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
     }
 
     pub fn email(&self) -> Option<&str> {
@@ -194,16 +386,37 @@ impl FromStr for Author {
     }
 }
 
+// Whose signature is shown as the author of the commit
+#[derive(Clone, Debug, Default, PartialEq, EnumString)]
+#[strum(serialize_all = "kebab-case")]
+pub enum Identity {
+    #[default]
+    Author,
+    // Differs from the author on the rebased or cherry-picked commits
+    Committer,
+}
+
 // TODO Separate responsibilities
 #[derive(Debug, Eq, Clone, PartialEq, Hash)]
 pub struct Commit {
     pub id: LibOid,
     summary: String,
     author: Author,
-    datetime: DateTime<Utc>,
-    parent_count: usize,
+    datetime: DateTime<FixedOffset>,
+    parents: Vec<LibOid>,
     cc: Option<ConventionalCommits>,
     obj: Option<NamableObj>,
+    reverts: Option<LibOid>,
+    stats: Option<Stats>,
+    // The GPG or SSH signature is good by `git verify-commit`
+    verified: bool,
+}
+
+#[derive(Debug, Eq, Clone, PartialEq, Hash)]
+pub struct Stats {
+    pub files: usize,
+    pub insertions: usize,
+    pub deletions: usize,
 }
 
 impl Commit {
@@ -211,8 +424,8 @@ impl Commit {
         id: LibOid,
         summary: &str,
         author: &str,
-        datetime: DateTime<Utc>,
-        parent_count: usize,
+        datetime: DateTime<FixedOffset>,
+        parents: Vec<LibOid>,
         cc: Option<ConventionalCommits>,
         obj: Option<NamableObj>,
     ) -> Result<Self> {
@@ -221,29 +434,54 @@ impl Commit {
             summary: String::from(summary),
             author: Author::from_str(author)?,
             datetime,
-            parent_count,
+            parents,
             cc,
             obj,
+            reverts: None,
+            stats: None,
+            verified: false,
         })
     }
 
-    pub fn empty() -> Result<Self> {
+    pub fn empty(now: DateTime<Utc>) -> Result<Self> {
         let id = Oid::from_str(EMPTY_HASH)?;
-        Self::new(id, "", "", Utc::now(), 1, None, None)
+        Self::new(id, "", "", now.into(), Vec::new(), None, None)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.hash() == EMPTY_HASH
     }
 
     pub fn short_hash(&self) -> String {
         self.hash().chars().take(7).collect()
     }
 
+    pub fn parent_short_hashes(&self) -> Vec<String> {
+        self.parents
+            .iter()
+            .map(|id| id.to_string().chars().take(7).collect())
+            .collect()
+    }
+
     pub fn hash(&self) -> String {
         self.id.to_string()
     }
 
+    pub fn is_breaking(&self) -> bool {
+        self.cc.as_ref().is_some_and(|c| c.is_breaking())
+    }
+
+    pub fn scope(&self) -> Option<&str> {
+        self.cc.as_ref().and_then(|c| c.scope.as_deref())
+    }
+
+    // The commits made by `git revert` are detected by the `This reverts commit <sha>.` line
     pub fn raw_type(&self) -> CommitType {
-        self.cc
-            .as_ref()
-            .map_or_else(|| CommitType::Others, |c| c.raw_type())
+        match (self.cc.as_ref(), self.reverts) {
+            (Some(c), _) => c.raw_type(),
+            (None, Some(_)) => CommitType::Revert,
+            (None, None) => CommitType::Others,
+        }
     }
 
     pub fn message(&self) -> String {
@@ -252,6 +490,54 @@ impl Commit {
             .map_or_else(|| self.summary.clone(), |c| c.description.clone())
     }
 
+    pub fn stats(&self) -> Option<&Stats> {
+        self.stats.as_ref()
+    }
+
+    // The commit reverted by this commit
+    pub fn reverts(&self) -> Option<&LibOid> {
+        self.reverts.as_ref()
+    }
+
+    pub fn body(&self) -> Option<&str> {
+        self.cc.as_ref().and_then(|c| c.body.as_deref())
+    }
+
+    // The issue references of the closing footers. ex) Closes #12, Fixes: org/repo#12
+    pub fn closes(&self) -> Vec<String> {
+        const KEYWORDS: &[&str] = &[
+            "close", "closes", "closed", "fix", "fixes", "fixed", "resolve", "resolves", "resolved",
+        ];
+        self.footers()
+            .iter()
+            .filter(|f| KEYWORDS.contains(&f.token.to_lowercase().as_str()))
+            .flat_map(|f| f.value.split(|c: char| c == ',' || c.is_whitespace()))
+            .filter(|r| !r.is_empty())
+            // The `#` is the separator of `Closes #12`
+            .map(|r| match r.parse::<u64>() {
+                Ok(_) => format!("#{}", r),
+                Err(_) => r.to_string(),
+            })
+            .collect()
+    }
+
+    // The git trailers in the last paragraph. ex) Reviewed-by: Z
+    pub fn footers(&self) -> &[Footer] {
+        self.cc.as_ref().map_or(&[], |c| c.footers.as_slice())
+    }
+
+    pub fn deprecation(&self) -> Option<&str> {
+        self.cc.as_ref().and_then(|c| c.footer("Deprecated"))
+    }
+
+    pub fn breaking_change(&self) -> Option<&str> {
+        self.cc.as_ref().and_then(|c| c.breaking_change())
+    }
+
+    pub fn datetime(&self) -> &DateTime<FixedOffset> {
+        &self.datetime
+    }
+
     pub fn author(&self) -> &Author {
         &self.author
     }
@@ -271,8 +557,48 @@ impl Commit {
         }
     }
 
+    // The commits made by `git revert` aren't conventional, but have the type
+    pub fn is_conventional(&self) -> bool {
+        self.cc.is_some() || self.reverts.is_some()
+    }
+
+    pub fn is_verified(&self) -> bool {
+        self.verified
+    }
+
+    // The commit pointed by the version tag
+    pub fn is_release(&self) -> bool {
+        matches!(self.obj, Some(NamableObj::Tag { .. }))
+    }
+
     pub(crate) fn parent_count(&self) -> usize {
-        self.parent_count
+        self.parents.len()
+    }
+
+    #[cfg(test)]
+    pub(crate) fn with_reverts(mut self, message: &str) -> Self {
+        self.reverts = reverted_id(message);
+        self
+    }
+
+    #[cfg(test)]
+    pub(crate) fn with_verified(mut self) -> Self {
+        self.verified = true;
+        self
+    }
+
+    #[cfg(test)]
+    pub(crate) fn with_parents(mut self, parents: Vec<LibOid>) -> Self {
+        self.parents = parents;
+        self
+    }
+
+    #[cfg(test)]
+    pub(crate) fn with_tag_message(mut self, message: &str) -> Self {
+        if let Some(NamableObj::Tag { message: m, .. }) = self.obj.as_mut() {
+            *m = Some(message.to_string());
+        }
+        self
     }
 }
 
@@ -289,52 +615,207 @@ impl Ord for Commit {
     }
 }
 
-impl<'a> From<LibCommit<'a>> for Commit {
-    fn from(commit: LibCommit<'a>) -> Self {
+// Make a commit with the tags and the header pattern resolved once
+pub(crate) struct CommitParser<'a> {
+    tags: &'a Tags,
+    header_pattern: Option<&'a Regex>,
+    // The diff stats are computed only with the repository
+    repo: Option<&'a Repository>,
+    identity: Identity,
+    path_filter: Option<&'a Pattern>,
+    // Canonical names and emails. ex) .mailmap
+    mailmap: Option<Mailmap>,
+    type_aliases: Option<&'a HashMap<String, CommitType>>,
+    strip_leading_emoji: bool,
+    // The signatures of the release commits are verified only with the repository
+    verify_repo: Option<&'a Repository>,
+    // The failure to run git is warned only once
+    verify_warned: Cell<bool>,
+}
+
+impl<'a> CommitParser<'a> {
+    pub(crate) fn new(tags: &'a Tags, header_pattern: Option<&'a Regex>) -> Self {
+        CommitParser {
+            tags,
+            header_pattern,
+            repo: None,
+            identity: Identity::default(),
+            path_filter: None,
+            mailmap: None,
+            type_aliases: None,
+            strip_leading_emoji: false,
+            verify_repo: None,
+            verify_warned: Cell::new(false),
+        }
+    }
+
+    pub(crate) fn with_path_filter(mut self, pattern: Option<&'a Pattern>) -> Self {
+        self.path_filter = pattern;
+        self
+    }
+
+    // Whether the commit changed any file matched by the path filter. Always true without the filter
+    pub(crate) fn is_in_path(&self, repo: &Repository, commit: &LibCommit) -> bool {
+        match self.path_filter {
+            Some(pattern) => touches(repo, commit, pattern).unwrap_or(false),
+            None => true,
+        }
+    }
+
+    pub(crate) fn with_identity(mut self, identity: Identity) -> Self {
+        self.identity = identity;
+        self
+    }
+
+    pub(crate) fn with_mailmap(mut self, mailmap: Option<Mailmap>) -> Self {
+        self.mailmap = mailmap;
+        self
+    }
+
+    pub(crate) fn with_type_aliases(mut self, aliases: &'a HashMap<String, CommitType>) -> Self {
+        self.type_aliases = Some(aliases);
+        self
+    }
+
+    pub(crate) fn with_strip_leading_emoji(mut self, strip: bool) -> Self {
+        self.strip_leading_emoji = strip;
+        self
+    }
+
+    pub(crate) fn with_release_verification(mut self, repo: &'a Repository) -> Self {
+        self.verify_repo = Some(repo);
+        self
+    }
+
+    pub(crate) fn with_stats(mut self, repo: &'a Repository) -> Self {
+        self.repo = Some(repo);
+        self
+    }
+
+    pub(crate) fn parse(&self, commit: LibCommit) -> Commit {
         let id = commit.id();
 
         let summary = commit.summary().map(String::from).unwrap_or_default();
 
-        let author = Author::from(commit.author());
-        let datetime = DateTime::from_utc(
-            NaiveDateTime::from_timestamp(commit.time().seconds(), 0),
-            Utc,
-        );
-        let parent_count = commit.parent_count();
-        let cc = ConventionalCommits::from_str(commit.message().unwrap_or_default()).ok();
-        // TODO check tag_prefix pattern
-        let desc = commit
-            .as_object()
-            .describe(
-                DescribeOptions::new()
-                    .describe_tags()
-                    // value:0 is --exact-match option
-                    // https://libgit2.org/libgit2/ex/HEAD/describe.html#git_describe_options_init-1
-                    .max_candidates_tags(0),
-            )
-            .ok();
-
-        let obj = desc.and_then(|x| {
-            let name = x.format(None).unwrap_or_default();
-            let version = Version::from_str(name.as_str()).ok();
-            version.map(|x| NamableObj::Tag {
-                version: x,
-                datetime,
-            })
+        let sig = match self.identity {
+            Identity::Author => commit.author(),
+            Identity::Committer => commit.committer(),
+        };
+        let author = match self.mailmap.as_ref().map(|m| m.resolve_signature(&sig)) {
+            Some(Ok(resolved)) => Author::from(resolved),
+            _ => Author::from(sig),
+        };
+        // Keep the offset of the committer for the displayed date
+        let time = commit.time();
+        let datetime = FixedOffset::east(time.offset_minutes() * 60).timestamp(time.seconds(), 0);
+        let parents = commit.parent_ids().collect();
+        let message = commit.message().unwrap_or_default();
+        let header = if self.strip_leading_emoji {
+            strip_leading_emoji(message)
+        } else {
+            message
+        };
+        let cc = ConventionalCommits::parse(header, self.header_pattern)
+            .ok()
+            .map(|cc| match self.type_aliases {
+                Some(aliases) => cc.with_type_aliases(aliases),
+                None => cc,
+            });
+        let obj = self.tags.get(&id).map(|v| NamableObj::Tag {
+            version: v.clone(),
+            datetime,
+            aliases: self.tags.aliases(&id).to_vec(),
+            message: self.tags.message(v).map(String::from),
+            signer: self.tags.signer(v).map(String::from),
         });
+        let stats = self.repo.and_then(|r| stats(r, &commit).ok());
+        let verified = match self.verify_repo {
+            Some(r) if obj.is_some() && is_signed(&commit) => verify(r, id).unwrap_or_else(|e| {
+                if !self.verify_warned.replace(true) {
+                    warn!("The release commits aren't verified: {:#}", e);
+                }
+                false
+            }),
+            _ => false,
+        };
 
         Commit {
             id,
             summary,
             author,
             datetime,
-            parent_count,
+            parents,
             cc,
             obj,
+            reverts: reverted_id(message),
+            stats,
+            verified,
         }
     }
 }
 
+// Has the GPG or SSH signature, it isn't verified
+fn is_signed(commit: &LibCommit) -> bool {
+    commit.header_field_bytes("gpgsig").is_ok()
+}
+
+// Checked by git with the user's gpg or ssh settings, libgit2 can't verify the signatures
+// Only the bad signatures are false, the failure to run git is the error
+fn verify(repo: &Repository, id: LibOid) -> Result<bool> {
+    let status = Command::new("git")
+        .arg("--git-dir")
+        .arg(repo.path())
+        .args(["verify-commit", &id.to_string()])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .context("Failed to run git verify-commit")?;
+    Ok(status.success())
+}
+
+// Diff against the first parent, or the empty tree for the root commit
+fn diff<'r>(repo: &'r Repository, commit: &LibCommit) -> Result<Diff<'r>> {
+    let tree = commit.tree()?;
+    let parent = match commit.parent_count() {
+        0 => None,
+        _ => Some(commit.parent(0)?.tree()?),
+    };
+    Ok(repo.diff_tree_to_tree(parent.as_ref(), Some(&tree), None)?)
+}
+
+fn stats(repo: &Repository, commit: &LibCommit) -> Result<Stats> {
+    let stats = diff(repo, commit)?.stats()?;
+    Ok(Stats {
+        files: stats.files_changed(),
+        insertions: stats.insertions(),
+        deletions: stats.deletions(),
+    })
+}
+
+// The pattern matches the changed file or one of its directories. ex) packages/foo
+fn touches(repo: &Repository, commit: &LibCommit, pattern: &Pattern) -> Result<bool> {
+    let matched = diff(repo, commit)?.deltas().any(|delta| {
+        [delta.old_file().path(), delta.new_file().path()]
+            .iter()
+            .flatten()
+            .flat_map(|path| path.ancestors())
+            .filter(|path| *path != Path::new(""))
+            .any(|path| pattern.matches_path(path))
+    });
+    Ok(matched)
+}
+
+// Message generated by `git revert`
+fn reverted_id(message: &str) -> Option<LibOid> {
+    lazy_static! {
+        static ref REVERT_PATTERN: Regex =
+            Regex::new(r"This reverts commit (?P<id>[0-9a-f]{40})").unwrap();
+    }
+    REVERT_PATTERN
+        .captures(message)
+        .and_then(|c| LibOid::from_str(&c["id"]).ok())
+}
+
 #[derive(Debug, PartialEq)]
 pub(super) struct ScanRange {
     latest: Option<Commit>,
@@ -362,7 +843,7 @@ impl ScanRange {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::git::repository::Findable;
+    use crate::git::repository::{Findable, TagFindable};
     use crate::git::tests::{dummy_commit, git_dir};
     use crate::git::version::Version;
     use anyhow::Result;
@@ -373,8 +854,9 @@ mod tests {
         let git_dir = git_dir(1)?;
         let repo = Repository::open(git_dir)?;
         let version = Version::from_str("0.1.0")?;
+        let tags = repo.tags(None)?;
 
-        let commit = repo.find_by(&version)?;
+        let commit = repo.find_by(&version, &CommitParser::new(&tags, None))?;
         let expected = dummy_commit(
             "9fa3647bfd047ee3c4c120a492065fa6f1c97bcb",
             "chore",
@@ -385,18 +867,101 @@ mod tests {
             "Wed Apr 29 16:29:47 2020 +0900",
             1,
             Some("0.1.0"),
-        )?;
+        )?
+        .with_parents(vec![Oid::from_str(
+            "75a1b966a31dd2250a5a82bf961c6879245b5c06",
+        )?]);
 
         assert_eq!(commit, expected);
         Ok(())
     }
 
+    #[test]
+    fn reverted_id_ok() -> Result<()> {
+        let msg = r#"Revert "feat: add 1"
+
+This reverts commit 1d185faf719f12292414c88872e3397fc5dc4e62."#;
+        let e = Oid::from_str("1d185faf719f12292414c88872e3397fc5dc4e62")?;
+        assert_eq!(reverted_id(msg), Some(e));
+
+        assert_eq!(reverted_id("feat: add 1"), None);
+        Ok(())
+    }
+
+    #[test]
+    fn identity_ok() -> Result<()> {
+        let tmp = tempfile::tempdir()?;
+        let repo = Repository::init(tmp.path())?;
+        let time = Time::new(Utc::now().timestamp(), 0);
+        let author = Signature::new("Test User", "test-user@test.com", &time)?;
+        let committer = Signature::new("Other User", "other-user@test.com", &time)?;
+        let tree = repo.find_tree(repo.index()?.write_tree()?)?;
+        let id = repo.commit(None, &author, &committer, "feat: add 1", &tree, &[])?;
+
+        let tags = Tags::default();
+        let parser = CommitParser::new(&tags, None);
+        let commit = parser.parse(repo.find_commit(id)?);
+        assert_eq!(commit.author().name(), Some("Test User"));
+
+        let parser = CommitParser::new(&tags, None).with_identity(Identity::Committer);
+        let commit = parser.parse(repo.find_commit(id)?);
+        assert_eq!(commit.author().name(), Some("Other User"));
+        assert_eq!(commit.author().email(), Some("other-user@test.com"));
+        Ok(())
+    }
+
+    #[test]
+    fn signed_ok() -> Result<()> {
+        let tmp = tempfile::tempdir()?;
+        let repo = Repository::init(tmp.path())?;
+        let time = Time::new(Utc::now().timestamp(), 0);
+        let sig = Signature::new("Test User", "test-user@test.com", &time)?;
+        let tree = repo.find_tree(repo.index()?.write_tree()?)?;
+        let unsigned = repo.commit(None, &sig, &sig, "feat: add 1", &tree, &[])?;
+        let buf = repo.commit_create_buffer(&sig, &sig, "feat: add 2", &tree, &[])?;
+        let content = buf.as_str().context("The commit buffer isn't UTF-8")?;
+        let signature = "-----BEGIN PGP SIGNATURE-----\n\nabc\n-----END PGP SIGNATURE-----";
+        let signed = repo.commit_signed(content, signature, None)?;
+
+        assert!(!is_signed(&repo.find_commit(unsigned)?));
+        assert!(is_signed(&repo.find_commit(signed)?));
+        // The dummy signature is bad
+        assert!(!verify(&repo, signed)?);
+        Ok(())
+    }
+
+    #[test]
+    fn mailmap_ok() -> Result<()> {
+        let tmp = tempfile::tempdir()?;
+        let repo = Repository::init(tmp.path())?;
+        let time = Time::new(Utc::now().timestamp(), 0);
+        let tree = repo.find_tree(repo.index()?.write_tree()?)?;
+        let old = Signature::new("test user", "old@test.com", &time)?;
+        let id1 = repo.commit(None, &old, &old, "feat: add 1", &tree, &[])?;
+        let work = Signature::new("Test U.", "work@test.com", &time)?;
+        let id2 = repo.commit(None, &work, &work, "feat: add 2", &tree, &[])?;
+        let mailmap = "Test User <test-user@test.com> <old@test.com>\nTest User <test-user@test.com> <work@test.com>\n";
+        std::fs::write(tmp.path().join(".mailmap"), mailmap)?;
+
+        let tags = Tags::default();
+        let parser = CommitParser::new(&tags, None).with_mailmap(Some(repo.mailmap()?));
+        for id in &[id1, id2] {
+            let commit = parser.parse(repo.find_commit(*id)?);
+            assert_eq!(commit.author().name(), Some("Test User"));
+            assert_eq!(commit.author().email(), Some("test-user@test.com"));
+        }
+
+        let commit = CommitParser::new(&tags, None).parse(repo.find_commit(id1)?);
+        assert_eq!(commit.author().name(), Some("test user"));
+        Ok(())
+    }
+
     #[test]
     fn author_from_str_ok() -> Result<()> {
         let a = Author::from_str("Test User <test-user@test.com>")?;
 
         let e = "Test User";
-        assert_eq!(a.name(), e);
+        assert_eq!(a.name(), Some(e));
 
         let e = "test-user@test.com";
         assert_eq!(a.email, Some(String::from(e)));
@@ -410,7 +975,7 @@ mod tests {
         let a = Author::from(sig);
 
         let e = "Test User";
-        assert_eq!(a.name(), e);
+        assert_eq!(a.name(), Some(e));
 
         let e = "test-user@test.com";
         assert_eq!(a.email, Some(String::from(e)));
@@ -1,7 +1,8 @@
 use anyhow::*;
 use inflector::Inflector;
 use lazy_static::*;
-use regex::Regex;
+use regex::{Captures, Regex};
+use std::collections::HashMap;
 use std::str::FromStr;
 use std::string::ToString;
 use strum::EnumMessage;
@@ -26,15 +27,17 @@ pub enum CommitType {
 }
 
 // Not available EnumString for custom type
+// Case-insensitive, so `Feat` and `FIX` are the same as `feat` and `fix`
 impl FromStr for CommitType {
     type Err = strum::ParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s {
+        let s = s.to_lowercase();
+        match s.as_str() {
             "feat" => Ok(CommitType::Feat),
             "fix" => Ok(CommitType::Fix),
             "build" => Ok(CommitType::Build),
-            "doc" => Ok(CommitType::Doc),
+            "doc" | "docs" => Ok(CommitType::Doc),
             "chore" => Ok(CommitType::Chore),
             "ci" => Ok(CommitType::Ci),
             "style" => Ok(CommitType::Style),
@@ -44,7 +47,17 @@ impl FromStr for CommitType {
             "revert" => Ok(CommitType::Revert),
             "security" => Ok(CommitType::Security),
             "others" => Ok(CommitType::Others),
-            _ => Ok(CommitType::Custom(s.to_string())),
+            _ => Ok(CommitType::Custom(s)),
+        }
+    }
+}
+
+impl CommitType {
+    // The type as written in the commit header. ex) feat
+    pub fn token(&self) -> String {
+        match self {
+            CommitType::Custom(s) => s.clone(),
+            _ => self.as_ref().to_lowercase(),
         }
     }
 }
@@ -60,12 +73,21 @@ impl std::fmt::Display for CommitType {
     }
 }
 
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub struct Footer {
+    pub token: String,
+    pub value: String,
+}
+
 #[derive(Debug, PartialEq, Eq, Clone, Hash)]
 pub struct ConventionalCommits {
     break_change: bool,
     pub _type: CommitType,
     pub scope: Option<String>,
     pub description: String,
+    // The body without the footers
+    pub body: Option<String>,
+    pub footers: Vec<Footer>,
 }
 
 impl ConventionalCommits {
@@ -81,26 +103,123 @@ impl ConventionalCommits {
             _type,
             scope,
             description: String::from(description),
+            body: None,
+            footers: Vec::new(),
+        }
+    }
+
+    // The `!` is only looked up in the parsed header, not anywhere in the summary
+    fn break_change(cap: &Captures, footers: &[Footer]) -> bool {
+        cap.name("breaking").is_some()
+            || footers
+                .iter()
+                .any(|f| f.token == "BREAKING CHANGE" || f.token == "BREAKING-CHANGE")
+    }
+
+    // Footers are only in the last paragraph of the body, after a blank line
+    // https://www.conventionalcommits.org/en/v1.0.0/#specification
+    fn footers(body: Option<&str>) -> Vec<Footer> {
+        lazy_static! {
+            static ref FOOTER_PATTERN: Regex = Regex::new(
+                r"^(?P<token>BREAKING CHANGE|BREAKING-CHANGE|[a-zA-Z-]+)(?:: | #)(?P<value>.*)$"
+            )
+            .unwrap();
+        }
+
+        // The body starts with the line break after the summary
+        let body = body.map(str::trim_end).unwrap_or_default();
+        let paragraph = match body.rfind("\n\n") {
+            Some(i) => &body[i + 2..],
+            None if body.starts_with('\n') => &body[1..],
+            None => return Vec::new(),
+        };
+
+        let mut footers: Vec<Footer> = Vec::new();
+        for line in paragraph.lines() {
+            match (FOOTER_PATTERN.captures(line), footers.last_mut()) {
+                (Some(cap), _) => footers.push(Footer {
+                    token: cap["token"].to_string(),
+                    value: cap["value"].to_string(),
+                }),
+                // Multi-line value
+                (None, Some(footer)) => {
+                    footer.value.push('\n');
+                    footer.value.push_str(line);
+                }
+                (None, None) => return Vec::new(),
+            }
+        }
+        footers
+    }
+
+    // The footers paragraph is dropped from the body
+    fn body(body: Option<&str>, footers: &[Footer]) -> Option<String> {
+        let body = body.map(str::trim_end).unwrap_or_default();
+        let body = match body.rfind("\n\n") {
+            Some(i) if !footers.is_empty() => &body[..i],
+            None if !footers.is_empty() => "",
+            _ => body,
+        };
+        let body = body.trim();
+        if body.is_empty() {
+            None
+        } else {
+            Some(body.to_string())
         }
     }
 
-    fn break_change(summary: &str, body: Option<&str>) -> bool {
-        summary.contains("!:") || body.map_or_else(|| false, |s| s.contains("BREAKING CHANGE: "))
+    // Quoted scopes may have spaces. ex) feat("user profile") and feat(user profile) are the same
+    fn scope(s: &str) -> Option<String> {
+        let s = s.trim();
+        let s = ['"', '\'']
+            .iter()
+            .find_map(|q| s.strip_prefix(*q).and_then(|x| x.strip_suffix(*q)))
+            .unwrap_or(s)
+            .trim();
+        if s.is_empty() {
+            None
+        } else {
+            Some(s.to_string())
+        }
+    }
+
+    pub fn is_breaking(&self) -> bool {
+        self.break_change
+    }
+
+    pub fn footer(&self, token: &str) -> Option<&str> {
+        self.footers
+            .iter()
+            .find(|f| f.token == token)
+            .map(|f| f.value.as_str())
+    }
+
+    pub fn breaking_change(&self) -> Option<&str> {
+        self.footer("BREAKING CHANGE")
+            .or_else(|| self.footer("BREAKING-CHANGE"))
     }
 
     pub fn raw_type(&self) -> CommitType {
         self._type.clone()
     }
-}
 
-impl FromStr for ConventionalCommits {
-    type Err = anyhow::Error;
+    // Project-specific types. ex) wip => chore
+    pub(crate) fn with_type_aliases(mut self, aliases: &HashMap<String, CommitType>) -> Self {
+        if let Some(t) = aliases.get(&self._type.token()) {
+            self._type = t.clone();
+        }
+        self
+    }
+}
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
+impl ConventionalCommits {
+    // The pattern needs `type` and `description` capture groups and may have `scope` and `breaking` capture groups
+    pub fn parse(s: &str, pattern: Option<&Regex>) -> Result<Self> {
         lazy_static! {
-            static ref CONVENTIONAL_COMMIT_PATTERN: Regex =
-                Regex::new(r"^(?P<type>[a-zA-Z-_]+?)(?P<scope>\(.+?\))?!?: (?P<description>.+?)$")
-                    .unwrap();
+            static ref CONVENTIONAL_COMMIT_PATTERN: Regex = Regex::new(
+                r"^(?P<type>[a-zA-Z-_]+?)(?:\((?P<scope>.+?)\))?(?P<breaking>!)?: (?P<description>.+?)$"
+            )
+            .unwrap();
         }
         let lines = s.splitn(2, '\n').collect::<Vec<&str>>();
         let (summary, body) = if lines.len() == 2 {
@@ -109,32 +228,58 @@ impl FromStr for ConventionalCommits {
             (s, None)
         };
 
-        let cap = CONVENTIONAL_COMMIT_PATTERN
-            .captures(&summary)
+        let cap = pattern
+            .unwrap_or(&CONVENTIONAL_COMMIT_PATTERN)
+            .captures(summary)
             .ok_or_else(|| anyhow!("Invalid conventional commits format"))?;
         let _type = cap
             .name("type")
             .context("Invalid conventional commits format")?
             .as_str()
             .to_string();
-        let scope = cap.name("scope").map(|s| String::from(s.as_str()));
+        let scope = cap.name("scope").and_then(|s| Self::scope(s.as_str()));
         let description = cap
             .name("description")
             .context("Invalid conventional commits format")?
             .as_str()
             .to_string();
 
+        let footers = Self::footers(body);
         let cc = ConventionalCommits {
-            break_change: Self::break_change(summary, body),
+            break_change: Self::break_change(&cap, &footers),
             _type: CommitType::from_str(&_type)?,
             scope,
             description,
+            body: Self::body(body, &footers),
+            footers,
         };
 
         Ok(cc)
     }
 }
 
+// Gitmoji before the type. ex) ✨ feat: add x, :sparkles: feat: add x
+pub(crate) fn strip_leading_emoji(s: &str) -> &str {
+    lazy_static! {
+        static ref LEADING_EMOJI: Regex = Regex::new(
+            r"^(?:(?::[\w+-]+:|[\p{Extended_Pictographic}\p{Emoji_Modifier}\u{200D}\u{FE0F}]+)\s*)+"
+        )
+        .unwrap();
+    }
+    match LEADING_EMOJI.find(s) {
+        Some(m) => &s[m.end()..],
+        None => s,
+    }
+}
+
+impl FromStr for ConventionalCommits {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ConventionalCommits::parse(s, None)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -153,6 +298,222 @@ mod tests {
         let a = CommitType::from_str("test")?.to_string();
         let e = "Test";
         assert_eq!(a, e);
+
+        assert_eq!(CommitType::from_str("ci")?.token(), "ci");
+        assert_eq!(CommitType::from_str("unknown")?.token(), "unknown");
+        Ok(())
+    }
+
+    #[test]
+    fn commit_type_case_insensitive_ok() -> Result<()> {
+        assert_eq!(CommitType::from_str("Feat")?, CommitType::Feat);
+        assert_eq!(CommitType::from_str("FIX")?, CommitType::Fix);
+        assert_eq!(CommitType::from_str("DOCS")?, CommitType::Doc);
+        assert_eq!(
+            CommitType::from_str("Deps")?,
+            CommitType::Custom(String::from("deps"))
+        );
+
+        let cc = ConventionalCommits::from_str("Feat: add x")?;
+        assert_eq!(cc._type, CommitType::Feat);
+        Ok(())
+    }
+
+    #[test]
+    fn type_aliases_ok() -> Result<()> {
+        let mut aliases = HashMap::new();
+        aliases.insert(String::from("wip"), CommitType::Chore);
+
+        let cc = ConventionalCommits::from_str("WIP: add x")?.with_type_aliases(&aliases);
+        assert_eq!(cc._type, CommitType::Chore);
+        let cc = ConventionalCommits::from_str("deps: bump x")?.with_type_aliases(&aliases);
+        assert_eq!(cc._type, CommitType::Custom(String::from("deps")));
+        Ok(())
+    }
+
+    #[test]
+    fn header_pattern_ok() -> Result<()> {
+        let re = Regex::new(r"^\[(?P<type>\w+)\] (?P<description>.+)$")?;
+        let cc = ConventionalCommits::parse("[feat] add x", Some(&re))?;
+        assert_eq!(cc._type, CommitType::Feat);
+        assert_eq!(cc.scope, None);
+        assert_eq!(cc.description, "add x");
+
+        let re = Regex::new(r"^(?P<type>\w+)/(?P<scope>\w+) - (?P<description>.+)$")?;
+        let cc = ConventionalCommits::parse("fix/api - fix x", Some(&re))?;
+        assert_eq!(cc._type, CommitType::Fix);
+        assert_eq!(cc.scope, Some(String::from("api")));
+        assert_eq!(cc.description, "fix x");
+
+        assert!(ConventionalCommits::parse("feat: add x", Some(&re)).is_err());
+        assert!(ConventionalCommits::parse("[feat] add x", None).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn scope_ok() -> Result<()> {
+        let cc = ConventionalCommits::from_str("feat(ui/login): add x")?;
+        assert_eq!(cc.scope.as_deref(), Some("ui/login"));
+
+        let cc = ConventionalCommits::from_str(r#"feat("user profile"): add x"#)?;
+        assert_eq!(cc.scope.as_deref(), Some("user profile"));
+        assert_eq!(cc.description, "add x");
+
+        let cc = ConventionalCommits::from_str("feat( 'user profile' )!: add x")?;
+        assert_eq!(cc.scope.as_deref(), Some("user profile"));
+        assert!(cc.is_breaking());
+
+        let cc = ConventionalCommits::from_str(r#"feat(""): add x"#)?;
+        assert_eq!(cc.scope, None);
+        Ok(())
+    }
+
+    #[test]
+    fn parse_edge_ng() -> Result<()> {
+        for s in &[
+            "", ":", ": ", "feat:", "feat: ", "(api): x", "feat(: x", "\n\n", "\n: x",
+        ] {
+            assert!(ConventionalCommits::from_str(s).is_err(), "{:?}", s);
+        }
+        let long = "a".repeat(10_000);
+        assert!(ConventionalCommits::from_str(&long).is_err());
+
+        let cc = ConventionalCommits::from_str(&format!("feat: {}", long))?;
+        assert_eq!(cc.description, long);
+        let cc = ConventionalCommits::from_str("fix: x\n\n\n")?;
+        assert!(cc.footers.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn footers_ok() -> Result<()> {
+        let msg = r#"feat: add new api
+
+Some description.
+
+Deprecated: old api will be removed
+  in the next major release
+Refs #123
+BREAKING CHANGE: drop v1"#;
+        let cc = ConventionalCommits::from_str(msg)?;
+        assert_eq!(
+            cc.footer("Deprecated"),
+            Some("old api will be removed\n  in the next major release")
+        );
+        assert_eq!(cc.footer("Refs"), Some("123"));
+        assert_eq!(cc.footer("BREAKING CHANGE"), Some("drop v1"));
+        assert_eq!(cc.footer("Unknown"), None);
+
+        let cc = ConventionalCommits::from_str("feat: add new api\n\nDeprecated here.")?;
+        assert!(cc.footers.is_empty());
+
+        let msg = "fix: fix x\n\nReviewed-by: Z\nCloses #12\nBREAKING-CHANGE: drop y";
+        let cc = ConventionalCommits::from_str(msg)?;
+        let tokens = cc
+            .footers
+            .iter()
+            .map(|f| (f.token.as_str(), f.value.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            tokens,
+            vec![
+                ("Reviewed-by", "Z"),
+                ("Closes", "12"),
+                ("BREAKING-CHANGE", "drop y")
+            ]
+        );
+        assert_eq!(cc.breaking_change(), Some("drop y"));
+        assert_eq!(cc.description, "fix x");
+
+        // Not separated from the summary by a blank line
+        let cc = ConventionalCommits::from_str("feat: add new api\nRefs: #123")?;
+        assert!(cc.footers.is_empty());
+
+        let cc = ConventionalCommits::from_str("feat: add new api")?;
+        assert!(cc.footers.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn body_ok() -> Result<()> {
+        let msg = "feat: add x\n\nFirst line\nsecond line\n\nNext paragraph\n\nRefs: #1\n";
+        let cc = ConventionalCommits::from_str(msg)?;
+        assert_eq!(
+            cc.body.as_deref(),
+            Some("First line\nsecond line\n\nNext paragraph")
+        );
+        assert_eq!(cc.description, "add x");
+
+        let cc = ConventionalCommits::from_str("feat: add x\n\nOnly the body\n")?;
+        assert_eq!(cc.body.as_deref(), Some("Only the body"));
+
+        let cc = ConventionalCommits::from_str("feat: add x\n\nRefs: #1")?;
+        assert_eq!(cc.body, None);
+
+        let cc = ConventionalCommits::from_str("feat: add x")?;
+        assert_eq!(cc.body, None);
+        Ok(())
+    }
+
+    #[test]
+    fn break_change_ok() -> Result<()> {
+        for msg in &["feat!: add x", "refactor!: move x", "perf!: speed up x"] {
+            assert!(ConventionalCommits::from_str(msg)?.break_change, "{}", msg);
+        }
+
+        let cc = ConventionalCommits::from_str("chore(db)!: drop x")?;
+        assert!(cc.break_change);
+        assert_eq!(cc._type, CommitType::Chore);
+        assert_eq!(cc.scope, Some(String::from("db")));
+
+        let cc = ConventionalCommits::from_str("feat: use a!: syntax in docs")?;
+        assert!(!cc.break_change);
+        assert_eq!(cc.description, "use a!: syntax in docs");
+
+        let cc = ConventionalCommits::from_str("fix: fix x\n\nBREAKING CHANGE: drop y")?;
+        assert!(cc.break_change);
+
+        // The hyphenated token is the same as the spaced one
+        let cc = ConventionalCommits::from_str("fix: fix x\n\nBREAKING-CHANGE: drop y")?;
+        assert!(cc.is_breaking());
+        assert_eq!(cc.breaking_change(), Some("drop y"));
+        Ok(())
+    }
+
+    #[test]
+    fn strip_leading_emoji_ok() -> Result<()> {
+        for msg in &[
+            "✨ feat: add x",
+            ":sparkles: feat: add x",
+            "✨:sparkles: feat: add x",
+            "🧑‍💻 feat: add x",
+        ] {
+            let cc = ConventionalCommits::from_str(strip_leading_emoji(msg))?;
+            assert_eq!(cc._type, CommitType::Feat, "{}", msg);
+            assert_eq!(cc.description, "add x");
+        }
+        assert!(ConventionalCommits::from_str("✨ feat: add x").is_err());
+        assert_eq!(strip_leading_emoji("feat: add :tada:"), "feat: add :tada:");
+        assert_eq!(strip_leading_emoji("1 feat: add x"), "1 feat: add x");
+        Ok(())
+    }
+
+    #[test]
+    fn break_change_marker_ok() -> Result<()> {
+        let cc = ConventionalCommits::from_str("feat!: add x")?;
+        assert!(cc.break_change);
+        assert_eq!(cc.scope, None);
+
+        let cc = ConventionalCommits::from_str("feat(x)!: add x")?;
+        assert!(cc.break_change);
+        assert_eq!(cc.scope, Some(String::from("x")));
+
+        // `!:` only in the description
+        for msg in &["fix: resolve a!: edge", "fix(x): resolve a!: edge"] {
+            let cc = ConventionalCommits::from_str(msg)?;
+            assert!(!cc.break_change, "{}", msg);
+            assert_eq!(cc.description, "resolve a!: edge");
+        }
         Ok(())
     }
 }
@@ -2,31 +2,248 @@ use super::Commit;
 use crate::git::NamableObj;
 use lazy_static::*;
 use regex::Regex;
+use std::collections::HashMap;
 
+#[derive(Debug, Clone, PartialEq, EnumString)]
+#[strum(serialize_all = "kebab-case")]
+pub enum Forge {
+    Github,
+    Gitlab,
+    Bitbucket,
+    // Forgejo shares the same paths
+    Gitea,
+}
+
+// The links overridable by the templates
+#[derive(Debug, Clone, PartialEq, Eq, Hash, EnumString)]
+#[strum(serialize_all = "kebab-case")]
+pub enum UrlKind {
+    // Placeholders: {base}, {sha}
+    Commit,
+    // Placeholders: {base}, {from}, {to}
+    Compare,
+    // Placeholders: {base}, {sha}
+    Files,
+}
+
+impl Forge {
+    // Self-hosted instances are detected by the host name. ex) gitlab.example.com
+    fn detect(base_url: &str) -> Self {
+        let host = base_url
+            .split("://")
+            .nth(1)
+            .and_then(|s| s.split('/').next())
+            .unwrap_or_default();
+        if host.contains("gitlab") {
+            Forge::Gitlab
+        } else if host == "bitbucket.org" {
+            Forge::Bitbucket
+        } else if host.contains("gitea") || host == "codeberg.org" {
+            Forge::Gitea
+        } else {
+            Forge::Github
+        }
+    }
+}
+
+// Named after GitHub, the links of the other forges are made by `forge`
 #[derive(Debug, PartialEq)]
 pub struct GithubUrl {
     base_url: String,
+    forge: Forge,
+    templates: HashMap<UrlKind, String>,
 }
 
-// TODO GitLab, GitBucket
 impl GithubUrl {
     pub(crate) fn new(url: &str) -> Self {
         let base_url = git2http(url);
-        GithubUrl { base_url }
+        let forge = Forge::detect(&base_url);
+        GithubUrl {
+            base_url,
+            forge,
+            templates: HashMap::new(),
+        }
+    }
+
+    pub(crate) fn with_forge(mut self, forge: Forge) -> Self {
+        self.forge = forge;
+        self
+    }
+
+    pub(crate) fn with_template(mut self, kind: UrlKind, template: &str) -> Self {
+        self.templates.insert(kind, template.to_string());
+        self
+    }
+
+    fn expand(&self, kind: &UrlKind, vars: &[(&str, &str)]) -> Option<String> {
+        let template = self.templates.get(kind)?;
+        let url = vars.iter().fold(
+            template.replace("{base}", &self.base_url),
+            |acc, (name, value)| acc.replace(&format!("{{{}}}", name), value),
+        );
+        Some(url)
+    }
+
+    // Local remotes (e.g. /srv/git/repo.git) can't be linked, so treat them like no remote
+    pub(crate) fn parse(url: &str) -> Option<Self> {
+        if is_local(url) {
+            return None;
+        }
+        Some(Self::new(url))
     }
 
     pub(crate) fn compare(&self, start: &NamableObj, end: Option<&NamableObj>) -> String {
-        format!(
-            "{}/compare/{}...{}",
-            self.base_url,
-            start.name(),
-            end.map_or_else(|| String::from("HEAD"), |tag| tag.name())
-        )
+        let base = &self.base_url;
+        let start = start.name();
+        let end = end.map_or_else(|| String::from("HEAD"), |tag| tag.name());
+        if let Some(url) = self.expand(&UrlKind::Compare, &[("from", &start), ("to", &end)]) {
+            return url;
+        }
+        match self.forge {
+            Forge::Github | Forge::Gitea => format!("{}/compare/{}...{}", base, start, end),
+            Forge::Gitlab => format!("{}/-/compare/{}...{}", base, start, end),
+            // The newer revision comes first
+            Forge::Bitbucket => format!("{}/branches/compare/{}..{}#diff", base, end, start),
+        }
     }
 
     pub(crate) fn commit(&self, commit: &Commit) -> String {
-        format!("{}/commit/{}", self.base_url, commit.hash(),)
+        let base = &self.base_url;
+        if let Some(url) = self.expand(&UrlKind::Commit, &[("sha", &commit.hash())]) {
+            return url;
+        }
+        match self.forge {
+            Forge::Github | Forge::Gitea => format!("{}/commit/{}", base, commit.hash()),
+            Forge::Gitlab => format!("{}/-/commit/{}", base, commit.hash()),
+            Forge::Bitbucket => format!("{}/commits/{}", base, commit.hash()),
+        }
+    }
+
+    // GitLab references outside the code spans. ex) #123 issue, !45 merge request, %v1.0 milestone
+    pub(crate) fn link_references(&self, message: &str) -> String {
+        lazy_static! {
+            static ref REFERENCE: Regex =
+                Regex::new(r#"(?P<pre>^|[\s(])(?P<ref>[#!](?P<num>[0-9]+)|%"(?P<quoted>[^"]+)"|%(?P<name>[\w.-]+))"#)
+                    .unwrap();
+        }
+
+        if self.forge != Forge::Gitlab {
+            return message.to_string();
+        }
+
+        let base = &self.base_url;
+        outside_code_spans(message, |part| {
+            REFERENCE
+                .replace_all(part, |c: &regex::Captures| {
+                    let reference = &c["ref"];
+                    let url = match (c.name("num"), c.name("quoted").or_else(|| c.name("name"))) {
+                        (Some(num), _) if reference.starts_with('#') => {
+                            format!("{}/-/issues/{}", base, num.as_str())
+                        }
+                        (Some(num), _) => format!("{}/-/merge_requests/{}", base, num.as_str()),
+                        (None, Some(name)) => format!(
+                            "{}/-/milestones?title={}",
+                            base,
+                            name.as_str().replace(' ', "+")
+                        ),
+                        _ => return c[0].to_string(),
+                    };
+                    format!("{}[{}]({})", &c["pre"], reference, url)
+                })
+                .to_string()
+        })
+    }
+
+    // Issue references outside the code spans. ex) #123, org/repo#123, GH-123
+    pub(crate) fn link_issues(&self, message: &str) -> String {
+        lazy_static! {
+            static ref ISSUE: Regex = Regex::new(
+                r"(?P<pre>^|[\s(])(?P<ref>(?:(?P<repo>[\w.-]+/[\w.-]+)#|#|(?P<gh>GH-))(?P<num>[0-9]+))\b"
+            )
+            .unwrap();
+        }
+
+        outside_code_spans(message, |part| {
+            ISSUE
+                .replace_all(part, |c: &regex::Captures| {
+                    // GH- is only the GitHub style
+                    if c.name("gh").is_some() && self.forge != Forge::Github {
+                        return c[0].to_string();
+                    }
+                    let repo = c.name("repo").map(|r| r.as_str());
+                    let url = self.issue(repo, &c["num"]);
+                    format!("{}[{}]({})", &c["pre"], &c["ref"], url)
+                })
+                .to_string()
+        })
+    }
+
+    // The other repository is on the same host. ex) org/repo#123
+    fn issue(&self, repo: Option<&str>, num: &str) -> String {
+        let base = match repo {
+            Some(r) => format!("{}/{}", self.origin(), r),
+            None => self.base_url.clone(),
+        };
+        match self.forge {
+            Forge::Github | Forge::Bitbucket | Forge::Gitea => {
+                format!("{}/issues/{}", base, num)
+            }
+            Forge::Gitlab => format!("{}/-/issues/{}", base, num),
+        }
     }
+
+    // The scheme and the host. ex) https://github.com
+    fn origin(&self) -> &str {
+        let start = self.base_url.find("://").map_or(0, |i| i + 3);
+        match self.base_url[start..].find('/') {
+            Some(i) => &self.base_url[..start + i],
+            None => &self.base_url,
+        }
+    }
+
+    pub(crate) fn files(&self, commit: &Commit) -> String {
+        let base = &self.base_url;
+        if let Some(url) = self.expand(&UrlKind::Files, &[("sha", &commit.hash())]) {
+            return url;
+        }
+        match self.forge {
+            Forge::Github | Forge::Gitea => format!("{}/commit/{}.diff", base, commit.hash()),
+            Forge::Gitlab => format!("{}/-/commit/{}.diff", base, commit.hash()),
+            // No raw diff page, the commit page lists the changed files
+            Forge::Bitbucket => format!("{}/commits/{}", base, commit.hash()),
+        }
+    }
+}
+
+// The odd parts split by the backquotes are in the code spans
+fn outside_code_spans<F>(message: &str, link: F) -> String
+where
+    F: Fn(&str) -> String,
+{
+    message
+        .split('`')
+        .enumerate()
+        .map(|(i, part)| {
+            if i % 2 == 1 {
+                part.to_string()
+            } else {
+                link(part)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("`")
+}
+
+fn is_local(url: &str) -> bool {
+    lazy_static! {
+        // scp-like syntax. ex) git@github.com:watawuwu/ccclog.git
+        static ref SCP_LIKE: Regex = Regex::new(r"^(?:[^@/]+@)?[^/:]{2,}:").unwrap();
+    }
+
+    if url.starts_with("file://") {
+        return true;
+    }
+    !(url.contains("://") || SCP_LIKE.is_match(url))
 }
 
 fn git2http(url: &str) -> String {
@@ -64,12 +281,10 @@ fn git2http(url: &str) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::git::version::Version;
-    use crate::git::NamableObj;
+    use crate::git::tests::tag;
     use anyhow::*;
     use chrono::Utc;
     use git2::Oid;
-    use std::str::FromStr;
 
     #[test]
     fn ssh_ok() {
@@ -110,19 +325,24 @@ mod tests {
         assert_eq!(a, e);
     }
 
+    #[test]
+    fn local_path_ok() {
+        assert!(GithubUrl::parse("/srv/git/repo.git").is_none());
+        assert!(GithubUrl::parse("../repo").is_none());
+        assert!(GithubUrl::parse("file:///srv/git/repo.git").is_none());
+        assert!(GithubUrl::parse("C:\\git\\repo.git").is_none());
+
+        assert!(GithubUrl::parse("git@github.com:watawuwu/ccclog.git").is_some());
+        assert!(GithubUrl::parse("ssh://git@github.com/watawuwu/ccclog.git").is_some());
+        assert!(GithubUrl::parse("https://github.com/watawuwu/ccclog.git").is_some());
+    }
+
     #[test]
     fn compare_ok() -> Result<()> {
         let url = GithubUrl::new("https://test.com/watawuwu/ccclog.git");
 
-        let datetime = Utc::now();
-        let start = NamableObj::Tag {
-            version: Version::from_str("0.1.0")?,
-            datetime,
-        };
-        let end = NamableObj::Tag {
-            version: Version::from_str("0.3.0")?,
-            datetime,
-        };
+        let start = tag("0.1.0")?;
+        let end = tag("0.3.0")?;
 
         let a = url.compare(&start, Some(&end));
         let e = "https://test.com/watawuwu/ccclog/compare/0.1.0...0.3.0";
@@ -139,8 +359,8 @@ mod tests {
             Oid::from_str("1d185faf719f12292414c88872e3397fc5dc4e62")?,
             "test summary",
             "Test User<test-user@test.com>",
-            Utc::now(),
-            1,
+            Utc::now().into(),
+            vec![Oid::zero()],
             None,
             None,
         )?;
@@ -148,6 +368,190 @@ mod tests {
         let e = "https://test.com/watawuwu/ccclog/commit/1d185faf719f12292414c88872e3397fc5dc4e62";
         assert_eq!(a, e);
 
+        let a = url.files(&commit);
+        let e =
+            "https://test.com/watawuwu/ccclog/commit/1d185faf719f12292414c88872e3397fc5dc4e62.diff";
+        assert_eq!(a, e);
+
+        Ok(())
+    }
+
+    #[test]
+    fn gitlab_compare_ok() -> Result<()> {
+        let url = GithubUrl::new("git@gitlab.example.com:watawuwu/ccclog.git");
+
+        let start = tag("0.1.0")?;
+        let end = tag("0.3.0")?;
+
+        let a = url.compare(&start, Some(&end));
+        let e = "https://gitlab.example.com/watawuwu/ccclog/-/compare/0.1.0...0.3.0";
+        assert_eq!(a, e);
+
+        let url = GithubUrl::new("https://test.com/watawuwu/ccclog.git").with_forge(Forge::Gitlab);
+        let a = url.compare(&start, None);
+        let e = "https://test.com/watawuwu/ccclog/-/compare/0.1.0...HEAD";
+        assert_eq!(a, e);
+
+        Ok(())
+    }
+
+    #[test]
+    fn gitlab_commit_ok() -> Result<()> {
+        let url = GithubUrl::new("https://gitlab.example.com/watawuwu/ccclog.git");
+
+        let commit = Commit::new(
+            Oid::from_str("1d185faf719f12292414c88872e3397fc5dc4e62")?,
+            "test summary",
+            "Test User<test-user@test.com>",
+            Utc::now().into(),
+            vec![Oid::zero()],
+            None,
+            None,
+        )?;
+        let a = url.commit(&commit);
+        let e = "https://gitlab.example.com/watawuwu/ccclog/-/commit/1d185faf719f12292414c88872e3397fc5dc4e62";
+        assert_eq!(a, e);
+
+        let a = url.files(&commit);
+        let e = "https://gitlab.example.com/watawuwu/ccclog/-/commit/1d185faf719f12292414c88872e3397fc5dc4e62.diff";
+        assert_eq!(a, e);
+
+        Ok(())
+    }
+
+    #[test]
+    fn bitbucket_ok() -> Result<()> {
+        let start = tag("0.1.0")?;
+        let end = tag("0.3.0")?;
+        let commit = Commit::new(
+            Oid::from_str("1d185faf719f12292414c88872e3397fc5dc4e62")?,
+            "test summary",
+            "Test User<test-user@test.com>",
+            Utc::now().into(),
+            vec![Oid::zero()],
+            None,
+            None,
+        )?;
+
+        for remote in &[
+            "git@bitbucket.org:team/repo.git",
+            "https://bitbucket.org/team/repo.git",
+        ] {
+            let url = GithubUrl::new(remote);
+
+            let a = url.compare(&start, Some(&end));
+            let e = "https://bitbucket.org/team/repo/branches/compare/0.3.0..0.1.0#diff";
+            assert_eq!(a, e);
+
+            let a = url.commit(&commit);
+            let e =
+                "https://bitbucket.org/team/repo/commits/1d185faf719f12292414c88872e3397fc5dc4e62";
+            assert_eq!(a, e);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn url_template_ok() -> Result<()> {
+        let start = tag("0.1.0")?;
+        let end = tag("0.3.0")?;
+        let commit = Commit::new(
+            Oid::from_str("1d185faf719f12292414c88872e3397fc5dc4e62")?,
+            "test summary",
+            "Test User<test-user@test.com>",
+            Utc::now().into(),
+            vec![Oid::zero()],
+            None,
+            None,
+        )?;
+
+        let url = GithubUrl::new("https://codeberg.org/team/repo.git");
+        assert_eq!(url.forge, Forge::Gitea);
+        let a = url.compare(&start, Some(&end));
+        assert_eq!(a, "https://codeberg.org/team/repo/compare/0.1.0...0.3.0");
+
+        let url = GithubUrl::new("https://git.example.com/team/repo.git")
+            .with_template(UrlKind::Compare, "{base}/diff/{from}..{to}")
+            .with_template(UrlKind::Commit, "{base}/c/{sha}");
+        let a = url.compare(&start, None);
+        assert_eq!(a, "https://git.example.com/team/repo/diff/0.1.0..HEAD");
+        let a = url.commit(&commit);
+        let e = "https://git.example.com/team/repo/c/1d185faf719f12292414c88872e3397fc5dc4e62";
+        assert_eq!(a, e);
+        // Not templated, the forge is used
+        let a = url.files(&commit);
+        let e = "https://git.example.com/team/repo/commit/1d185faf719f12292414c88872e3397fc5dc4e62.diff";
+        assert_eq!(a, e);
+
+        let url = url.with_template(UrlKind::Files, "{base}/c/{sha}/files");
+        let a = url.files(&commit);
+        let e =
+            "https://git.example.com/team/repo/c/1d185faf719f12292414c88872e3397fc5dc4e62/files";
+        assert_eq!(a, e);
+
         Ok(())
     }
+
+    #[test]
+    fn link_issues_ok() {
+        let url = GithubUrl::new("https://github.com/team/repo.git");
+
+        let a = url.link_issues("fix x (#123) and #45");
+        let e = "fix x ([#123](https://github.com/team/repo/issues/123)) and [#45](https://github.com/team/repo/issues/45)";
+        assert_eq!(a, e);
+
+        let a = url.link_issues("use `#123` and `a #1` in a#2 #3x");
+        assert_eq!(a, "use `#123` and `a #1` in a#2 #3x");
+
+        let url = GithubUrl::new("https://gitlab.com/team/repo.git");
+        let a = url.link_issues("#7 fix");
+        assert_eq!(a, "[#7](https://gitlab.com/team/repo/-/issues/7) fix");
+    }
+
+    #[test]
+    fn link_issues_cross_repo_ok() {
+        let url = GithubUrl::new("https://github.com/team/repo.git");
+        let a = url.link_issues("fixes org/other#12");
+        assert_eq!(
+            a,
+            "fixes [org/other#12](https://github.com/org/other/issues/12)"
+        );
+
+        let a = url.link_issues("resolves GH-12");
+        assert_eq!(
+            a,
+            "resolves [GH-12](https://github.com/team/repo/issues/12)"
+        );
+
+        let url = GithubUrl::new("git@gitlab.example.com:team/repo.git");
+        let a = url.link_issues("closes group/other#3 and GH-4");
+        assert_eq!(
+            a,
+            "closes [group/other#3](https://gitlab.example.com/group/other/-/issues/3) and GH-4"
+        );
+    }
+
+    #[test]
+    fn link_references_ok() {
+        let url = GithubUrl::new("https://gitlab.example.com/team/repo.git");
+
+        let a = url.link_references("fix x (!45)");
+        let e = "fix x ([!45](https://gitlab.example.com/team/repo/-/merge_requests/45))";
+        assert_eq!(a, e);
+
+        let a = url.link_references("fix #12 for %v1.0 and %\"Next Release\"");
+        let e = "fix [#12](https://gitlab.example.com/team/repo/-/issues/12) for [%v1.0](https://gitlab.example.com/team/repo/-/milestones?title=v1.0) and [%\"Next Release\"](https://gitlab.example.com/team/repo/-/milestones?title=Next+Release)";
+        assert_eq!(a, e);
+
+        let a = url.link_references("100% done, a!45");
+        assert_eq!(a, "100% done, a!45");
+
+        let a = url.link_references("use `!45` and `a %v1.0` in !46");
+        let e = "use `!45` and `a %v1.0` in [!46](https://gitlab.example.com/team/repo/-/merge_requests/46)";
+        assert_eq!(a, e);
+
+        let url = GithubUrl::new("https://github.com/team/repo.git");
+        assert_eq!(url.link_references("fix x (!45)"), "fix x (!45)");
+    }
 }
@@ -4,72 +4,234 @@ mod github_url;
 mod repository;
 mod version;
 
-use std::convert::From;
+use std::collections::HashMap;
 use std::path::Path;
+use std::str::FromStr;
 
+use crate::error::ExitError;
 use anyhow::*;
+use chrono::{DateTime, Utc};
 use git2::{self, Repository};
+use glob::Pattern;
 use log::*;
+use regex::Regex;
 use repository::{Findable, TagFindable};
 
 pub use commit::*;
 pub use conventional_commit::*;
-pub use github_url::GithubUrl;
+pub use github_url::{Forge, GithubUrl, UrlKind};
+pub use version::{DatedVersion, MultiPrefix};
 
 use version::*;
 
 pub fn repo<P: AsRef<Path>>(path: P) -> Result<Repository> {
-    Repository::open(&path).context("Not found git repository path")
+    let path = path.as_ref();
+    if path.exists() && !path.is_dir() {
+        bail!("Path is not a directory. path: {}", path.display());
+    }
+    let repo = Repository::open(path);
+    match repo {
+        Err(e) if !path.exists() => Err(Error::new(e).context(ExitError::NoInput(String::from(
+            "Not found git repository path",
+        )))),
+        _ => repo.context("Not found git repository path"),
+    }
 }
 
-pub fn gurl(repo: &Repository) -> Option<GithubUrl> {
+// The forge is detected from the remote host unless specified
+pub fn gurl(
+    repo: &Repository,
+    forge: Option<&Forge>,
+    templates: &[(UrlKind, String)],
+) -> Option<GithubUrl> {
     let url = repo.remote_url();
-    url.map(|u| GithubUrl::new(u.as_str()))
+    let url = url.and_then(|u| GithubUrl::parse(u.as_str()));
+    let url = match forge {
+        Some(f) => url.map(|u| u.with_forge(f.clone())),
+        None => url,
+    };
+    url.map(|u| {
+        templates
+            .iter()
+            .fold(u, |acc, (kind, t)| acc.with_template(kind.clone(), t))
+    })
+}
+
+#[derive(Debug)]
+pub struct ScanConfig {
+    pub tag_prefix: Option<String>,
+    pub tag_pattern: Option<Regex>,
+    pub on_multiple_prefixes: MultiPrefix,
+    pub skip_prerelease_previous: bool,
+    pub header_pattern: Option<Regex>,
+    // Pinned current time, the real time is used if None
+    pub now: Option<DateTime<Utc>>,
+    // Computing the diff of each commit is slow
+    pub stats: bool,
+    pub identity: Identity,
+    // Only the commits changing the matched files. ex) packages/foo/**
+    pub path_filter: Option<Pattern>,
+    // Version of the untagged HEAD dated now. ex) 1.0.0
+    pub as_version: Option<String>,
+    // The pre-release tags are release boundaries unless false
+    pub include_prereleases: bool,
+    // Number of the detected releases, only the latest one if None
+    pub limit: Option<usize>,
+    // The whole history from the root commit to HEAD
+    pub all: bool,
+    // Canonical authors by the repository's mailmap. ex) .mailmap
+    pub mailmap: bool,
+    // Inclusive bounds of the commit dates in the whole history
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+    // Types resolved at parsing. ex) wip => chore
+    pub type_aliases: HashMap<String, CommitType>,
+    // Gitmoji before the type. ex) ✨ feat: add x
+    pub strip_leading_emoji: bool,
+    // Runs `git verify-commit` for the signed release commits
+    pub verify_release_commits: bool,
+}
+
+impl Default for ScanConfig {
+    fn default() -> Self {
+        ScanConfig {
+            tag_prefix: None,
+            tag_pattern: None,
+            on_multiple_prefixes: MultiPrefix::Error,
+            skip_prerelease_previous: false,
+            header_pattern: None,
+            now: None,
+            stats: false,
+            identity: Identity::Author,
+            path_filter: None,
+            as_version: None,
+            include_prereleases: true,
+            limit: None,
+            all: false,
+            mailmap: false,
+            since: None,
+            until: None,
+            type_aliases: HashMap::new(),
+            strip_leading_emoji: false,
+            verify_release_commits: false,
+        }
+    }
 }
 
-pub fn commits(repo: &Repository, spec: Option<&str>, tag_prefix: Option<&str>) -> Result<Commits> {
+pub fn commits(repo: &Repository, spec: Option<&str>, conf: &ScanConfig) -> Result<Commits> {
+    let mut tags = repo.tags(conf.tag_pattern.as_ref())?;
+    if !conf.include_prereleases {
+        tags.drop_prereleases();
+    }
+    let parser = CommitParser::new(&tags, conf.header_pattern.as_ref())
+        .with_identity(conf.identity.clone())
+        .with_path_filter(conf.path_filter.as_ref())
+        .with_type_aliases(&conf.type_aliases)
+        .with_strip_leading_emoji(conf.strip_leading_emoji)
+        .with_mailmap(if conf.mailmap {
+            Some(repo.mailmap()?)
+        } else {
+            None
+        });
+    let parser = if conf.stats {
+        parser.with_stats(repo)
+    } else {
+        parser
+    };
+    let parser = if conf.verify_release_commits {
+        parser.with_release_verification(repo)
+    } else {
+        parser
+    };
+    let now = conf.now.unwrap_or_else(Utc::now);
+    // No commit yet
+    if repo.is_empty()? {
+        return Ok(Commits::new(Commit::empty(now)?, Vec::new()));
+    }
     let range = match spec {
-        Some(s) => parse_range(repo, s)?,
+        Some(s) => parse_range(repo, s, &parser, now)?,
+        // The oldest release is compared with the empty tree
+        None if conf.all || conf.since.is_some() || conf.until.is_some() => {
+            ScanRange::new(None, Commit::empty(now)?)
+        }
         None => {
-            let mut versions = repo.versions(tag_prefix)?;
-            detect_range(repo, &mut versions)?
+            let mut versions =
+                tags.versions(conf.tag_prefix.as_deref(), &conf.on_multiple_prefixes)?;
+            detect_range(
+                repo,
+                &mut versions,
+                &parser,
+                conf.skip_prerelease_previous,
+                conf.limit.unwrap_or(1),
+                now,
+            )?
         }
     };
     debug!("scan range: {:?}", &range);
 
-    let list = repo.find_by(&range)?;
-    let commits = Commits::new(range.prev(), list);
+    let list = repo.find_by(&range, &parser)?;
+    // Filtered after the grouping to keep the releases of the tags outside the window
+    let mut commits = Commits::new(range.prev(), list).with_window(conf.since, conf.until);
+    if let Some(v) = conf.as_version.as_deref() {
+        commits.stamp(Version::from_str(v)?, now.into());
+    }
     Ok(commits)
 }
 
-fn parse_range(repo: &Repository, spec: &str) -> Result<ScanRange> {
-    let revspec = repo.revparse(spec).context("Invalid revspec")?;
+// The versions found with the same tag options as the scan, sorted from the oldest
+pub fn versions(repo: &Repository, conf: &ScanConfig) -> Result<Vec<DatedVersion>> {
+    let mut tags = repo.tags(conf.tag_pattern.as_ref())?;
+    if !conf.include_prereleases {
+        tags.drop_prereleases();
+    }
+    let versions = tags.versions(conf.tag_prefix.as_deref(), &conf.on_multiple_prefixes)?;
+    Ok(tags.dated(&versions))
+}
+
+fn parse_range(
+    repo: &Repository,
+    spec: &str,
+    parser: &CommitParser,
+    now: DateTime<Utc>,
+) -> Result<ScanRange> {
+    let revspec = repo
+        .revparse(spec)
+        .context(ExitError::DataErr(String::from("Invalid revspec")))?;
     if !revspec.mode().contains(git2::RevparseMode::RANGE) {
-        anyhow::bail!("Don't support mode. Supported mode is only range(two-dot)")
+        return Err(Error::new(ExitError::DataErr(String::from(
+            "Don't support mode. Supported mode is only range(two-dot)",
+        ))));
     }
 
     let from = revspec
         .from()
         .and_then(|o| o.peel_to_commit().ok())
-        .map(Commit::from);
+        .map(|c| parser.parse(c));
     let to = revspec
         .to()
         .and_then(|o| o.peel_to_commit().ok())
-        .map(Commit::from);
+        .map(|c| parser.parse(c));
     // revspec from..to is reversed when scanning
     let (latest, previous) = match (to, from) {
         (Some(l), Some(p)) => (Some(l), p),
-        (Some(l), None) => (Some(l), Commit::empty()?),
-        _ => (None, Commit::empty()?),
+        (Some(l), None) => (Some(l), Commit::empty(now)?),
+        _ => (None, Commit::empty(now)?),
     };
     Ok(ScanRange::new(latest, previous))
 }
 
-fn detect_range(repo: &Repository, vs: &mut Versions) -> Result<ScanRange> {
-    let (latest, previous) = match vs.latest_range() {
-        (Some(l), Some(p)) => (Some(repo.find_by(l)?), repo.find_by(p)?),
-        (Some(l), None) => (Some(repo.find_by(l)?), Commit::empty()?),
-        _ => (None, Commit::empty()?),
+fn detect_range(
+    repo: &Repository,
+    vs: &mut Versions,
+    parser: &CommitParser,
+    skip_prerelease_previous: bool,
+    limit: usize,
+    now: DateTime<Utc>,
+) -> Result<ScanRange> {
+    let (latest, previous) = match vs.latest_ranges(skip_prerelease_previous, limit) {
+        (Some(l), Some(p)) => (Some(repo.find_by(l, parser)?), repo.find_by(p, parser)?),
+        (Some(l), None) => (Some(repo.find_by(l, parser)?), Commit::empty(now)?),
+        _ => (None, Commit::empty(now)?),
     };
     Ok(ScanRange::new(latest, previous))
 }
@@ -92,13 +254,17 @@ pub(crate) mod tests {
     const GIT_DATA2: &[u8] = include_bytes!("../../tests/assets/git-data2.tar.gz");
     const GIT_DATA3: &[u8] = include_bytes!("../../tests/assets/git-data3.tar.gz");
     const GIT_DATA4: &[u8] = include_bytes!("../../tests/assets/git-data4.tar.gz");
+    const GIT_DATA5: &[u8] = include_bytes!("../../tests/assets/git-data5.tar.gz");
+    const GIT_DATA6: &[u8] = include_bytes!("../../tests/assets/git-data6.tar.gz");
 
     pub fn git_dir(num: u8) -> Result<PathBuf> {
         let buf = match num {
-            1 => GIT_DATA1.as_ref(),
-            2 => GIT_DATA2.as_ref(),
-            3 => GIT_DATA3.as_ref(),
-            4 => GIT_DATA4.as_ref(),
+            1 => GIT_DATA1,
+            2 => GIT_DATA2,
+            3 => GIT_DATA3,
+            4 => GIT_DATA4,
+            5 => GIT_DATA5,
+            6 => GIT_DATA6,
             _ => bail!("Not found test git data"),
         };
         let tmp_dir = tempdir()?;
@@ -110,6 +276,18 @@ pub(crate) mod tests {
         Ok(prefix.join(format!("git-data{}", num)))
     }
 
+    // The version tag dated now. ex) tag("0.1.0")
+    pub fn tag(version: &str) -> Result<NamableObj> {
+        Ok(NamableObj::Tag {
+            version: Version::from_str(version)?,
+            datetime: Utc::now().into(),
+            aliases: Vec::new(),
+            message: None,
+            signer: None,
+        })
+    }
+
+    #[allow(clippy::too_many_arguments)]
     pub fn dummy_commit(
         id: &str,
         _type: &str,
@@ -131,14 +309,17 @@ pub(crate) mod tests {
         let _type = scope.map_or_else(|| _type.to_string(), |s| format!("{}({})", _type, s));
         let summary = format!("{}: {}", _type, description);
         let datetime = DateTime::parse_from_str(datetime, "%a %b %d %H:%M:%S %Y %z")?;
-        let datetime = datetime.with_timezone(&Utc);
         let id = Oid::from_str(id)?;
         let tag = tag.map(|x| NamableObj::Tag {
             version: Version::from_str(x).unwrap(),
             datetime,
+            aliases: Vec::new(),
+            message: None,
+            signer: None,
         });
 
-        let commit = Commit::new(id, &summary, author, datetime, parent_count, Some(cc), tag)?;
+        let parents = vec![Oid::zero(); parent_count];
+        let commit = Commit::new(id, &summary, author, datetime, parents, Some(cc), tag)?;
 
         Ok(commit)
     }
@@ -151,13 +332,39 @@ pub(crate) mod tests {
         tag: Option<&str>,
     ) -> Result<Commit> {
         let datetime = DateTime::parse_from_str(datetime, "%a %b %d %H:%M:%S %Y %z")?;
-        let datetime = datetime.with_timezone(&Utc);
         let id = Oid::from_str(id)?;
         let tag = tag.map(|x| NamableObj::Tag {
             version: Version::from_str(x).unwrap(),
             datetime,
+            aliases: Vec::new(),
+            message: None,
+            signer: None,
         });
-        let commit = Commit::new(id, summary, author, datetime, 1, None, tag)?;
+        let commit = Commit::new(id, summary, author, datetime, vec![Oid::zero()], None, tag)?;
+
+        Ok(commit)
+    }
+
+    pub fn dummy_message_commit(
+        id: &str,
+        message: &str,
+        author: &str,
+        datetime: &str,
+        tag: Option<&str>,
+    ) -> Result<Commit> {
+        let cc = ConventionalCommits::from_str(message).ok();
+        let summary = message.lines().next().unwrap_or_default();
+        let datetime = DateTime::parse_from_str(datetime, "%a %b %d %H:%M:%S %Y %z")?;
+        let id = Oid::from_str(id)?;
+        let tag = tag.map(|x| NamableObj::Tag {
+            version: Version::from_str(x).unwrap(),
+            datetime,
+            aliases: Vec::new(),
+            message: None,
+            signer: None,
+        });
+        let commit = Commit::new(id, summary, author, datetime, vec![Oid::zero()], cc, tag)?
+            .with_reverts(message);
 
         Ok(commit)
     }
@@ -241,7 +448,9 @@ pub(crate) mod tests {
             Version::from_str("1.1.0")?,
         ]);
 
-        let a = detect_range(&repo, &mut versions)?;
+        let tags = repo.tags(None)?;
+        let parser = CommitParser::new(&tags, None);
+        let a = detect_range(&repo, &mut versions, &parser, false, 1, Utc::now())?;
         let latest = dummy_commit(
             "cd3354bedd0c7b66a899d27a2e66ff41594df0b1",
             "feat",
@@ -252,7 +461,11 @@ pub(crate) mod tests {
             "Thu May 21 21:54:57 2020 +0900",
             1,
             Some("1.1.0"),
-        )?;
+        )?
+        .with_parents(vec![Oid::from_str(
+            "9a5e72a6ade1f3b6975711f3bf05a82f1793c0b4",
+        )?])
+        .with_tag_message("1.1.0");
         let prev = dummy_commit(
             "9a5e72a6ade1f3b6975711f3bf05a82f1793c0b4",
             "feat",
@@ -263,10 +476,33 @@ pub(crate) mod tests {
             "Thu May 21 21:54:46 2020 +0900",
             1,
             Some("1.0.0"),
-        )?;
+        )?
+        .with_parents(vec![Oid::from_str(
+            "bb505b1c9491b1400e03d00a253d0b1d0ac3c1ea",
+        )?])
+        .with_tag_message("1.0.0");
         let e = ScanRange::new(Some(latest), prev);
 
         assert_eq!(a, e);
         Ok(())
     }
+
+    #[test]
+    fn pinned_now_ok() -> Result<()> {
+        let git_dir = git_dir(5)?;
+        let repo = repo(git_dir)?;
+
+        let now = DateTime::parse_from_rfc3339("2020-06-01T00:00:00Z")?.with_timezone(&Utc);
+        let conf = ScanConfig {
+            now: Some(now),
+            ..Default::default()
+        };
+        let a = commits(&repo, None, &conf)?;
+        let e = NamableObj::Commit {
+            short_hash: String::from("4b825dc"),
+            datetime: now.into(),
+        };
+        assert_eq!(a.range(None).0, e);
+        Ok(())
+    }
 }
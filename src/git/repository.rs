@@ -1,24 +1,29 @@
-use crate::git::version::{Version, Versions};
-use crate::git::{Commit, ScanRange};
+use crate::git::version::{Tags, Version};
+use crate::git::{Commit, CommitParser, ScanRange};
 use anyhow::*;
+use chrono::{FixedOffset, TimeZone};
 use git2::Repository;
-use std::str::FromStr;
+use log::*;
+use regex::Regex;
 
 pub(super) trait Findable<T, R> {
-    fn find_by(&self, v: &T) -> Result<R>;
+    fn find_by(&self, v: &T, parser: &CommitParser) -> Result<R>;
 }
 
 impl Findable<Version, Commit> for Repository {
     // TODO chang return type to more simple type
-    fn find_by(&self, version: &Version) -> Result<Commit> {
+    fn find_by(&self, version: &Version, parser: &CommitParser) -> Result<Commit> {
         let obj = self.revparse_single(version.to_string().as_str())?;
-        let commit = Commit::from(obj.peel_to_commit()?);
+        let commit = obj
+            .peel_to_commit()
+            .with_context(|| format!("The tag doesn't point at a commit. tag: {}", version))?;
+        let commit = parser.parse(commit);
         Ok(commit)
     }
 }
 
 impl Findable<ScanRange, Vec<Commit>> for Repository {
-    fn find_by(&self, range: &ScanRange) -> Result<Vec<Commit>> {
+    fn find_by(&self, range: &ScanRange, parser: &CommitParser) -> Result<Vec<Commit>> {
         let mut rev = self.revwalk()?;
         match range.latest_id() {
             Some(id) => rev.push(*id)?,
@@ -31,7 +36,8 @@ impl Findable<ScanRange, Vec<Commit>> for Repository {
             })
             .filter_map(|id| id.ok())
             .filter_map(|id| self.find_commit(id).ok())
-            .map(Commit::from)
+            .filter(|c| parser.is_in_path(self, c))
+            .map(|c| parser.parse(c))
             .collect::<Vec<Commit>>();
 
         Ok(commits)
@@ -39,26 +45,55 @@ impl Findable<ScanRange, Vec<Commit>> for Repository {
 }
 
 pub(super) trait TagFindable {
-    fn versions(&self, tag_prefix: Option<&str>) -> Result<Versions>;
+    fn tags(&self, tag_pattern: Option<&Regex>) -> Result<Tags>;
     fn remote_url(&self) -> Option<String>;
 }
 
 impl TagFindable for Repository {
-    fn versions(&self, tag_prefix: Option<&str>) -> Result<Versions> {
-        let tags = self.tag_names(None)?;
-        let versions: Versions = tags
-            .into_iter()
-            .flatten()
-            .filter_map(|x| Version::from_str(x).ok())
-            .collect();
-
-        let versions = versions.select(tag_prefix);
-        let prefix = versions.prefix();
-        if prefix.len() > 1 {
-            bail!("There are two or more Semantic version styles. Please specify and specify the tag-prefix option. ex) --tag-prefix={}", prefix.get(0).unwrap());
+    fn tags(&self, tag_pattern: Option<&Regex>) -> Result<Tags> {
+        let mut tags = Tags::default();
+        for reference in self.references_glob("refs/tags/*")? {
+            let reference = reference?;
+            let name = match reference.shorthand() {
+                Some(n) => n,
+                None => continue,
+            };
+            // A tag can point at a tree or a blob. ex) git tag 1.0.0 HEAD^{tree}
+            let commit = match reference.peel_to_commit() {
+                Ok(c) => c,
+                Err(e) => {
+                    warn!(
+                        "Skip the tag not pointing at a commit. tag: {}, {}",
+                        name, e
+                    );
+                    continue;
+                }
+            };
+            match Version::parse(name, tag_pattern) {
+                Ok(version) => {
+                    // Only annotated tags are tag objects
+                    if let Ok(tag) = reference.peel_to_tag() {
+                        let (message, signature) =
+                            split_signature(tag.message().unwrap_or_default());
+                        if !message.is_empty() {
+                            tags.add_message(&version, message);
+                        }
+                        let tagger = tag.tagger();
+                        let signer = tagger.as_ref().and_then(|t| t.name());
+                        if let (Some(_), Some(name)) = (signature, signer) {
+                            tags.add_signer(&version, name);
+                        }
+                    }
+                    let time = commit.time();
+                    let datetime =
+                        FixedOffset::east(time.offset_minutes() * 60).timestamp(time.seconds(), 0);
+                    tags.add_date(&version, datetime);
+                    tags.add(commit.id(), version)
+                }
+                Err(_) => tags.add_alias(commit.id(), name),
+            }
         }
-
-        Ok(versions)
+        Ok(tags)
     }
 
     // TODO change to get from config
@@ -69,22 +104,48 @@ impl TagFindable for Repository {
     }
 }
 
+const SIGNATURE_BEGINS: [&str; 2] = [
+    "-----BEGIN PGP SIGNATURE-----",
+    "-----BEGIN SSH SIGNATURE-----",
+];
+
+// The signature is appended to the end of the tag message. ex) -----BEGIN PGP SIGNATURE-----
+// The other armored blocks quoted in the message are kept.
+fn split_signature(message: &str) -> (&str, Option<&str>) {
+    let start = message
+        .match_indices('\n')
+        .map(|(i, _)| i + 1)
+        .chain(std::iter::once(0))
+        .filter(|&i| SIGNATURE_BEGINS.iter().any(|b| message[i..].starts_with(b)))
+        .max();
+    match start {
+        Some(i) if message[i..].trim_end().ends_with(" SIGNATURE-----") => {
+            (message[..i].trim(), Some(&message[i..]))
+        }
+        _ => (message.trim(), None),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::git::tests::*;
+    use crate::git::version::{MultiPrefix, Versions};
+    use git2::DescribeOptions;
+    use glob::Pattern;
+    use std::str::FromStr;
 
     #[test]
     fn versions_ok() -> Result<()> {
         let repo = Repository::open(git_dir(1)?)?;
-        let versions = repo.versions(None)?;
+        let versions = repo.tags(None)?.versions(None, &MultiPrefix::Error)?;
         let expect = vec![Version::from_str("0.1.0")?, Version::from_str("0.2.0")?]
             .into_iter()
             .collect::<Versions>();
         assert_eq!(versions, expect);
 
         let repo = Repository::open(git_dir(3)?)?;
-        let versions = repo.versions(Some("v"))?;
+        let versions = repo.tags(None)?.versions(Some("v"), &MultiPrefix::Error)?;
         let expect = vec![
             Version::from_str("v0.1.0")?,
             Version::from_str("v0.2.0")?,
@@ -94,7 +155,9 @@ mod tests {
         .collect::<Versions>();
         assert_eq!(versions, expect);
 
-        let versions = repo.versions(Some("component-v"))?;
+        let versions = repo
+            .tags(None)?
+            .versions(Some("component-v"), &MultiPrefix::Error)?;
         let expect = vec![
             Version::from_str("component-v0.1.0")?,
             Version::from_str("component-v0.2.0")?,
@@ -103,7 +166,7 @@ mod tests {
         .collect::<Versions>();
         assert_eq!(versions, expect);
 
-        let versions = repo.versions(None)?;
+        let versions = repo.tags(None)?.versions(None, &MultiPrefix::Error)?;
         let expect = vec![Version::from_str("1.0.0")?, Version::from_str("1.1.0")?]
             .into_iter()
             .collect::<Versions>();
@@ -112,10 +175,48 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn signed_tags_ok() -> Result<()> {
+        let repo = Repository::open(git_dir(6)?)?;
+        let tags = repo.tags(None)?;
+
+        let signed = Version::from_str("0.1.0")?;
+        assert_eq!(tags.signer(&signed), Some("Test User"));
+        assert_eq!(tags.message(&signed), Some("Release 0.1.0"));
+
+        let unsigned = Version::from_str("0.2.0")?;
+        assert_eq!(tags.signer(&unsigned), None);
+        assert_eq!(tags.message(&unsigned), Some("Release 0.2.0"));
+        Ok(())
+    }
+
+    #[test]
+    fn split_signature_ok() {
+        let pgp = "-----BEGIN PGP SIGNATURE-----\n\niQEz\n-----END PGP SIGNATURE-----\n";
+        let message = format!("Release 0.1.0\n{}", pgp);
+        assert_eq!(split_signature(&message), ("Release 0.1.0", Some(pgp)));
+
+        let ssh = "-----BEGIN SSH SIGNATURE-----\nU1NI\n-----END SSH SIGNATURE-----\n";
+        let message = format!("Release 0.1.0\n{}", ssh);
+        assert_eq!(split_signature(&message), ("Release 0.1.0", Some(ssh)));
+
+        // The quoted blocks aren't the signature
+        let quoted =
+            "Rotate the cert\n\n-----BEGIN CERTIFICATE-----\nMIIB\n-----END CERTIFICATE-----";
+        assert_eq!(split_signature(quoted), (quoted, None));
+        let message = format!("{}\n{}", quoted, pgp);
+        assert_eq!(split_signature(&message), (quoted, Some(pgp)));
+
+        let inline = "Explain -----BEGIN PGP SIGNATURE----- in the middle";
+        assert_eq!(split_signature(inline), (inline, None));
+    }
+
     #[test]
     fn versions_ng() -> Result<()> {
         let repo = Repository::open(git_dir(4)?)?;
-        let versions = repo.versions(Some("aaa-v"))?;
+        let versions = repo
+            .tags(None)?
+            .versions(Some("aaa-v"), &MultiPrefix::Error)?;
         let expect = vec![
             Version::from_str("aaa-v0.1.0")?,
             Version::from_str("aaa-v0.2.0")?,
@@ -124,7 +225,9 @@ mod tests {
         .collect::<Versions>();
         assert_eq!(versions, expect);
 
-        let versions = repo.versions(Some("bbb-v"))?;
+        let versions = repo
+            .tags(None)?
+            .versions(Some("bbb-v"), &MultiPrefix::Error)?;
         let expect = vec![
             Version::from_str("bbb-v0.1.0")?,
             Version::from_str("bbb-v0.2.0")?,
@@ -133,9 +236,45 @@ mod tests {
         .collect::<Versions>();
         assert_eq!(versions, expect);
 
-        let versions = repo.versions(None);
+        let versions = repo.tags(None)?.versions(None, &MultiPrefix::Error);
         assert!(versions.is_err());
 
+        let versions = repo.tags(None)?.versions(None, &MultiPrefix::First)?;
+        let expect = vec![
+            Version::from_str("aaa-v0.1.0")?,
+            Version::from_str("aaa-v0.2.0")?,
+        ]
+        .into_iter()
+        .collect::<Versions>();
+        assert_eq!(versions, expect);
+
+        Ok(())
+    }
+
+    #[test]
+    fn tags_same_as_describe_ok() -> Result<()> {
+        for num in 1..=4 {
+            let repo = Repository::open(git_dir(num)?)?;
+            let tags = repo.tags(None)?;
+
+            let mut rev = repo.revwalk()?;
+            rev.push_glob("refs/tags/*")?;
+            for id in rev {
+                let commit = repo.find_commit(id?)?;
+                let describe = commit
+                    .as_object()
+                    .describe(
+                        DescribeOptions::new()
+                            .describe_tags()
+                            .max_candidates_tags(0),
+                    )
+                    .ok()
+                    .and_then(|d| d.format(None).ok())
+                    .and_then(|name| Version::from_str(&name).ok());
+
+                assert_eq!(tags.get(&commit.id()), describe.as_ref());
+            }
+        }
         Ok(())
     }
 
@@ -170,7 +309,8 @@ mod tests {
 
         let range = ScanRange::new(Some(latest), previous);
 
-        let commits = repo.find_by(&range)?;
+        let tags = Tags::default();
+        let commits = repo.find_by(&range, &CommitParser::new(&tags, None))?;
         let actual = commits
             .iter()
             .map(|c| c.id.to_string())
@@ -187,4 +327,55 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn tags_skip_non_commit_ok() -> Result<()> {
+        let repo = Repository::open(git_dir(1)?)?;
+        let head = repo.head()?.peel_to_commit()?;
+        let blob = repo.blob(b"not a commit")?;
+        repo.reference("refs/tags/0.3.0", head.tree_id(), false, "tree tag")?;
+        repo.reference("refs/tags/0.4.0", blob, false, "blob tag")?;
+
+        let versions = repo.tags(None)?.versions(None, &MultiPrefix::Error)?;
+        let expect = vec![Version::from_str("0.1.0")?, Version::from_str("0.2.0")?]
+            .into_iter()
+            .collect::<Versions>();
+        assert_eq!(versions, expect);
+
+        let tags = Tags::default();
+        let err = repo
+            .find_by(
+                &Version::from_str("0.3.0")?,
+                &CommitParser::new(&tags, None),
+            )
+            .unwrap_err();
+        assert!(err.to_string().contains("tag: 0.3.0"));
+        Ok(())
+    }
+
+    #[test]
+    fn find_by_path_filter_ok() -> Result<()> {
+        let repo = Repository::open(git_dir(1)?)?;
+        let tags = repo.tags(None)?;
+        let latest = repo.find_by(
+            &Version::from_str("0.2.0")?,
+            &CommitParser::new(&tags, None),
+        )?;
+        let previous = repo.find_by(
+            &Version::from_str("0.1.0")?,
+            &CommitParser::new(&tags, None),
+        )?;
+        let range = ScanRange::new(Some(latest), previous);
+
+        let ids = |pattern: &str| -> Result<Vec<String>> {
+            let pattern = Pattern::new(pattern)?;
+            let parser = CommitParser::new(&tags, None).with_path_filter(Some(&pattern));
+            let commits = repo.find_by(&range, &parser)?;
+            Ok(commits.iter().map(|c| c.short_hash()).collect())
+        };
+        assert_eq!(ids("build.sh")?, vec!["6f90482", "a673434"]);
+        assert_eq!(ids("*.sh")?, vec!["9cd3662", "6f90482", "a673434"]);
+        assert!(ids("src")?.is_empty());
+        Ok(())
+    }
 }
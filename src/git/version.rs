@@ -1,9 +1,13 @@
 use anyhow::*;
+use chrono::{DateTime, FixedOffset};
+use git2::Oid;
 use itertools::Itertools;
 use lazy_static::*;
 use log::*;
 use regex::Regex;
 use semver::Version as SemVer;
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+use std::collections::HashMap;
 use std::fmt;
 use std::iter::FromIterator;
 use std::str::FromStr;
@@ -17,18 +21,37 @@ lazy_static! {
 pub struct Version {
     prefix: String,
     ver: SemVer,
+    // Original tag name
+    tag: String,
 }
 
 impl Version {
     pub fn starts_with(&self, pre: &str) -> bool {
         self.prefix.starts_with(pre)
     }
-}
 
-impl FromStr for Version {
-    type Err = Error;
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let caps = PREFIX
+    pub fn semver(&self) -> &SemVer {
+        &self.ver
+    }
+
+    // The tag name without the prefix. ex) component-v0.2.0 => 0.2.0
+    pub fn bare(&self) -> &str {
+        self.tag.strip_prefix(&self.prefix).unwrap_or(&self.tag)
+    }
+
+    // Only the pre-releases of a normal release. ex) 2.0.0-rc.1 is a pre-release of 2.0.0
+    fn is_prerelease_of(&self, other: &Version) -> bool {
+        self.ver.is_prerelease()
+            && !other.ver.is_prerelease()
+            && self.ver.major == other.ver.major
+            && self.ver.minor == other.ver.minor
+            && self.ver.patch == other.ver.patch
+    }
+
+    // The pattern needs a `version` capture group and may have a `prefix` capture group
+    pub fn parse(s: &str, pattern: Option<&Regex>) -> Result<Self> {
+        let re = pattern.unwrap_or(&PREFIX);
+        let caps = re
             .captures(s)
             .ok_or_else(|| anyhow!("Can't find semver format. value: {}", s))?;
 
@@ -44,17 +67,84 @@ impl FromStr for Version {
         debug!("prefix: {}", prefix);
         debug!("version: {}", version);
 
+        let ver = match pattern {
+            Some(_) => coerce(version)?,
+            None => SemVer::parse(version)?,
+        };
+
         Ok(Version {
             prefix: prefix.to_string(),
-            ver: SemVer::parse(version)?,
+            ver,
+            tag: s.to_string(),
         })
     }
 }
 
+impl FromStr for Version {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Version::parse(s, None)
+    }
+}
+
 impl fmt::Display for Version {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}{}", self.prefix, self.ver)
+        write!(f, "{}", self.tag)
+    }
+}
+
+impl Serialize for Version {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("Version", 3)?;
+        state.serialize_field("tag", &self.tag)?;
+        state.serialize_field("prefix", &self.prefix)?;
+        state.serialize_field("version", &self.ver.to_string())?;
+        state.end()
+    }
+}
+
+// The version with the date of the tagged commit. ex) --list-versions
+#[derive(Clone, Debug, PartialEq, serde::Serialize)]
+pub struct DatedVersion {
+    #[serde(flatten)]
+    pub version: Version,
+    pub date: Option<DateTime<FixedOffset>>,
+}
+
+// Custom tag patterns may capture a non semver value. ex) 2024w01 => 2024.1.0
+fn coerce(version: &str) -> Result<SemVer> {
+    if let Ok(ver) = SemVer::parse(version) {
+        return Ok(ver);
+    }
+
+    lazy_static! {
+        static ref NUMBER: Regex = Regex::new(r"[0-9]+").unwrap();
     }
+    let nums = NUMBER
+        .find_iter(version)
+        .take(3)
+        .map(|m| m.as_str().parse::<u64>())
+        .collect::<Result<Vec<u64>, _>>()?;
+    if nums.is_empty() {
+        bail!("Can't find version number. value: {}", version);
+    }
+
+    let num = |i: usize| nums.get(i).copied().unwrap_or_default();
+    Ok(SemVer::new(num(0), num(1), num(2)))
+}
+
+// How to choose the tags when there are two or more prefixes
+#[derive(Clone, Debug, PartialEq, EnumString)]
+#[strum(serialize_all = "kebab-case")]
+pub enum MultiPrefix {
+    // Prefer no prefix, then `v`, otherwise fail
+    Error,
+    // Prefer no prefix, then `v`, otherwise the first prefix in alphabetical order
+    PreferEmpty,
+    // Prefer `v`, then no prefix, otherwise the first prefix in alphabetical order
+    PreferV,
+    // The first prefix in alphabetical order
+    First,
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -74,20 +164,53 @@ impl Versions {
         self.0.push(elem);
     }
 
-    pub fn latest_range(&mut self) -> (Option<&Version>, Option<&Version>) {
+    // If skip_prerelease is true, the pre-releases of the latest version are not the previous.
+    // ex) 1.9.0, 2.0.0-rc.1, 2.0.0 => 1.9.0..2.0.0
+    #[cfg(test)]
+    pub fn latest_range(&mut self, skip_prerelease: bool) -> (Option<&Version>, Option<&Version>) {
+        self.latest_ranges(skip_prerelease, 1)
+    }
+
+    // Span of the latest `limit` releases. ex) 0.1.0, 0.2.0, 0.3.0 and 2 => 0.1.0..0.3.0
+    pub fn latest_ranges(
+        &mut self,
+        skip_prerelease: bool,
+        limit: usize,
+    ) -> (Option<&Version>, Option<&Version>) {
+        let ranges = self.ranges(skip_prerelease);
+        let latest = ranges.first().map(|(l, _)| *l);
+        let previous = ranges
+            .get(limit.max(1) - 1)
+            .or_else(|| ranges.last())
+            .and_then(|(_, p)| *p);
+        (latest, previous)
+    }
+
+    // Each version and its previous version, newest first
+    pub fn ranges(&mut self, skip_prerelease: bool) -> Vec<(&Version, Option<&Version>)> {
         self.0.sort();
         self.0.reverse();
-        let mut it = self.0.iter();
-        let latest_tag = it.next();
-        let previous_tag = it.next();
-        (latest_tag, previous_tag)
+        let versions = &self.0;
+        versions
+            .iter()
+            .enumerate()
+            .map(move |(i, v)| {
+                let mut rest = versions[i + 1..].iter();
+                let previous = if skip_prerelease {
+                    rest.find(|p| !p.is_prerelease_of(v))
+                } else {
+                    rest.next()
+                };
+                (v, previous)
+            })
+            .collect()
     }
 
     pub fn prefix(&self) -> Vec<&str> {
         self.0.iter().map(|x| x.prefix.as_str()).unique().collect()
     }
 
-    pub fn select(self, prefix: Option<&str>) -> Self {
+    pub fn select(self, prefix: Option<&str>, multi: &MultiPrefix) -> Self {
         if let Some(pre) = prefix {
             return self
                 .0
@@ -96,17 +219,24 @@ impl Versions {
                 .collect::<Versions>();
         }
 
-        let prefix = "";
-        if self.has_prefix(prefix) {
-            return self.filter(prefix);
+        let precedence = match multi {
+            MultiPrefix::Error | MultiPrefix::PreferEmpty => vec!["", "v"],
+            MultiPrefix::PreferV => vec!["v", ""],
+            MultiPrefix::First => Vec::new(),
+        };
+        for prefix in precedence {
+            if self.has_prefix(prefix) {
+                return self.filter(prefix);
+            }
         }
 
-        let prefix = "v";
-        if self.has_prefix(prefix) {
-            return self.filter(prefix);
+        if *multi == MultiPrefix::Error {
+            return self;
+        }
+        match self.prefix().into_iter().min().map(String::from) {
+            Some(first) => self.filter(&first),
+            None => self,
         }
-
-        self
     }
 
     fn filter(self, prefix: &str) -> Self {
@@ -121,6 +251,94 @@ impl Versions {
     }
 }
 
+// Versions attached to each commit id, built once instead of calling `describe` per commit
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Tags {
+    versions: HashMap<Oid, Vec<Version>>,
+    aliases: HashMap<Oid, Vec<String>>,
+    messages: HashMap<Version, String>,
+    signers: HashMap<Version, String>,
+    dates: HashMap<Version, DateTime<FixedOffset>>,
+}
+
+impl Tags {
+    pub fn add(&mut self, id: Oid, version: Version) {
+        self.versions.entry(id).or_default().push(version);
+    }
+
+    // Tag names that aren't versions. ex) stable, latest
+    pub fn add_alias(&mut self, id: Oid, name: &str) {
+        self.aliases.entry(id).or_default().push(name.to_string());
+    }
+
+    pub fn aliases(&self, id: &Oid) -> &[String] {
+        self.aliases.get(id).map_or(&[], |a| a.as_slice())
+    }
+
+    // Annotation of the tag. ex) git tag -a 1.0.0 -m "First stable release"
+    pub fn add_message(&mut self, version: &Version, message: &str) {
+        self.messages.insert(version.clone(), message.to_string());
+    }
+
+    pub fn message(&self, version: &Version) -> Option<&str> {
+        self.messages.get(version).map(String::as_str)
+    }
+
+    // The tagger of the signed tag. The signature isn't verified
+    pub fn add_signer(&mut self, version: &Version, signer: &str) {
+        self.signers.insert(version.clone(), signer.to_string());
+    }
+
+    pub fn signer(&self, version: &Version) -> Option<&str> {
+        self.signers.get(version).map(String::as_str)
+    }
+
+    // The date of the tagged commit
+    pub fn add_date(&mut self, version: &Version, datetime: DateTime<FixedOffset>) {
+        self.dates.insert(version.clone(), datetime);
+    }
+
+    pub fn dated(&self, versions: &Versions) -> Vec<DatedVersion> {
+        versions
+            .0
+            .iter()
+            .map(|v| DatedVersion {
+                version: v.clone(),
+                date: self.dates.get(v).copied(),
+            })
+            .collect()
+    }
+
+    // The pre-release tags are kept only as the aliases, so they don't split the releases
+    pub fn drop_prereleases(&mut self) {
+        for (id, versions) in self.versions.iter_mut() {
+            let (pre, normal): (Vec<Version>, Vec<Version>) =
+                versions.drain(..).partition(|v| v.ver.is_prerelease());
+            *versions = normal;
+            let aliases = self.aliases.entry(*id).or_default();
+            aliases.extend(pre.iter().map(|v| v.to_string()));
+        }
+        self.versions.retain(|_, versions| !versions.is_empty());
+        self.aliases.retain(|_, aliases| !aliases.is_empty());
+    }
+
+    // If there are two or more tags on the same commit, the greatest version is used
+    pub fn get(&self, id: &Oid) -> Option<&Version> {
+        self.versions.get(id).and_then(|vs| vs.iter().max())
+    }
+
+    pub fn versions(&self, prefix: Option<&str>, multi: &MultiPrefix) -> Result<Versions> {
+        let versions: Versions = self.versions.values().flatten().cloned().sorted().collect();
+        let versions = versions.select(prefix, multi);
+        let prefix = versions.prefix();
+        if prefix.len() > 1 {
+            bail!("There are two or more Semantic version styles. Please specify the tag-prefix option. ex) --tag-prefix={} or the on-multiple-prefixes option. ex) --on-multiple-prefixes=first", prefix.first().unwrap());
+        }
+
+        Ok(versions)
+    }
+}
+
 impl FromIterator<Version> for Versions {
     fn from_iter<I: IntoIterator<Item = Version>>(iter: I) -> Self {
         let mut c = Versions::new();
@@ -143,24 +361,113 @@ mod tests {
             .into_iter()
             .collect::<Versions>();
 
-        let (latest, prev) = versions.latest_range();
+        let (latest, prev) = versions.latest_range(false);
         assert_eq!(prev, Some(&expected_prev));
         assert_eq!(latest, Some(&expected_latest));
         Ok(())
     }
 
+    #[test]
+    fn latest_ranges_ok() -> Result<()> {
+        let mut versions = dummy_versions(vec!["0.3.0", "0.1.0", "0.2.0"])?;
+        let ranges = versions
+            .ranges(false)
+            .into_iter()
+            .map(|(l, p)| (l.to_string(), p.map(|v| v.to_string())))
+            .collect::<Vec<_>>();
+        let e = vec![
+            (String::from("0.3.0"), Some(String::from("0.2.0"))),
+            (String::from("0.2.0"), Some(String::from("0.1.0"))),
+            (String::from("0.1.0"), None),
+        ];
+        assert_eq!(ranges, e);
+
+        let (latest, prev) = versions.latest_ranges(false, 2);
+        assert_eq!(latest, Some(&Version::from_str("0.3.0")?));
+        assert_eq!(prev, Some(&Version::from_str("0.1.0")?));
+
+        let (latest, prev) = versions.latest_ranges(false, 10);
+        assert_eq!(latest, Some(&Version::from_str("0.3.0")?));
+        assert_eq!(prev, None);
+
+        let (latest, prev) = versions.latest_ranges(false, 0);
+        assert_eq!(latest, Some(&Version::from_str("0.3.0")?));
+        assert_eq!(prev, Some(&Version::from_str("0.2.0")?));
+        Ok(())
+    }
+
+    #[test]
+    fn latest_range_prerelease_order_ok() -> Result<()> {
+        let mut versions = dummy_versions(vec!["1.2.0", "1.2.0-rc.2", "1.1.0", "1.2.0-rc.1"])?;
+        let (latest, prev) = versions.latest_range(false);
+        assert_eq!(latest, Some(&Version::from_str("1.2.0")?));
+        assert_eq!(prev, Some(&Version::from_str("1.2.0-rc.2")?));
+
+        let mut versions = dummy_versions(vec!["1.2.0-rc.2", "1.2.0-rc.1", "1.1.0+build.9"])?;
+        let (latest, prev) = versions.latest_range(false);
+        assert_eq!(latest, Some(&Version::from_str("1.2.0-rc.2")?));
+        assert_eq!(prev, Some(&Version::from_str("1.2.0-rc.1")?));
+        Ok(())
+    }
+
+    #[test]
+    fn drop_prereleases_ok() -> Result<()> {
+        let (id1, id2, id3) = (
+            Oid::from_str("1d185faf719f12292414c88872e3397fc5dc4e62")?,
+            Oid::from_str("2d185faf719f12292414c88872e3397fc5dc4e62")?,
+            Oid::from_str("3d185faf719f12292414c88872e3397fc5dc4e62")?,
+        );
+        let mut tags = Tags::default();
+        tags.add(id1, Version::from_str("1.2.0-rc.1")?);
+        tags.add(id2, Version::from_str("1.2.0-rc.2")?);
+        tags.add(id3, Version::from_str("1.2.0")?);
+        tags.add_alias(id3, "stable");
+        tags.drop_prereleases();
+
+        assert_eq!(tags.get(&id1), None);
+        assert_eq!(tags.aliases(&id2), ["1.2.0-rc.2"]);
+        assert_eq!(tags.get(&id3), Some(&Version::from_str("1.2.0")?));
+        assert_eq!(tags.aliases(&id3), ["stable"]);
+        let versions = tags.versions(None, &MultiPrefix::Error)?;
+        assert_eq!(versions, dummy_versions(vec!["1.2.0"])?);
+        Ok(())
+    }
+
+    #[test]
+    fn latest_range_skip_prerelease_ok() -> Result<()> {
+        let mut versions = dummy_versions(vec!["1.9.0", "2.0.0-rc.1", "2.0.0-rc.2", "2.0.0"])?;
+        let (latest, prev) = versions.latest_range(false);
+        assert_eq!(latest, Some(&Version::from_str("2.0.0")?));
+        assert_eq!(prev, Some(&Version::from_str("2.0.0-rc.2")?));
+
+        let (latest, prev) = versions.latest_range(true);
+        assert_eq!(latest, Some(&Version::from_str("2.0.0")?));
+        assert_eq!(prev, Some(&Version::from_str("1.9.0")?));
+
+        let mut versions = dummy_versions(vec!["2.0.0-rc.1", "2.0.0"])?;
+        let (_, prev) = versions.latest_range(true);
+        assert_eq!(prev, None);
+
+        // The latest is also a pre-release
+        let mut versions = dummy_versions(vec!["1.9.0", "2.0.0-rc.1", "2.0.0-rc.2"])?;
+        let (latest, prev) = versions.latest_range(true);
+        assert_eq!(latest, Some(&Version::from_str("2.0.0-rc.2")?));
+        assert_eq!(prev, Some(&Version::from_str("2.0.0-rc.1")?));
+        Ok(())
+    }
+
     #[test]
     fn latest_range_ng() -> Result<()> {
         let expected = Version::from_str("0.2.0")?;
         let mut versions = vec![expected.clone()].into_iter().collect::<Versions>();
 
-        let (latest, prev) = versions.latest_range();
+        let (latest, prev) = versions.latest_range(false);
         assert_eq!(prev, None);
         assert_eq!(latest, Some(&expected));
 
         let mut versions = Vec::new().into_iter().collect::<Versions>();
 
-        let (latest, prev) = versions.latest_range();
+        let (latest, prev) = versions.latest_range(false);
         assert_eq!(prev, None);
         assert_eq!(latest, None);
 
@@ -188,6 +495,61 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn bare_ok() -> Result<()> {
+        assert_eq!(Version::from_str("component-v0.2.0")?.bare(), "0.2.0");
+        assert_eq!(Version::from_str("0.2.0")?.bare(), "0.2.0");
+
+        let re = Regex::new(r"^(?P<prefix>release-)(?P<version>[0-9]+w[0-9]+)$")?;
+        assert_eq!(
+            Version::parse("release-2024w01", Some(&re))?.bare(),
+            "2024w01"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn parse_pattern_ok() -> Result<()> {
+        let re = Regex::new(r"^(?P<prefix>release-)(?P<version>[0-9]+w[0-9]+)$")?;
+        let a = Version::parse("release-2024w01", Some(&re))?;
+        assert_eq!(a.prefix, "release-");
+        assert_eq!(a.ver, SemVer::new(2024, 1, 0));
+        assert_eq!(a.to_string(), "release-2024w01");
+
+        let re = Regex::new(r"^build/(?P<version>.+)$")?;
+        let a = Version::parse("build/3.2.1", Some(&re))?;
+        assert!(a.prefix.is_empty());
+        assert_eq!(a.ver, SemVer::new(3, 2, 1));
+        assert_eq!(a.to_string(), "build/3.2.1");
+
+        assert!(Version::parse("release-2024w01", None).is_err());
+        assert!(Version::parse("0.1.0", Some(&re)).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn select_pattern_prefix_ok() -> Result<()> {
+        // The prefix captured by the pattern groups the versions. ex) monorepo packages
+        let re = Regex::new(r"^(?P<prefix>[^/]+/)(?P<version>.+)$")?;
+        let versions = vec!["foo/1.2.3", "bar/1.2.3", "foo/1.3.0"]
+            .into_iter()
+            .map(|v| Version::parse(v, Some(&re)))
+            .collect::<Result<Versions>>()?;
+        assert_eq!(versions.prefix(), vec!["foo/", "bar/"]);
+
+        let a = versions.clone().select(Some("foo/"), &MultiPrefix::Error);
+        let e = vec!["foo/1.2.3", "foo/1.3.0"]
+            .into_iter()
+            .map(|v| Version::parse(v, Some(&re)))
+            .collect::<Result<Versions>>()?;
+        assert_eq!(a, e);
+
+        let a = versions.select(Some("bar/"), &MultiPrefix::Error);
+        assert_eq!(a.prefix(), vec!["bar/"]);
+        Ok(())
+    }
+
     fn dummy_versions(vs: Vec<&str>) -> Result<Versions> {
         let v = vs
             .into_iter()
@@ -202,7 +564,7 @@ mod tests {
         assert_eq!(a.prefix(), vec!["", "v", "prefix-", "test-"]);
 
         let a = dummy_versions(Vec::new())?;
-        assert_eq!(a.prefix().iter().count(), 0);
+        assert_eq!(a.prefix().len(), 0);
 
         Ok(())
     }
@@ -213,32 +575,59 @@ mod tests {
 
         let a = versions.clone();
         let e = dummy_versions(vec!["0.1.0"])?;
-        assert_eq!(a.select(Some("")), e);
+        assert_eq!(a.select(Some(""), &MultiPrefix::Error), e);
 
         let a = versions.clone();
         let e = dummy_versions(vec!["v0.2.0"])?;
-        assert_eq!(a.select(Some("v")), e);
+        assert_eq!(a.select(Some("v"), &MultiPrefix::Error), e);
 
         let a = versions.clone();
         let e = dummy_versions(vec!["prefix-0.2.0"])?;
-        assert_eq!(a.select(Some("prefix-")), e);
+        assert_eq!(a.select(Some("prefix-"), &MultiPrefix::Error), e);
 
         let a = versions.clone();
         let e = dummy_versions(vec!["test-0.2.0"])?;
-        assert_eq!(a.select(Some("test-")), e);
+        assert_eq!(a.select(Some("test-"), &MultiPrefix::Error), e);
 
         let a = versions.clone();
         let e = dummy_versions(vec!["0.1.0"])?;
-        assert_eq!(a.select(None), e);
+        assert_eq!(a.select(None, &MultiPrefix::Error), e);
 
         let a = dummy_versions(vec!["v0.2.0", "prefix-0.2.0", "test-0.2.0"])?;
         let e = dummy_versions(vec!["v0.2.0"])?;
-        assert_eq!(a.select(None), e);
+        assert_eq!(a.select(None, &MultiPrefix::Error), e);
 
         let a = dummy_versions(vec!["prefix-0.2.0", "test-0.2.0"])?;
         let e = dummy_versions(vec!["prefix-0.2.0", "test-0.2.0"])?;
-        assert_eq!(a.select(None), e);
+        assert_eq!(a.select(None, &MultiPrefix::Error), e);
+
+        Ok(())
+    }
+
+    #[test]
+    fn select_multi_prefix_ok() -> Result<()> {
+        let versions = dummy_versions(vec!["0.1.0", "v0.2.0", "prefix-0.2.0", "test-0.2.0"])?;
+
+        let a = versions.clone();
+        let e = dummy_versions(vec!["0.1.0"])?;
+        assert_eq!(a.select(None, &MultiPrefix::PreferEmpty), e);
+
+        let a = versions.clone();
+        let e = dummy_versions(vec!["v0.2.0"])?;
+        assert_eq!(a.select(None, &MultiPrefix::PreferV), e);
+
+        let a = versions;
+        let e = dummy_versions(vec!["0.1.0"])?;
+        assert_eq!(a.select(None, &MultiPrefix::First), e);
+
+        let a = dummy_versions(vec!["test-0.2.0", "prefix-0.2.0"])?;
+        let e = dummy_versions(vec!["prefix-0.2.0"])?;
+        assert_eq!(a.select(None, &MultiPrefix::PreferV), e);
 
+        assert_eq!(
+            MultiPrefix::from_str("prefer-empty")?,
+            MultiPrefix::PreferEmpty
+        );
         Ok(())
     }
 }
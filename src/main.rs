@@ -3,34 +3,406 @@ extern crate strum_macros;
 
 mod args;
 mod changelog;
+mod config;
+mod error;
 mod git;
 
 use crate::args::Args;
 use log::*;
 
-use crate::changelog::{Changelog, Config};
+use crate::changelog::{AuthorLinkStyle, Changelog, Chronology, Config, Format};
+use crate::config::FileConfig;
+use crate::error::ExitError;
+use crate::git::ScanConfig;
 use anyhow::*;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use similar::TextDiff;
+use std::collections::{HashMap, HashSet};
 use std::env;
-use std::process::exit;
+use std::fs;
+use std::io::{ErrorKind, Write};
+use std::iter;
+use std::path::Path;
+use std::process::{exit, Command, Stdio};
+use std::thread;
 
 fn run(args: Vec<String>) -> Result<String> {
     let args = Args::new(&args)?;
     debug!("args: {:?}", args);
 
-    let repo = git::repo(&args.path)?;
-    let commits = git::commits(&repo, args.revspec(), args.tag_prefix.as_deref())?;
+    let output = if args.repos.is_empty() {
+        changelog(&args, &args.path)?
+    } else {
+        if args.porcelain || args.format != Format::Markdown {
+            bail!("Multiple repositories are only supported in the markdown format")
+        }
+        iter::once(&args.path)
+            .chain(args.repos.iter())
+            .map(|path| {
+                // The missing repository is reported by the git error
+                let changelog = changelog(&args, path)?;
+                Ok(format!("# {}\n\n{}", repo_name(path)?, changelog))
+            })
+            .collect::<Result<Vec<String>>>()?
+            .join("\n")
+    };
+
+    if let Some(path) = args.manifest.as_ref() {
+        if !args.repos.is_empty() {
+            bail!("The manifest is only supported for a single repository")
+        }
+        write_manifest(path, &output, &range(&args, &args.path)?)?;
+    }
+
+    if let Some(path) = args.prepend.as_ref() {
+        if !args.repos.is_empty() || args.porcelain || args.format != Format::Markdown {
+            bail!("--prepend is only supported in the markdown format for a single repository")
+        }
+        return prepend(path, &output);
+    }
+
+    match args.check.as_ref() {
+        Some(path) => check(path, &output),
+        None => Ok(output),
+    }
+}
+
+fn scan_config(args: &Args) -> ScanConfig {
+    ScanConfig {
+        tag_prefix: args.tag_prefix.clone(),
+        tag_pattern: args.tag_pattern.clone(),
+        on_multiple_prefixes: args.on_multiple_prefixes.clone(),
+        skip_prerelease_previous: args.skip_prerelease_previous,
+        header_pattern: args.header_pattern.clone(),
+        now: args.now,
+        stats: args.show_stats,
+        identity: args.identity.clone(),
+        path_filter: args.path_filter.clone(),
+        as_version: args.as_version.clone(),
+        include_prereleases: !args.exclude_prereleases,
+        limit: args.limit,
+        all: args.all,
+        mailmap: args.mailmap,
+        since: args.since,
+        until: args.until,
+        type_aliases: args.type_aliases.iter().cloned().collect(),
+        strip_leading_emoji: args.strip_leading_emoji,
+        verify_release_commits: args.verify_release_commit_only,
+    }
+}
 
+fn changelog(args: &Args, path: &str) -> Result<String> {
+    let repo = git::repo(path)?;
+    let scan_config = scan_config(args);
+    if args.list_versions {
+        let versions = git::versions(&repo, &scan_config)?;
+        return match args.format {
+            Format::Json => Ok(format!("{}\n", serde_json::to_string_pretty(&versions)?)),
+            _ => Ok(versions
+                .iter()
+                .map(|v| format!("{}\n", v.version))
+                .collect()),
+        };
+    }
+
+    let commits = git::commits(&repo, args.revspec(), &scan_config)?;
+    let url = git::gurl(&repo, args.forge.as_ref(), &args.url_templates());
+
+    if args.compare_url_only {
+        let url = url.context("Not found remote url")?;
+        let (start, end) = commits.range(args.tag_prefix.as_deref());
+        return Ok(format!("{}\n", url.compare(&start, end.as_ref())));
+    }
+
+    // The bare repositories have no config file
+    let file = match repo.workdir() {
+        Some(dir) => FileConfig::load(dir)?,
+        None => FileConfig::default(),
+    };
     let config = Config {
-        enable_email_link: args.enable_email_link,
-        reverse: args.reverse,
-        root_indent_level: args.root_indent_level,
-        ignore_summary: args.ignore_summary,
-        ignore_types: args.ignore_types,
+        author_link_style: match args.author_link_style.clone() {
+            Some(style) => style,
+            None if args.enable_email_link || file.enable_email_link.unwrap_or_default() => {
+                AuthorLinkStyle::Email
+            }
+            None => AuthorLinkStyle::None,
+        },
+        author_map: match args.author_map.as_ref() {
+            Some(path) => config::author_map(path)?,
+            None => HashMap::new(),
+        },
+        // `reverse` is kept as the alias of the oldest first
+        commit_chronology: match args.commit_chronology.clone() {
+            Some(c) => c,
+            None if args.reverse || file.reverse.unwrap_or_default() => Chronology::OldestFirst,
+            None => Chronology::NewestFirst,
+        },
+        root_indent_level: args
+            .root_indent_level
+            .or(file.root_indent_level)
+            .unwrap_or(Config::default().root_indent_level),
+        ignore_summary: match args.ignore_summary.clone() {
+            Some(r) => Some(r),
+            None => file.ignore_summary()?,
+        },
+        ignore_types: match args.ignore_types.clone() {
+            Some(t) => Some(t),
+            None => file.ignore_types()?,
+        },
+        include_types: args.include_types.clone(),
+        show_deprecations: args.show_deprecations,
+        empty_message: args.empty_message.clone(),
+        show_merge_parents: args.show_merge_parents,
+        show_tag_aliases: args.show_tag_aliases,
+        drop_unbounded_first: args.drop_unbounded_first,
+        drop_pre_first_tag: args.drop_pre_first_tag,
+        show_files_link: args.show_files_link,
+        revert_handling: args.revert_handling.clone(),
+        by_author: args.by_author,
+        dedupe: args.dedupe,
+        dedupe_normalize: args.dedupe_normalize,
+        unknown_author: args.unknown_author.clone(),
+        show_stats: args.show_stats,
+        group_by: args.group_by.clone(),
+        full_hash: args.full_hash,
+        hide_redundant_scope: args.hide_redundant_scope,
+        type_order: args.type_order.clone(),
+        link_references: args.link_references,
+        min_level: args.min_level.clone(),
+        type_titles: args.type_titles.iter().cloned().collect(),
+        show_scope: args.show_scope,
+        drop_empty_subjects: !args.keep_empty_subjects,
+        custom_type_order: args.custom_type_order.clone(),
+        only_summary: args.only_summary.clone(),
+        full_changelog_footer: args.full_changelog_footer,
+        link_issues: args.link_issues,
+        show_breaking_changes: args.show_breaking_changes,
+        show_tag_messages: args.show_tag_messages,
+        always_include_breaking: args.always_include_breaking,
+        timezone: args.timezone,
+        date_format: args.date_format.clone(),
+        stable_author_sort: args.stable_author_sort,
+        show_tag_signer: args.show_tag_signer,
+        verify_release_commit_only: args.verify_release_commit_only,
+        show_date: !args.hide_date,
+        type_only: args.type_only.clone(),
+        front_matter: if args.front_matter {
+            Some(args.front_matter_fields.clone())
+        } else {
+            None
+        },
+        scopeless_position: args.scopeless_position.clone(),
+        strip_tag_prefix_in_heading: args.strip_tag_prefix_in_heading,
+        include_body: args.include_body,
+        others_title: args.others_title.clone(),
+        hide_others: args.hide_others,
+        release_separator: args.release_separator.clone(),
+        show_contributor_count: args.show_contributor_count,
+        hidden_types: if args.show_all {
+            Vec::new()
+        } else {
+            Config::default().hidden_types
+        },
     };
     let changelog = Changelog::from(config);
-    let url = git::gurl(&repo);
-    let markdown = changelog.markdown(url.as_ref(), &commits, args.tag_prefix.as_deref())?;
-    Ok(markdown)
+    if args.strict {
+        changelog.strict(&commits)?;
+    }
+    let tag_prefix = args.tag_prefix.as_deref();
+    if let Some(path) = args.template.as_ref() {
+        let template = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read the template. path: {}", path.display()))?;
+        return changelog.template(url.as_ref(), &commits, tag_prefix, &template);
+    }
+
+    if let Some(cmd) = args.formatter_cmd.as_ref() {
+        let json = changelog.json(url.as_ref(), &commits, tag_prefix)?;
+        return format_with(cmd, &json);
+    }
+
+    let output = match (args.porcelain, &args.format) {
+        (true, _) => changelog.porcelain(&commits, tag_prefix),
+        (false, Format::Markdown) => changelog.markdown(url.as_ref(), &commits, tag_prefix)?,
+        (false, Format::Json) => changelog.json(url.as_ref(), &commits, tag_prefix)?,
+        (false, Format::Asciidoc) => changelog.asciidoc(url.as_ref(), &commits, tag_prefix)?,
+        (false, Format::Discord) => changelog.discord(url.as_ref(), &commits, tag_prefix)?,
+        (false, Format::Plain) => changelog.plain(&commits, tag_prefix),
+        (false, Format::NdjsonReleases) => {
+            changelog.ndjson_releases(url.as_ref(), &commits, tag_prefix)?
+        }
+    };
+    Ok(output)
+}
+
+// Pipes the json output to the command run by the shell, and returns its stdout
+fn format_with(cmd: &str, json: &str) -> Result<String> {
+    let mut child = Command::new("sh")
+        .arg("-c")
+        .arg(cmd)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("Failed to run the formatter. cmd: {}", cmd))?;
+    let mut stdin = child
+        .stdin
+        .take()
+        .context("Failed to open the stdin of the formatter")?;
+    // Written in another thread, or a full stdout pipe blocks both processes
+    let input = json.to_string();
+    let writer = thread::spawn(move || stdin.write_all(input.as_bytes()));
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        bail!("The formatter failed with {}. cmd: {}", output.status, cmd);
+    }
+    match writer.join() {
+        // The formatter may exit without reading all the input
+        Ok(Err(e)) if e.kind() != ErrorKind::BrokenPipe => {
+            return Err(e).context("Failed to write the json to the formatter")
+        }
+        Err(_) => bail!("Failed to write the json to the formatter"),
+        _ => {}
+    }
+    String::from_utf8(output.stdout).context("The formatter output isn't UTF-8")
+}
+
+// The directory name of the repository. ex) /src/ccclog => ccclog
+fn repo_name(path: &str) -> Result<String> {
+    let path = fs::canonicalize(path).context("Not found git repository path")?;
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    Ok(name)
+}
+
+#[derive(Serialize)]
+struct Manifest<'a> {
+    sha256: String,
+    range: &'a str,
+    version: &'a str,
+}
+
+// The range of the output. ex) 0.1.0...0.2.0
+fn range(args: &Args, path: &str) -> Result<String> {
+    let repo = git::repo(path)?;
+    let commits = git::commits(&repo, args.revspec(), &scan_config(args))?;
+    let (start, end) = commits.range(args.tag_prefix.as_deref());
+    let end = end.map_or_else(|| String::from("HEAD"), |e| e.name());
+    Ok(format!("{}...{}", start.name(), end))
+}
+
+// Records the hash of the output for the reproducibility audits
+fn write_manifest(path: &Path, output: &str, range: &str) -> Result<()> {
+    let manifest = Manifest {
+        sha256: format!("{:x}", Sha256::digest(output.as_bytes())),
+        range,
+        version: env!("CARGO_PKG_VERSION"),
+    };
+    let json = serde_json::to_string_pretty(&manifest)?;
+    fs::write(path, format!("{}\n", json))
+        .with_context(|| format!("Failed to write the manifest. path: {}", path.display()))
+}
+
+// Nothing is printed if the file is up to date, otherwise fails with the unified diff
+fn check(path: &Path, output: &str) -> Result<String> {
+    let current = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read the changelog. path: {}", path.display()))?;
+    if current == output {
+        return Ok(String::new());
+    }
+
+    let diff = TextDiff::from_lines(current.as_str(), output)
+        .unified_diff()
+        .header(&path.display().to_string(), "generated")
+        .to_string();
+    Err(Error::new(ExitError::OutOfDate(format!(
+        "{} is out of date\n{}",
+        path.display(),
+        diff
+    ))))
+}
+
+// Inserts the releases above the first release of the file and their links at the top of its link block.
+// The link labels already defined in the file are kept once
+fn prepend(path: &Path, output: &str) -> Result<String> {
+    let current = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read the changelog. path: {}", path.display()))?;
+    let (links, releases): (Vec<&str>, Vec<&str>) = output.lines().partition(|l| is_link(l));
+    let releases = releases.join("\n");
+    let releases = releases.trim_end();
+    // Same level as the generated headings. ex) ##
+    let level = releases.chars().take_while(|c| *c == '#').count();
+    if level == 0 {
+        bail!("Nothing to prepend, the output has no release heading")
+    }
+    let heading = format!("{} ", "#".repeat(level));
+
+    let mut lines = current.lines().collect::<Vec<&str>>();
+    if let Some(first) = releases.lines().next() {
+        if lines.contains(&first) {
+            bail!(
+                "The release is already in the changelog. heading: {}",
+                first
+            )
+        }
+    }
+    let mut labels = HashSet::new();
+    let links = links
+        .into_iter()
+        .filter(|l| link_label(l).is_some_and(|label| labels.insert(label)))
+        .collect::<Vec<&str>>();
+    // The generated links replace the stale ones. ex) [Unreleased]
+    lines.retain(|l| link_label(l).is_none_or(|label| !labels.contains(label)));
+
+    // The blank lines and the comments after the link block stay at the end
+    let tail_start = lines.len()
+        - lines
+            .iter()
+            .rev()
+            .take_while(|l| l.trim().is_empty() || is_comment(l))
+            .count();
+    let tail = lines.split_off(tail_start);
+    // The link block is the trailing link lines
+    let link_start = lines.len() - lines.iter().rev().take_while(|l| is_link(l)).count();
+    let mut seen = HashSet::new();
+    let block = lines
+        .split_off(link_start)
+        .into_iter()
+        .filter(|l| link_label(l).is_some_and(|label| seen.insert(label)))
+        .collect::<Vec<&str>>();
+    let at = lines
+        .iter()
+        .position(|l| l.starts_with(&heading))
+        .unwrap_or(link_start);
+    lines.splice(at..at, releases.lines().chain(iter::once("")));
+    if block.is_empty() && !links.is_empty() && lines.last() != Some(&"") {
+        lines.push("");
+    }
+    lines.extend(links.into_iter().chain(block).chain(tail));
+
+    let changelog = format!("{}\n", lines.join("\n").trim_end());
+    fs::write(path, changelog)
+        .with_context(|| format!("Failed to write the changelog. path: {}", path.display()))?;
+    Ok(String::new())
+}
+
+// ex) <!-- generated by ccclog -->
+fn is_comment(line: &str) -> bool {
+    let line = line.trim();
+    line.starts_with("<!--") && line.ends_with("-->")
+}
+
+// ex) [0.1.0]: https://github.com/watawuwu/ccclog/compare/0.0.0...0.1.0
+fn is_link(line: &str) -> bool {
+    link_label(line).is_some()
+}
+
+// ex) [0.1.0] of the link line
+fn link_label(line: &str) -> Option<&str> {
+    line.split_once("]: ")
+        .map(|(label, _)| label)
+        .filter(|label| label.starts_with('['))
 }
 
 fn main() {
@@ -43,7 +415,7 @@ fn main() {
         }
         Err(err) => {
             eprintln!("{:?}", err);
-            exitcode::USAGE
+            error::exit_code(&err)
         }
     };
     exit(code)
@@ -76,12 +448,812 @@ mod tests {
 ### Fix
 - [6f90482] fix build script (Test User)
 
+### Feature
+- [9cd3662] new fun (Test User)
+"#;
+        test_ok(args, expect)
+    }
+
+    #[test]
+    fn show_all_ok() -> Result<()> {
+        let dir = git_dir(1)?;
+        let dir = dir.to_str().context("Failed to change PathBuf to &str")?;
+
+        // chore and build are hidden by default
+        let args = vec![BIN, "--all", dir];
+        let expect = r#"## 0.2.0 - 2020-04-29
+### Fix
+- [6f90482] fix build script (Test User)
+
+### Feature
+- [9cd3662] new fun (Test User)
+
+## 0.1.0 - 2020-04-29
+### Feature
+- [75a1b96] add first files (Test User)
+"#;
+        test_ok(args, expect)?;
+
+        let args = vec![BIN, "--all", "--show-all", dir];
+        let expect = r#"## 0.2.0 - 2020-04-29
+### Fix
+- [6f90482] fix build script (Test User)
+
 ### Build
 - [a673434] add build script (Test User)
 
 ### Feature
 - [9cd3662] new fun (Test User)
+
+## 0.1.0 - 2020-04-29
+### Chore
+- [9fa3647] add README (Test User)
+
+### Feature
+- [75a1b96] add first files (Test User)
+"#;
+        test_ok(args, expect)?;
+
+        // The included types are shown
+        let args = vec![BIN, "--include-types", "chore", "--all", dir];
+        let expect = r#"## 0.2.0 - 2020-04-29
+
+## 0.1.0 - 2020-04-29
+### Chore
+- [9fa3647] add README (Test User)
 "#;
         test_ok(args, expect)
     }
+
+    #[test]
+    fn show_tag_aliases_ok() -> Result<()> {
+        let dir = git_dir(1)?;
+        let repo = git::repo(&dir)?;
+        let release = repo.revparse_single("0.2.0")?;
+        repo.tag_lightweight("stable", &release, false)?;
+        repo.tag_lightweight("latest", &release, false)?;
+        let dir = dir.to_str().context("Failed to change PathBuf to &str")?;
+        let args = vec![BIN, "--show-all", "--show-tag-aliases", dir];
+
+        let expect = r#"## 0.2.0 (latest, stable) - 2020-04-29
+### Fix
+- [6f90482] fix build script (Test User)
+
+### Build
+- [a673434] add build script (Test User)
+
+### Feature
+- [9cd3662] new fun (Test User)
+"#;
+        test_ok(args, expect)
+    }
+
+    #[test]
+    fn drop_unbounded_first_ok() -> Result<()> {
+        let dir = git_dir(5)?;
+        let dir = dir.to_str().context("Failed to change PathBuf to &str")?;
+
+        let args = vec![BIN, dir];
+        let expect = r#"## 0.1.0 - 2020-05-01
+### Feat
+- [eb8b838] add first (Test User)
+
+### Fix
+- [f4df5b9] fix first (Test User)
+"#;
+        test_ok(args, expect)?;
+
+        let args = vec![BIN, "--drop-unbounded-first", dir];
+        test_ok(args, "")
+    }
+
+    #[test]
+    fn compare_url_only_ok() -> Result<()> {
+        let dir = git_dir(1)?;
+        let repo = git::repo(&dir)?;
+        let dir = dir.to_str().context("Failed to change PathBuf to &str")?;
+
+        let args = vec![BIN, "--compare-url-only", dir];
+        assert!(run(args.into_iter().map(String::from).collect()).is_err());
+
+        repo.remote("origin", "git@github.com:watawuwu/ccclog.git")?;
+        let args = vec![BIN, "--compare-url-only", dir];
+        let expect = "https://github.com/watawuwu/ccclog/compare/0.1.0...0.2.0\n";
+        test_ok(args, expect)
+    }
+
+    #[test]
+    fn list_versions_ok() -> Result<()> {
+        let dir = git_dir(3)?;
+        let dir = dir.to_str().context("Failed to change PathBuf to &str")?;
+
+        let args = vec![BIN, "--list-versions", "--tag-prefix", "v", dir];
+        test_ok(args, "v0.1.0\nv0.2.0\nv0.3.0\n")?;
+
+        let args = vec![
+            BIN,
+            "--list-versions",
+            "--format",
+            "json",
+            "--tag-prefix",
+            "component-v",
+            dir,
+        ];
+        let expect = r#"[
+  {
+    "tag": "component-v0.1.0",
+    "prefix": "component-v",
+    "version": "0.1.0",
+    "date": "2020-05-21T21:54:00+09:00"
+  },
+  {
+    "tag": "component-v0.2.0",
+    "prefix": "component-v",
+    "version": "0.2.0",
+    "date": "2020-05-21T21:54:38+09:00"
+  }
+]
+"#;
+        test_ok(args, expect)
+    }
+
+    #[test]
+    fn local_remote_ok() -> Result<()> {
+        let dir = git_dir(1)?;
+        let repo = git::repo(&dir)?;
+        repo.remote("origin", "/srv/git/repo.git")?;
+        let dir = dir.to_str().context("Failed to change PathBuf to &str")?;
+        let args = vec![BIN, "--show-all", dir];
+
+        let expect = r#"## 0.2.0 - 2020-04-29
+### Fix
+- [6f90482] fix build script (Test User)
+
+### Build
+- [a673434] add build script (Test User)
+
+### Feature
+- [9cd3662] new fun (Test User)
+"#;
+        test_ok(args, expect)
+    }
+
+    #[test]
+    fn show_stats_ok() -> Result<()> {
+        let dir = git_dir(1)?;
+        let dir = dir.to_str().context("Failed to change PathBuf to &str")?;
+        let args = vec![BIN, "--show-all", "--show-stats", dir];
+
+        let expect = r#"## 0.2.0 - 2020-04-29
+### Fix
+- [6f90482] fix build script (Test User) (+1 -1, 1 file)
+
+### Build
+- [a673434] add build script (Test User) (+1 -0, 1 file)
+
+### Feature
+- [9cd3662] new fun (Test User) (+1 -0, 1 file)
+"#;
+        test_ok(args, expect)
+    }
+
+    #[test]
+    fn check_ok() -> Result<()> {
+        let dir = git_dir(1)?;
+        let dir = dir.to_str().context("Failed to change PathBuf to &str")?;
+        let tmp = tempfile::tempdir()?;
+        let path = tmp.path().join("CHANGELOG.md");
+        let file = path.to_str().context("Failed to change PathBuf to &str")?;
+
+        let markdown = run(vec![BIN.to_string(), dir.to_string()])?;
+        fs::write(&path, &markdown)?;
+        test_ok(vec![BIN, "--check", file, dir], "")?;
+
+        fs::write(&path, markdown.replace("new fun", "old fun"))?;
+        let args = vec![BIN, "--check", file, dir];
+        let err = run(args.into_iter().map(String::from).collect()).unwrap_err();
+        assert_eq!(error::exit_code(&err), 1);
+        let err = err.to_string();
+        assert!(err.contains("is out of date"));
+        assert!(err.contains("-- [9cd3662] old fun (Test User)"));
+        assert!(err.contains("+- [9cd3662] new fun (Test User)"));
+        Ok(())
+    }
+
+    #[test]
+    fn prepend_ok() -> Result<()> {
+        let dir = git_dir(1)?;
+        let repo = git::repo(&dir)?;
+        repo.remote("origin", "git@github.com:watawuwu/ccclog.git")?;
+        let dir = dir.to_str().context("Failed to change PathBuf to &str")?;
+        let tmp = tempfile::tempdir()?;
+        let path = tmp.path().join("CHANGELOG.md");
+        let file = path.to_str().context("Failed to change PathBuf to &str")?;
+
+        let current = r#"# Changelog
+
+## [0.1.0] - 2020-04-29
+### Chore
+- [[9fa3647]] add README (Test User)
+
+[0.1.0]: https://github.com/watawuwu/ccclog/compare/4b825dc...0.1.0
+[9fa3647]: https://github.com/watawuwu/ccclog/commit/9fa3647a24bc6a1e8ec9e1e7ed4b9ba4cb1ba6a1
+"#;
+        fs::write(&path, current)?;
+        test_ok(vec![BIN, "--show-all", "--prepend", file, dir], "")?;
+        let expect = r#"# Changelog
+
+## [0.2.0] - 2020-04-29
+### Fix
+- [[6f90482]] fix build script (Test User)
+
+### Build
+- [[a673434]] add build script (Test User)
+
+### Feature
+- [[9cd3662]] new fun (Test User)
+
+## [0.1.0] - 2020-04-29
+### Chore
+- [[9fa3647]] add README (Test User)
+
+[0.2.0]: https://github.com/watawuwu/ccclog/compare/0.1.0...0.2.0
+[6f90482]: https://github.com/watawuwu/ccclog/commit/6f904822757b9d40ba885d946f9e78a7b5b63ddf
+[a673434]: https://github.com/watawuwu/ccclog/commit/a673434d9fa4efc63c7026a426a36841b247f446
+[9cd3662]: https://github.com/watawuwu/ccclog/commit/9cd36629bddcf2ce9cfc16fcfbd9ea48815b2dc8
+[0.1.0]: https://github.com/watawuwu/ccclog/compare/4b825dc...0.1.0
+[9fa3647]: https://github.com/watawuwu/ccclog/commit/9fa3647a24bc6a1e8ec9e1e7ed4b9ba4cb1ba6a1
+"#;
+        assert_eq!(fs::read_to_string(&path)?, expect);
+
+        // The latest release isn't added twice
+        let args = vec![BIN, "--prepend", file, dir];
+        assert!(run(args.into_iter().map(String::from).collect()).is_err());
+        assert_eq!(fs::read_to_string(&path)?, expect);
+        Ok(())
+    }
+
+    #[test]
+    fn prepend_dedupe_links_ok() -> Result<()> {
+        let dir = git_dir(1)?;
+        let repo = git::repo(&dir)?;
+        repo.remote("origin", "git@github.com:watawuwu/ccclog.git")?;
+        let dir = dir.to_str().context("Failed to change PathBuf to &str")?;
+        let tmp = tempfile::tempdir()?;
+        let path = tmp.path().join("CHANGELOG.md");
+        let file = path.to_str().context("Failed to change PathBuf to &str")?;
+
+        // The stale link and the accumulated duplicate
+        let current = r#"## [0.1.0] - 2020-04-29
+### Chore
+- [[9fa3647]] add README (Test User)
+
+[9cd3662]: https://github.com/watawuwu/ccclog/commit/9cd3662
+[0.1.0]: https://github.com/watawuwu/ccclog/compare/4b825dc...0.1.0
+[0.1.0]: https://github.com/watawuwu/ccclog/compare/4b825dc...0.1.0
+"#;
+        fs::write(&path, current)?;
+        test_ok(vec![BIN, "--prepend", file, dir], "")?;
+        let expect = r#"## [0.2.0] - 2020-04-29
+### Fix
+- [[6f90482]] fix build script (Test User)
+
+### Feature
+- [[9cd3662]] new fun (Test User)
+
+## [0.1.0] - 2020-04-29
+### Chore
+- [[9fa3647]] add README (Test User)
+
+[0.2.0]: https://github.com/watawuwu/ccclog/compare/0.1.0...0.2.0
+[6f90482]: https://github.com/watawuwu/ccclog/commit/6f904822757b9d40ba885d946f9e78a7b5b63ddf
+[9cd3662]: https://github.com/watawuwu/ccclog/commit/9cd36629bddcf2ce9cfc16fcfbd9ea48815b2dc8
+[0.1.0]: https://github.com/watawuwu/ccclog/compare/4b825dc...0.1.0
+"#;
+        assert_eq!(fs::read_to_string(&path)?, expect);
+        Ok(())
+    }
+
+    #[test]
+    fn prepend_footer_comment_ok() -> Result<()> {
+        let dir = git_dir(1)?;
+        let repo = git::repo(&dir)?;
+        repo.remote("origin", "git@github.com:watawuwu/ccclog.git")?;
+        let dir = dir.to_str().context("Failed to change PathBuf to &str")?;
+        let tmp = tempfile::tempdir()?;
+        let path = tmp.path().join("CHANGELOG.md");
+        let file = path.to_str().context("Failed to change PathBuf to &str")?;
+
+        let current = r#"## [0.1.0] - 2020-04-29
+### Chore
+- [[9fa3647]] add README (Test User)
+
+[0.1.0]: https://github.com/watawuwu/ccclog/compare/4b825dc...0.1.0
+
+<!-- generated by ccclog -->
+
+"#;
+        fs::write(&path, current)?;
+        test_ok(vec![BIN, "--prepend", file, dir], "")?;
+        let expect = r#"## [0.2.0] - 2020-04-29
+### Fix
+- [[6f90482]] fix build script (Test User)
+
+### Feature
+- [[9cd3662]] new fun (Test User)
+
+## [0.1.0] - 2020-04-29
+### Chore
+- [[9fa3647]] add README (Test User)
+
+[0.2.0]: https://github.com/watawuwu/ccclog/compare/0.1.0...0.2.0
+[6f90482]: https://github.com/watawuwu/ccclog/commit/6f904822757b9d40ba885d946f9e78a7b5b63ddf
+[9cd3662]: https://github.com/watawuwu/ccclog/commit/9cd36629bddcf2ce9cfc16fcfbd9ea48815b2dc8
+[0.1.0]: https://github.com/watawuwu/ccclog/compare/4b825dc...0.1.0
+
+<!-- generated by ccclog -->
+"#;
+        assert_eq!(fs::read_to_string(&path)?, expect);
+
+        // No release in the range
+        let args = vec![BIN, "--prepend", file, "--since", "2020-04-30", dir];
+        let err = run(args.into_iter().map(String::from).collect()).unwrap_err();
+        assert!(err.to_string().contains("Nothing to prepend"));
+        assert_eq!(fs::read_to_string(&path)?, expect);
+        Ok(())
+    }
+
+    #[test]
+    fn prepend_unreleased_link_ok() -> Result<()> {
+        let dir = git_dir(3)?;
+        let repo = git::repo(&dir)?;
+        repo.remote("origin", "git@github.com:watawuwu/ccclog.git")?;
+        let dir = dir.to_str().context("Failed to change PathBuf to &str")?;
+        let tmp = tempfile::tempdir()?;
+        let path = tmp.path().join("CHANGELOG.md");
+        let file = path.to_str().context("Failed to change PathBuf to &str")?;
+
+        let current = r#"## [v0.2.0] - 2020-04-29
+
+[Unreleased]: https://github.com/watawuwu/ccclog/compare/v0.2.0...HEAD
+[v0.2.0]: https://github.com/watawuwu/ccclog/compare/v0.1.0...v0.2.0
+"#;
+        fs::write(&path, current)?;
+        let args = vec![
+            BIN,
+            "--prepend",
+            file,
+            "--tag-prefix",
+            "v",
+            dir,
+            "v0.2.0..HEAD",
+        ];
+        test_ok(args, "")?;
+        let changelog = fs::read_to_string(&path)?;
+        let links = changelog
+            .lines()
+            .filter(|l| l.contains("/compare/"))
+            .collect::<Vec<&str>>();
+        assert_eq!(
+            links,
+            vec![
+                "[Unreleased]: https://github.com/watawuwu/ccclog/compare/v0.3.0...HEAD",
+                "[v0.3.0]: https://github.com/watawuwu/ccclog/compare/v0.2.0...v0.3.0",
+                "[v0.2.0]: https://github.com/watawuwu/ccclog/compare/v0.1.0...v0.2.0",
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn manifest_ok() -> Result<()> {
+        let dir = git_dir(1)?;
+        let dir = dir.to_str().context("Failed to change PathBuf to &str")?;
+        let tmp = tempfile::tempdir()?;
+        let path = tmp.path().join("manifest.json");
+        let file = path.to_str().context("Failed to change PathBuf to &str")?;
+
+        let args = vec![BIN, "--manifest", file, dir];
+        let output = run(args.into_iter().map(String::from).collect())?;
+        let manifest: serde_json::Value = serde_json::from_str(&fs::read_to_string(&path)?)?;
+        assert_eq!(
+            manifest["sha256"],
+            format!("{:x}", Sha256::digest(output.as_bytes()))
+        );
+        assert_eq!(manifest["range"], "0.1.0...0.2.0");
+        assert_eq!(manifest["version"], env!("CARGO_PKG_VERSION"));
+        Ok(())
+    }
+
+    #[test]
+    fn config_file_ok() -> Result<()> {
+        let dir = git_dir(1)?;
+        let content = "root_indent_level = 3\nignore_types = [\"build\"]\n";
+        fs::write(dir.join(".ccclog.toml"), content)?;
+        let git_dir = dir.join(".git");
+        let git_dir = git_dir
+            .to_str()
+            .context("Failed to change PathBuf to &str")?;
+        let dir = dir.to_str().context("Failed to change PathBuf to &str")?;
+
+        let expect = r#"### 0.2.0 - 2020-04-29
+#### Fix
+- [6f90482] fix build script (Test User)
+
+#### Feature
+- [9cd3662] new fun (Test User)
+"#;
+        test_ok(vec![BIN, dir], expect)?;
+        // Read from the root of the repository
+        test_ok(vec![BIN, git_dir], expect)?;
+
+        // The flags take precedence over the file
+        let expect = r#"## 0.2.0 - 2020-04-29
+### Fix
+- [6f90482] fix build script (Test User)
+
+### Feature
+- [9cd3662] new fun (Test User)
+"#;
+        test_ok(vec![BIN, "-i", "2", dir], expect)
+    }
+
+    #[test]
+    fn show_tag_messages_ok() -> Result<()> {
+        let dir = git_dir(2)?;
+        let dir = dir.to_str().context("Failed to change PathBuf to &str")?;
+        let markdown = run(vec![BIN.to_string(), dir.to_string()])?;
+        assert!(!markdown.contains("Create annoted tag"));
+
+        let args = vec![BIN, "--show-all", "--show-tag-messages", dir];
+        let expect = r#"## v0.2.0 - 2020-05-18
+
+Create annoted tag 0.2.0
+
+### Fix
+- [de763ce] fix2 (Wataru Matsui)
+- [f42dc20] fix1 (Wataru Matsui)
+
+### Build
+- [31e6d1f] build1 (Wataru Matsui)
+
+### Doc
+- [7210553] doc2 (Wataru Matsui)
+- [228b4fe] doc1 (Wataru Matsui)
+
+### Chore
+- [c3b3ffb] chore1 (Wataru Matsui)
+
+### CI
+- [e72700c] ci1 (Wataru Matsui)
+
+### Perf
+- [8044b14] perf1 (Wataru Matsui)
+"#;
+        test_ok(args, expect)
+    }
+
+    #[test]
+    fn show_tag_signer_ok() -> Result<()> {
+        let dir = git_dir(6)?;
+        let dir = dir.to_str().context("Failed to change PathBuf to &str")?;
+        let args = vec![BIN, "--show-tag-signer", dir];
+        let expect = r#"## 0.2.0 - 2020-06-02
+### Fix
+- [8f9eaf7] fix first (Test User)
+"#;
+        test_ok(args, expect)?;
+
+        let args = vec![BIN, "--show-tag-signer", dir, "..0.1.0"];
+        let expect = r#"## 0.1.0 - 2020-06-01 (signed by Test User)
+### Feat
+- [3157d58] add first (Test User)
+"#;
+        test_ok(args, expect)
+    }
+
+    #[test]
+    fn range_split_by_tags_ok() -> Result<()> {
+        let dir = git_dir(3)?;
+        let dir = dir.to_str().context("Failed to change PathBuf to &str")?;
+
+        // The intermediate tags split the given range the same as the detected range
+        let args = vec![BIN, "--tag-prefix", "v", dir, "v0.1.0..v0.3.0"];
+        let expect = r#"## v0.3.0 - 2020-05-21
+### Feat
+- [52d8dcc] 5 (Test User)
+- [642f513] 4 (Test User)
+
+## v0.2.0 - 2020-05-21
+### Feat
+- [21d28aa] 3 (Test User)
+"#;
+        test_ok(args, expect)
+    }
+
+    #[test]
+    fn limit_ok() -> Result<()> {
+        let dir = git_dir(3)?;
+        let dir = dir.to_str().context("Failed to change PathBuf to &str")?;
+
+        let args = vec![BIN, "--tag-prefix", "v", "--limit", "2", dir];
+        let expect = r#"## v0.3.0 - 2020-05-21
+### Feat
+- [52d8dcc] 5 (Test User)
+- [642f513] 4 (Test User)
+
+## v0.2.0 - 2020-05-21
+### Feat
+- [21d28aa] 3 (Test User)
+"#;
+        test_ok(args, expect)?;
+
+        // All the releases when the limit is over the number of the tags
+        let args = vec![BIN, "--tag-prefix", "v", "--limit", "10", dir];
+        let expect = format!(
+            "{}{}",
+            expect,
+            r#"
+## v0.1.0 - 2020-05-21
+### Feat
+- [d54d1e1] 2 (Test User)
+- [310fd78] 1 (Test User)
+"#
+        );
+        test_ok(args, &expect)
+    }
+
+    #[test]
+    fn all_ok() -> Result<()> {
+        let dir = git_dir(1)?;
+        let repo = git::repo(&dir)?;
+        let dir = dir.to_str().context("Failed to change PathBuf to &str")?;
+
+        let args = vec![BIN, "--show-all", "--all", dir];
+        let expect = r#"## 0.2.0 - 2020-04-29
+### Fix
+- [6f90482] fix build script (Test User)
+
+### Build
+- [a673434] add build script (Test User)
+
+### Feature
+- [9cd3662] new fun (Test User)
+
+## 0.1.0 - 2020-04-29
+### Chore
+- [9fa3647] add README (Test User)
+
+### Feature
+- [75a1b96] add first files (Test User)
+"#;
+        test_ok(args, expect)?;
+
+        repo.remote("origin", "git@github.com:watawuwu/ccclog.git")?;
+        let args = vec![BIN, "--all", dir];
+        let actual = run(args.into_iter().map(String::from).collect())?;
+        assert!(actual
+            .contains("[0.1.0]: https://github.com/watawuwu/ccclog/compare/4b825dc...0.1.0\n"));
+
+        let args = vec![BIN, "--all", dir, "0.1.0..HEAD"];
+        assert!(run(args.into_iter().map(String::from).collect()).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn prefixed_compare_ok() -> Result<()> {
+        let dir = git_dir(3)?;
+        let repo = git::repo(&dir)?;
+        repo.remote("origin", "git@github.com:watawuwu/ccclog.git")?;
+        let dir = dir.to_str().context("Failed to change PathBuf to &str")?;
+        let compare_links = |args: Vec<&str>| -> Result<String> {
+            let actual = run(args.into_iter().map(String::from).collect())?;
+            Ok(actual
+                .lines()
+                .filter(|l| l.contains("/compare/"))
+                .map(|l| format!("{}\n", l))
+                .collect())
+        };
+
+        // The first release is compared with the empty tree
+        let args = vec![BIN, "--all", "--tag-prefix", "v", dir];
+        let expect = r#"[Unreleased]: https://github.com/watawuwu/ccclog/compare/v0.3.0...HEAD
+[v0.3.0]: https://github.com/watawuwu/ccclog/compare/v0.2.0...v0.3.0
+[v0.2.0]: https://github.com/watawuwu/ccclog/compare/v0.1.0...v0.2.0
+[v0.1.0]: https://github.com/watawuwu/ccclog/compare/4b825dc...v0.1.0
+"#;
+        assert_eq!(compare_links(args)?, expect);
+
+        // The links keep the tag names with the stripped headings
+        let args = vec![
+            BIN,
+            "--tag-prefix",
+            "component-v",
+            "--strip-tag-prefix-in-heading",
+            dir,
+            "component-v0.1.0..HEAD",
+        ];
+        let expect = r#"[Unreleased]: https://github.com/watawuwu/ccclog/compare/component-v0.2.0...HEAD
+[0.2.0]: https://github.com/watawuwu/ccclog/compare/component-v0.1.0...component-v0.2.0
+"#;
+        assert_eq!(compare_links(args)?, expect);
+        Ok(())
+    }
+
+    #[test]
+    fn drop_pre_first_tag_ok() -> Result<()> {
+        let dir = git_dir(1)?;
+        let dir = dir.to_str().context("Failed to change PathBuf to &str")?;
+
+        let args = vec![BIN, "--show-all", "--all", "--drop-pre-first-tag", dir];
+        let expect = r#"## 0.2.0 - 2020-04-29
+### Fix
+- [6f90482] fix build script (Test User)
+
+### Build
+- [a673434] add build script (Test User)
+
+### Feature
+- [9cd3662] new fun (Test User)
+
+## 0.1.0 - 2020-04-29
+### Chore
+- [9fa3647] add README (Test User)
+"#;
+        test_ok(args, expect)?;
+
+        // The range from the first tag has nothing to drop
+        let args = vec![BIN, "--drop-pre-first-tag", dir];
+        let expect = run(vec![BIN.to_string(), dir.to_string()])?;
+        test_ok(args, &expect)
+    }
+
+    #[test]
+    fn since_until_ok() -> Result<()> {
+        let dir = git_dir(1)?;
+        let dir = dir.to_str().context("Failed to change PathBuf to &str")?;
+
+        let args = vec![
+            BIN,
+            "--show-all",
+            "--since",
+            "2020-04-29T16:29:47+09:00",
+            "--until",
+            "2020-04-29T16:31:11+09:00",
+            dir,
+        ];
+        let expect = r#"## 0.2.0 - 2020-04-29
+### Build
+- [a673434] add build script (Test User)
+
+## 0.1.0 - 2020-04-29
+### Chore
+- [9fa3647] add README (Test User)
+"#;
+        test_ok(args, expect)?;
+
+        test_ok(vec![BIN, "--since", "2020-04-30", dir], "")?;
+        let all = run(vec![BIN.to_string(), "--all".to_string(), dir.to_string()])?;
+        test_ok(vec![BIN, "--until", "2020-04-29", dir], &all)?;
+
+        let args = vec![BIN, "--since", "2020-04-30", "--until", "2020-04-29", dir];
+        assert!(run(args.into_iter().map(String::from).collect()).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn as_version_ok() -> Result<()> {
+        let dir = git_dir(5)?;
+        let repo = git::repo(&dir)?;
+        let dir = dir.to_str().context("Failed to change PathBuf to &str")?;
+        repo.remote("origin", "git@github.com:watawuwu/ccclog.git")?;
+
+        let now = "2020-06-01T00:00:00Z";
+        let args = vec![
+            BIN,
+            "--as-version",
+            "1.0.0",
+            "--now",
+            now,
+            dir,
+            "0.1.0..HEAD",
+        ];
+        let expect = r#"## [1.0.0] - 2020-06-01
+### Feat
+- [[b10aa2f]] add second (Test User)
+
+[1.0.0]: https://github.com/watawuwu/ccclog/compare/0.1.0...1.0.0
+[b10aa2f]: https://github.com/watawuwu/ccclog/commit/b10aa2f87ec73d6ec5f71041e393b7534420cfcc
+"#;
+        test_ok(args, expect)?;
+
+        let args = vec![BIN, "--as-version", "next", dir];
+        assert!(run(args.into_iter().map(String::from).collect()).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn formatter_cmd_ok() -> Result<()> {
+        let dir = git_dir(1)?;
+        let dir = dir.to_str().context("Failed to change PathBuf to &str")?;
+
+        let json = run(vec![
+            BIN.to_string(),
+            "--format".to_string(),
+            "json".to_string(),
+            dir.to_string(),
+        ])?;
+        test_ok(vec![BIN, "--formatter-cmd", "cat", dir], &json)?;
+
+        let args = vec![BIN, "--formatter-cmd", "cat > /dev/null; exit 3", dir];
+        let err = run(args.into_iter().map(String::from).collect()).unwrap_err();
+        assert!(err.to_string().contains("The formatter failed"));
+        Ok(())
+    }
+
+    #[test]
+    fn formatter_cmd_large_input_ok() -> Result<()> {
+        // Larger than the pipe buffers of stdin and stdout
+        let json = format!("[{}]", "\"x\",".repeat(1 << 18));
+        assert_eq!(format_with("cat", &json)?, json);
+        Ok(())
+    }
+
+    #[test]
+    fn multi_repo_ok() -> Result<()> {
+        let dir1 = git_dir(1)?;
+        let dir1 = dir1.to_str().context("Failed to change PathBuf to &str")?;
+        let dir5 = git_dir(5)?;
+        let dir5 = dir5.to_str().context("Failed to change PathBuf to &str")?;
+        let args = vec![BIN, "--show-all", dir1, "--", dir5];
+
+        let expect = r#"# git-data1
+
+## 0.2.0 - 2020-04-29
+### Fix
+- [6f90482] fix build script (Test User)
+
+### Build
+- [a673434] add build script (Test User)
+
+### Feature
+- [9cd3662] new fun (Test User)
+
+# git-data5
+
+## 0.1.0 - 2020-05-01
+### Feat
+- [eb8b838] add first (Test User)
+
+### Fix
+- [f4df5b9] fix first (Test User)
+"#;
+        test_ok(args, expect)?;
+
+        let args = vec![BIN, "--format", "json", dir1, "--", dir5];
+        assert!(run(args.into_iter().map(String::from).collect()).is_err());
+
+        // The revision spec is applied to all the repositories
+        let args = vec![BIN, "--show-all", dir1, "0.1.0..HEAD", "--", dir5];
+        let expect = r#"# git-data1
+
+## 0.2.0 - 2020-04-29
+### Fix
+- [6f90482] fix build script (Test User)
+
+### Build
+- [a673434] add build script (Test User)
+
+### Feature
+- [9cd3662] new fun (Test User)
+
+# git-data5
+
+## Unreleased
+### Feat
+- [b10aa2f] add second (Test User)
+"#;
+        test_ok(args, expect)?;
+        Ok(())
+    }
 }
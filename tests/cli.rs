@@ -48,11 +48,26 @@ fn not_found_git_repo_err() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn file_path_err() -> Result<()> {
+    let mut cmd = cmd()?;
+    let tmp_dir = tempdir()?;
+    let path = tmp_dir.path().join("CHANGELOG.md");
+    std::fs::write(&path, "")?;
+
+    cmd.arg(path.to_str().unwrap());
+    cmd.assert()
+        .failure()
+        .code(exitcode::USAGE)
+        .stderr(predicate::str::contains("Path is not a directory"));
+    Ok(())
+}
+
 #[test]
 fn auto_detect_range_ok() -> Result<()> {
     let mut cmd = cmd()?;
     let dir = git_dir()?;
-    cmd.args(&[dir.to_str().unwrap()]);
+    cmd.args(["--show-all", dir.to_str().unwrap()]);
     cmd.assert().success().code(exitcode::OK).stdout(
         r#"## 0.2.0 - 2020-04-29
 ### Fix
@@ -69,11 +84,29 @@ fn auto_detect_range_ok() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn hidden_types_default_ok() -> Result<()> {
+    let mut cmd = cmd()?;
+    let dir = git_dir()?;
+    cmd.args([dir.to_str().unwrap()]);
+    cmd.assert().success().code(exitcode::OK).stdout(
+        r#"## 0.2.0 - 2020-04-29
+### Fix
+- [6f90482] fix build script (Test User)
+
+### Feature
+- [9cd3662] new fun (Test User)
+"#,
+    );
+
+    Ok(())
+}
+
 #[test]
 fn parse_range_ok() -> Result<()> {
     let mut cmd = cmd()?;
     let dir = git_dir()?;
-    cmd.args(&[dir.to_str().unwrap(), "..0.1.0"]);
+    cmd.args(["--show-all", dir.to_str().unwrap(), "..0.1.0"]);
     cmd.assert().success().code(exitcode::OK).stdout(
         r#"## 0.1.0 - 2020-04-29
 ### Chore
@@ -91,18 +124,92 @@ fn parse_range_ok() -> Result<()> {
 fn invalid_spec_ng() -> Result<()> {
     let mut cmd = cmd()?;
     let dir = git_dir()?;
-    cmd.args(&[dir.to_str().unwrap(), "0.1.0"]);
+    cmd.args([dir.to_str().unwrap(), "0.1.0"]);
     cmd.assert()
         .failure()
-        .code(exitcode::USAGE)
+        .code(exitcode::DATAERR)
         .stderr(predicate::str::contains("Don't support mode."));
     Ok(())
 }
 
+#[test]
+fn invalid_revspec_ng() -> Result<()> {
+    let mut cmd = cmd()?;
+    let dir = git_dir()?;
+    cmd.args([dir.to_str().unwrap(), "0.3.0..0.2.0"]);
+    cmd.assert()
+        .failure()
+        .code(exitcode::DATAERR)
+        .stderr(predicate::str::contains("Invalid revspec"));
+    Ok(())
+}
+
+#[test]
+fn check_out_of_date_ng() -> Result<()> {
+    let mut cmd = cmd()?;
+    let dir = git_dir()?;
+    let path = dir.join("CHANGELOG.md");
+    std::fs::write(&path, "## 0.2.0 - 2020-04-29\n")?;
+
+    cmd.args(["--check", path.to_str().unwrap(), dir.to_str().unwrap()]);
+    cmd.assert()
+        .failure()
+        .code(1)
+        .stdout("")
+        .stderr(predicate::str::contains("+### Fix"));
+    Ok(())
+}
+
+#[test]
+fn not_exist_path_err() -> Result<()> {
+    let mut cmd = cmd()?;
+    let path = tempdir()?.path().join("not-exist");
+
+    cmd.arg(path.to_str().unwrap());
+    cmd.assert()
+        .failure()
+        .code(exitcode::NOINPUT)
+        .stderr(predicate::str::contains("Not found git repository path"));
+    Ok(())
+}
+
+#[test]
+fn not_exist_repos_err() -> Result<()> {
+    let mut cmd = cmd()?;
+    let dir = git_dir()?;
+    let path = tempdir()?.path().join("not-exist");
+
+    cmd.args([dir.to_str().unwrap(), "--", path.to_str().unwrap()]);
+    cmd.assert()
+        .failure()
+        .code(exitcode::NOINPUT)
+        .stderr(predicate::str::contains("Not found git repository path"));
+    Ok(())
+}
+
+#[test]
+fn empty_range_ok() -> Result<()> {
+    let mut cmd = cmd()?;
+    let dir = git_dir()?;
+    cmd.args([dir.to_str().unwrap(), "0.2.0..0.2.0"]);
+    cmd.assert().success().code(exitcode::OK).stdout("");
+    Ok(())
+}
+
+#[test]
+fn empty_repo_ok() -> Result<()> {
+    let mut cmd = cmd()?;
+    let tmp_dir = tempdir()?;
+    git2::Repository::init(tmp_dir.path())?;
+    cmd.arg(tmp_dir.path().to_str().unwrap());
+    cmd.assert().success().code(exitcode::OK).stdout("");
+    Ok(())
+}
+
 #[test]
 fn invalid_option_ng() -> Result<()> {
     let mut cmd = cmd()?;
-    cmd.args(&["--unknown"]);
+    cmd.args(["--unknown"]);
     cmd.assert()
         .failure()
         .code(exitcode::USAGE)